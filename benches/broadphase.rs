@@ -0,0 +1,86 @@
+//! Compares broadphase pair counts and timings for increasing numbers of particles.
+//! Run with `cargo bench --bench broadphase`.
+// We prefer explicit returns throughout this crate
+#![allow(clippy::needless_return)]
+
+use particle_sim::broadphase::{BoundingBox, BroadphaseType};
+use particle_sim::xyz::XYZ;
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+const PARTICLE_RADIUS: f32 = 10.0;
+// Screen area per particle, in square pixels. This keeps the density constant as the particle
+// count grows, roughly like a full screen of 2550 particles.
+const AREA_PER_PARTICLE: f32 = 1080.0 * 720.0 / 2550.0;
+// Testing every pair of 100k particles takes minutes, and tells us nothing new
+const BRUTE_FORCE_MAX_PARTICLES: usize = 10_000;
+const REPETITIONS: u32 = 5;
+
+/// A small deterministic pseudo-random number generator, so that runs are comparable
+fn next_pseudo_random(state: &mut u64) -> f32 {
+    *state = state
+        .wrapping_mul(6364136223846793005)
+        .wrapping_add(1442695040888963407);
+    // Scale the top bits to [0, 1)
+    return (*state >> 40) as f32 / (1u64 << 24) as f32;
+}
+
+fn particle_bounds(count: usize) -> Vec<BoundingBox> {
+    let mut state = 2024;
+    let side = f32::sqrt(AREA_PER_PARTICLE * count as f32);
+    let extent = XYZ {
+        x: PARTICLE_RADIUS,
+        y: PARTICLE_RADIUS,
+        z: PARTICLE_RADIUS,
+    };
+    return (0..count)
+        .map(|_| {
+            let center = XYZ {
+                x: side * next_pseudo_random(&mut state),
+                y: side * next_pseudo_random(&mut state),
+                z: 0.0,
+            };
+            BoundingBox {
+                min: center - extent,
+                max: center + extent,
+            }
+        })
+        .collect();
+}
+
+fn main() {
+    let broadphases = [
+        BroadphaseType::BruteForce,
+        BroadphaseType::UniformGrid {
+            cell_size: 2.0 * PARTICLE_RADIUS,
+        },
+        BroadphaseType::SweepAndPrune,
+    ];
+    println!(
+        "{:>10} {:>40} {:>10} {:>12}",
+        "particles", "broadphase", "pairs", "time (ms)"
+    );
+    for count in [1_000, 10_000, 100_000] {
+        let bounds = particle_bounds(count);
+        for broadphase_type in broadphases {
+            if broadphase_type == BroadphaseType::BruteForce && count > BRUTE_FORCE_MAX_PARTICLES {
+                continue;
+            }
+            let mut broadphase = broadphase_type.broadphase();
+            let mut pairs = 0;
+            let mut best = Duration::MAX;
+            for _i in 0..REPETITIONS {
+                let start = Instant::now();
+                pairs = black_box(broadphase.candidate_pairs(black_box(&bounds))).len();
+                best = best.min(start.elapsed());
+            }
+            println!(
+                "{:>10} {:>40} {:>10} {:>12.3}",
+                count,
+                format!("{:?}", broadphase_type),
+                pairs,
+                best.as_secs_f64() * 1000.0
+            );
+        }
+    }
+}
@@ -0,0 +1,239 @@
+//! Broadphase collision detection, which cheaply narrows all pairs of objects down to the candidate
+//! pairs which might be colliding, so that the more expensive narrowphase (`TestCollision`) only
+//! runs on those.
+use crate::xyz::XYZ;
+use std::collections::HashMap;
+
+/// An axis-aligned box which fully contains a collider. Infinite colliders (e.g. planes) have
+/// infinite bounds.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct BoundingBox {
+    pub min: XYZ,
+    pub max: XYZ,
+}
+
+impl BoundingBox {
+    /// Returns true if the boxes overlap or touch
+    pub fn overlaps(&self, other: &BoundingBox) -> bool {
+        return self.min.x <= other.max.x
+            && self.max.x >= other.min.x
+            && self.min.y <= other.max.y
+            && self.max.y >= other.min.y
+            && self.min.z <= other.max.z
+            && self.max.z >= other.min.z;
+    }
+
+    /// Returns false for the bounds of infinite colliders (e.g. planes), and of `ColliderType::NONE`
+    pub fn is_finite(&self) -> bool {
+        return [
+            self.min.x, self.min.y, self.min.z, self.max.x, self.max.y, self.max.z,
+        ]
+        .iter()
+        .all(|v| v.is_finite());
+    }
+}
+
+pub trait Broadphase {
+    /// Returns every pair of indices (i, j), where i < j, whose bounding boxes overlap. Each pair
+    /// is returned exactly once, but in no particular order.
+    fn candidate_pairs(&mut self, bounds: &[BoundingBox]) -> Vec<(usize, usize)>;
+}
+
+/// Selects which broadphase a world uses.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum BroadphaseType {
+    /// Tests every pair. Only suitable for small numbers of objects, or as a reference.
+    BruteForce,
+    /// Fast when objects are of similar size, and `cell_size` is around their diameter.
+    UniformGrid { cell_size: f32 },
+    /// Needs no tuning, and copes well with objects of very different sizes.
+    SweepAndPrune,
+}
+
+impl BroadphaseType {
+    pub fn broadphase(&self) -> Box<dyn Broadphase> {
+        match self {
+            BroadphaseType::BruteForce => Box::new(BruteForce),
+            BroadphaseType::UniformGrid { cell_size } => Box::new(UniformGrid::new(*cell_size)),
+            BroadphaseType::SweepAndPrune => Box::new(SweepAndPrune),
+        }
+    }
+}
+
+/// The naive O(n^2) broadphase
+pub struct BruteForce;
+
+impl Broadphase for BruteForce {
+    fn candidate_pairs(&mut self, bounds: &[BoundingBox]) -> Vec<(usize, usize)> {
+        let mut pairs = Vec::new();
+        for i in 0..bounds.len() {
+            for j in (i + 1)..bounds.len() {
+                if bounds[i].overlaps(&bounds[j]) {
+                    pairs.push((i, j));
+                }
+            }
+        }
+        return pairs;
+    }
+}
+
+type Cell = (i32, i32, i32);
+
+// The most cells an object may be added to. Bigger objects would take too long to bucket, and
+// would share cells with most other objects anyway, so we test those against everything instead.
+const MAX_CELLS_PER_OBJECT: i64 = 4096;
+
+/// Buckets objects into a spatial hash of equally sized cubic cells, and only tests objects which
+/// share a cell. Objects spanning several cells are added to each of them, unless they span too many,
+/// in which case they're tested against every other object, like infinite ones. The cells are kept
+/// between calls, to reuse their memory.
+pub struct UniformGrid {
+    cell_size: f32,
+    cells: HashMap<Cell, Vec<usize>>,
+}
+
+impl UniformGrid {
+    /// `cell_size` must be greater than 0
+    pub fn new(cell_size: f32) -> UniformGrid {
+        return UniformGrid {
+            cell_size,
+            cells: HashMap::new(),
+        };
+    }
+
+    fn cell_of(&self, point: XYZ) -> Cell {
+        return (
+            (point.x / self.cell_size).floor() as i32,
+            (point.y / self.cell_size).floor() as i32,
+            (point.z / self.cell_size).floor() as i32,
+        );
+    }
+}
+
+impl Broadphase for UniformGrid {
+    fn candidate_pairs(&mut self, bounds: &[BoundingBox]) -> Vec<(usize, usize)> {
+        self.cells.clear();
+
+        // Infinite bounds can't be bucketed, and huge ones take too long to, so we test those
+        // against everything instead
+        let mut unbounded = Vec::new();
+        let mut min_cells = vec![(0, 0, 0); bounds.len()];
+        for (idx, b) in bounds.iter().enumerate() {
+            if !b.is_finite() {
+                unbounded.push(idx);
+                continue;
+            }
+            let min = self.cell_of(b.min);
+            let max = self.cell_of(b.max);
+            let span = |min: i32, max: i32| max as i64 - min as i64 + 1;
+            let cell_count = span(min.0, max.0)
+                .saturating_mul(span(min.1, max.1))
+                .saturating_mul(span(min.2, max.2));
+            if cell_count > MAX_CELLS_PER_OBJECT {
+                unbounded.push(idx);
+                continue;
+            }
+            min_cells[idx] = min;
+            for x in min.0..=max.0 {
+                for y in min.1..=max.1 {
+                    for z in min.2..=max.2 {
+                        self.cells.entry((x, y, z)).or_default().push(idx);
+                    }
+                }
+            }
+        }
+
+        let mut pairs = Vec::new();
+        for (cell, members) in self.cells.iter() {
+            for (n, &i) in members.iter().enumerate() {
+                for &j in &members[(n + 1)..] {
+                    // Two objects can share several cells. To report them only once, we only report
+                    // them from the first cell they share, i.e. the min corner of their overlap.
+                    let (a, b) = (min_cells[i], min_cells[j]);
+                    let first_shared_cell = (a.0.max(b.0), a.1.max(b.1), a.2.max(b.2));
+                    if *cell == first_shared_cell && bounds[i].overlaps(&bounds[j]) {
+                        pairs.push((usize::min(i, j), usize::max(i, j)));
+                    }
+                }
+            }
+        }
+
+        for (n, &i) in unbounded.iter().enumerate() {
+            for j in 0..bounds.len() {
+                // Avoid reporting pairs of two unbucketed objects twice
+                let already_reported = unbounded[..n].contains(&j);
+                if j != i && !already_reported && bounds[i].overlaps(&bounds[j]) {
+                    pairs.push((usize::min(i, j), usize::max(i, j)));
+                }
+            }
+        }
+        return pairs;
+    }
+}
+
+/// Sorts objects by where their bounds start along one axis, then sweeps along that axis, only
+/// testing objects whose bounds overlap on it. We sweep the axis along which objects are most
+/// spread out, since that prunes the most pairs.
+pub struct SweepAndPrune;
+
+impl Broadphase for SweepAndPrune {
+    fn candidate_pairs(&mut self, bounds: &[BoundingBox]) -> Vec<(usize, usize)> {
+        let axis = sweep_axis(bounds);
+        let start = |b: &BoundingBox| axis_value(b.min, axis);
+        let end = |b: &BoundingBox| axis_value(b.max, axis);
+
+        let mut order: Vec<usize> = (0..bounds.len()).collect();
+        order.sort_unstable_by(|&i, &j| start(&bounds[i]).total_cmp(&start(&bounds[j])));
+
+        let mut pairs = Vec::new();
+        // Objects whose bounds may still overlap those of objects later in the sweep
+        let mut active: Vec<usize> = Vec::new();
+        for &i in &order {
+            active.retain(|&j| end(&bounds[j]) >= start(&bounds[i]));
+            for &j in &active {
+                if bounds[i].overlaps(&bounds[j]) {
+                    pairs.push((usize::min(i, j), usize::max(i, j)));
+                }
+            }
+            active.push(i);
+        }
+        return pairs;
+    }
+}
+
+/// Returns the axis (0 = X, 1 = Y, 2 = Z) along which the centers of the finite bounds vary most
+fn sweep_axis(bounds: &[BoundingBox]) -> usize {
+    let centers: Vec<XYZ> = bounds
+        .iter()
+        .filter(|b| b.is_finite())
+        .map(|b| (b.min + b.max) * 0.5)
+        .collect();
+    if centers.is_empty() {
+        return 0;
+    }
+    let count = centers.len() as f32;
+    let mut variances = [0.0; 3];
+    for (axis, variance) in variances.iter_mut().enumerate() {
+        let mean = centers.iter().map(|c| axis_value(*c, axis)).sum::<f32>() / count;
+        *variance = centers
+            .iter()
+            .map(|c| (axis_value(*c, axis) - mean).powi(2))
+            .sum::<f32>()
+            / count;
+    }
+    let mut axis = 0;
+    for candidate in 1..3 {
+        if variances[candidate] > variances[axis] {
+            axis = candidate;
+        }
+    }
+    return axis;
+}
+
+fn axis_value(point: XYZ, axis: usize) -> f32 {
+    match axis {
+        0 => point.x,
+        1 => point.y,
+        _ => point.z,
+    }
+}
@@ -0,0 +1,144 @@
+//! Brownian motion: objects jostled by the molecules of an implicit solvent, and the mean squared
+//! displacement analysis which measures how quickly they diffuse.
+//!
+//! Temperatures are in energy units, i.e. they're Boltzmann's constant times the temperature, as in
+//! `thermostat`.
+use crate::forces::ForceGenerator;
+use crate::objects::Object;
+use crate::random::Rng;
+use crate::xyz::{DotProductXyz, XYZ};
+
+/// Returns the diffusion coefficient of an object at `temperature` in a solvent which drags it with
+/// the friction coefficient `damping`, by Einstein's relation D = kT / gamma
+pub fn einstein_diffusion_coefficient(temperature: f32, damping: f32) -> f32 {
+    return temperature / damping;
+}
+
+/// The force of a solvent on every movable object: a drag of -gamma v, plus a random kick, drawn
+/// afresh each tick from the world's random number generator. The kicks' strength, sqrt(2 gamma kT /
+/// dt) in each direction, balances the drag at the given temperature, so that the objects diffuse
+/// with the coefficient given by `einstein_diffusion_coefficient`.
+pub struct BrownianForce {
+    pub temperature: f32,
+    // gamma, the friction coefficient, in kg / s
+    pub damping: f32,
+    // See `thermostat::kinetic_temperature`
+    pub dimensions: usize,
+    // This tick's kick on each object
+    kicks: Vec<XYZ>,
+}
+
+impl BrownianForce {
+    pub fn new(temperature: f32, damping: f32, dimensions: usize) -> BrownianForce {
+        return BrownianForce {
+            temperature,
+            damping,
+            dimensions,
+            kicks: Vec::new(),
+        };
+    }
+}
+
+impl ForceGenerator for BrownianForce {
+    fn start_tick(&mut self, objects: &[Object], time_elapsed_seconds: f32, rng: &mut Rng) {
+        self.kicks.clear();
+        if time_elapsed_seconds <= 0.0 {
+            return;
+        }
+        let strength = (2.0 * self.damping * self.temperature / time_elapsed_seconds).sqrt();
+        let mut kick = |dimension: usize| {
+            if dimension < self.dimensions {
+                return strength * rng.normal();
+            }
+            return 0.0;
+        };
+        for _p in objects {
+            self.kicks.push(XYZ {
+                x: kick(0),
+                y: kick(1),
+                z: kick(2),
+            });
+        }
+    }
+
+    fn accumulate(&mut self, objects: &[Object], forces: &mut [XYZ]) {
+        for (idx, (p, force)) in objects.iter().zip(forces.iter_mut()).enumerate() {
+            if p.inverse_mass() == 0.0 {
+                continue;
+            }
+            // Objects added since the tick started haven't been kicked yet
+            let kick = self.kicks.get(idx).copied().unwrap_or_default();
+            *force += kick - p.velocity * self.damping;
+        }
+    }
+}
+
+/// Tracks how far the movable objects have wandered from where they started. For diffusing objects,
+/// the mean squared displacement grows linearly with time, as 2 d D t in d dimensions, once they've
+/// forgotten their initial velocity, so its slope gives the diffusion coefficient D.
+pub struct MeanSquaredDisplacement {
+    // Where each movable object started, by its index in the objects
+    origins: Vec<(usize, XYZ)>,
+    // See `thermostat::kinetic_temperature`
+    dimensions: usize,
+    // (time since the start, mean squared displacement) for each recording
+    samples: Vec<(f32, f32)>,
+}
+
+impl MeanSquaredDisplacement {
+    /// Starts tracking from the objects' current positions. The objects passed to `record` must be in
+    /// the same order.
+    pub fn new(objects: &[Object], dimensions: usize) -> MeanSquaredDisplacement {
+        return MeanSquaredDisplacement {
+            origins: objects
+                .iter()
+                .enumerate()
+                .filter(|(_idx, p)| p.inverse_mass() != 0.0)
+                .map(|(idx, p)| (idx, p.transform.position))
+                .collect(),
+            dimensions,
+            samples: Vec::new(),
+        };
+    }
+
+    /// Records the mean squared displacement `elapsed` seconds after the start, and returns it
+    pub fn record(&mut self, elapsed: f32, objects: &[Object]) -> f32 {
+        let mut total = 0.0;
+        for (idx, origin) in &self.origins {
+            let displacement = objects[*idx].transform.position - *origin;
+            total += displacement.dot(displacement);
+        }
+        let msd = if self.origins.is_empty() {
+            0.0
+        } else {
+            total / self.origins.len() as f32
+        };
+        self.samples.push((elapsed, msd));
+        return msd;
+    }
+
+    pub fn samples(&self) -> &[(f32, f32)] {
+        return &self.samples;
+    }
+
+    /// Returns the diffusion coefficient from the least squares slope of the recordings, or None
+    /// without at least two recordings at different times. Record only after the objects have had
+    /// time to forget their initial velocities, i.e. well after m / gamma, since before that they
+    /// move ballistically, and the mean squared displacement grows faster than linearly.
+    pub fn diffusion_coefficient(&self) -> Option<f32> {
+        let count = self.samples.len() as f32;
+        let mean_time = self.samples.iter().map(|(t, _msd)| t).sum::<f32>() / count;
+        let mean_msd = self.samples.iter().map(|(_t, msd)| msd).sum::<f32>() / count;
+        let mut covariance = 0.0;
+        let mut time_variance = 0.0;
+        for (t, msd) in &self.samples {
+            covariance += (t - mean_time) * (msd - mean_msd);
+            time_variance += (t - mean_time) * (t - mean_time);
+        }
+        if self.samples.len() < 2 || time_variance == 0.0 {
+            return None;
+        }
+        let slope = covariance / time_variance;
+        return Some(slope / (2.0 * self.dimensions as f32));
+    }
+}
@@ -1,8 +1,18 @@
+use crate::broadphase::BoundingBox;
+use crate::gjk::{gjk_epa_collision_points, Support};
+use crate::transform::{Mat3, Quat, Transform};
+use crate::xyz::CrossProductXyz;
+use crate::xyz::DotProductXyz;
 use crate::xyz::MagnitudeXyz;
 use crate::xyz::NormalizeXyz;
 use crate::xyz::XYZ;
 use core::fmt;
 
+/// The result of testing collider A against collider B, i.e. `a.test_collision(b)`.
+///
+/// When colliding, `normal` is the direction in which A must move to separate from B (and B must
+/// move in the opposite direction), and `depth` is how far they overlap along it.
+#[derive(Debug, Clone)]
 pub struct CollisionPoints {
     pub a: XYZ,      // Furthest point of A into B
     pub b: XYZ,      // Furthest point of B into A
@@ -11,6 +21,19 @@ pub struct CollisionPoints {
     pub has_collision: bool,
 }
 
+impl CollisionPoints {
+    /// The same collision, as seen from B's perspective, i.e. `b.test_collision(a)`
+    pub fn swapped(self) -> CollisionPoints {
+        return CollisionPoints {
+            a: self.b,
+            b: self.a,
+            normal: -self.normal,
+            depth: self.depth,
+            has_collision: self.has_collision,
+        };
+    }
+}
+
 impl fmt::Display for CollisionPoints {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(
@@ -21,6 +44,7 @@ impl fmt::Display for CollisionPoints {
     }
 }
 
+#[derive(Debug, Clone)]
 pub enum ColliderType {
     SPHERE {
         center: XYZ,
@@ -32,7 +56,449 @@ pub enum ColliderType {
         normal: XYZ,
         distance: f32,
     },
-    // todo? Add a none type, so we can have objects which don't collide
+    /// An axis-aligned box, which extends `half_extents` from its center along each axis
+    AABB {
+        center: XYZ,
+        half_extents: XYZ,
+    },
+    /// An oriented box, i.e. a box which extends `half_extents` from its center along each of its
+    /// own axes, which are the world axes rotated by `rotation`. The rotation must be normalized.
+    OBB {
+        center: XYZ,
+        half_extents: XYZ,
+        rotation: Quat,
+    },
+    /// All points within `radius` of the line segment from `start` to `end`, i.e. a cylinder with
+    /// hemispherical caps
+    CAPSULE {
+        start: XYZ,
+        end: XYZ,
+        radius: f32,
+    },
+    /// The line segment from `start` to `end`, i.e. a capsule with no radius
+    SEGMENT {
+        start: XYZ,
+        end: XYZ,
+    },
+    /// A convex polygon, whose vertices should all lie in one plane, in order around its edge
+    POLYGON {
+        vertices: Vec<XYZ>,
+    },
+    /// The convex hull of the vertices, i.e. the smallest convex shape containing them all. Only
+    /// the outermost vertices matter, so there's no need to remove any inside the hull.
+    HULL {
+        vertices: Vec<XYZ>,
+    },
+    /// A union of other colliders, e.g. two spheres joined by a capsule for a dumbbell. Unlike
+    /// the other shapes, it need not be convex.
+    COMPOUND {
+        children: Vec<ChildCollider>,
+    },
+    /// No collider at all, for objects which shouldn't collide with anything, e.g. decorations
+    NONE,
+}
+
+/// A part of a compound collider
+#[derive(Debug, Clone)]
+pub struct ChildCollider {
+    // Positions the child within the compound, in the same way an object's transform positions
+    // its collider within the world
+    pub transform: Transform,
+    pub collider: ColliderType,
+}
+
+impl ChildCollider {
+    /// Returns the child's collider in the compound's space
+    pub fn positioned(&self) -> ColliderType {
+        return self.collider.transformed(&self.transform);
+    }
+}
+
+impl ColliderType {
+    /// Returns the collider moved from a local space into the space containing it, e.g. from an
+    /// object's local space into the world, given the transform of the local space.
+    ///
+    /// The collider is scaled, then rotated, then moved by the transform's position, like any point
+    /// in the local space. Some shapes can't be represented after every transform, so:
+    /// * A sphere scaled unevenly would be an ellipsoid, so we scale its radius by the largest scale
+    ///   factor, which gives the smallest sphere containing that ellipsoid. Capsules do the same.
+    /// * An axis-aligned box which is rotated becomes an oriented box.
+    pub fn transformed(&self, transform: &Transform) -> ColliderType {
+        // An unset (e.g. all-zero) rotation is treated as no rotation
+        let object_rotation = if transform.rotation.is_normalized() {
+            transform.rotation
+        } else {
+            Quat::IDENTITY
+        };
+        let scale = transform.scale;
+        let abs_scale = XYZ {
+            x: scale.x.abs(),
+            y: scale.y.abs(),
+            z: scale.z.abs(),
+        };
+        let largest_scale = abs_scale.x.max(abs_scale.y).max(abs_scale.z);
+        let rotate = |v: XYZ| XYZ::from(object_rotation * glam::Vec3::from(v));
+        let to_world = |local: XYZ| transform.position + rotate(&local * scale);
+
+        match self {
+            ColliderType::SPHERE { center, radius } => ColliderType::SPHERE {
+                center: to_world(*center),
+                radius: *radius * largest_scale,
+            },
+            ColliderType::AABB {
+                center,
+                half_extents,
+            } if object_rotation.is_near_identity() => ColliderType::AABB {
+                center: to_world(*center),
+                half_extents: half_extents * abs_scale,
+            },
+            ColliderType::AABB {
+                center,
+                half_extents,
+            } => ColliderType::OBB {
+                center: to_world(*center),
+                half_extents: half_extents * abs_scale,
+                rotation: object_rotation,
+            },
+            // A box can only be scaled along its own axes, so for a box rotated within the object,
+            // this only matches the object's scale if the scale is uniform
+            ColliderType::OBB {
+                center,
+                half_extents,
+                rotation,
+            } => ColliderType::OBB {
+                center: to_world(*center),
+                half_extents: half_extents * abs_scale,
+                rotation: object_rotation * *rotation,
+            },
+            ColliderType::CAPSULE { start, end, radius } => ColliderType::CAPSULE {
+                start: to_world(*start),
+                end: to_world(*end),
+                radius: *radius * largest_scale,
+            },
+            ColliderType::SEGMENT { start, end } => ColliderType::SEGMENT {
+                start: to_world(*start),
+                end: to_world(*end),
+            },
+            ColliderType::POLYGON { vertices } => ColliderType::POLYGON {
+                vertices: vertices.iter().map(|v| to_world(*v)).collect(),
+            },
+            ColliderType::HULL { vertices } => ColliderType::HULL {
+                vertices: vertices.iter().map(|v| to_world(*v)).collect(),
+            },
+            ColliderType::PLANE { normal, distance } => {
+                // Normals don't scale like points: stretching a plane along an axis tilts its
+                // normal away from that axis. See "transforming normals" in any graphics text.
+                // The scale factors must be non-zero.
+                let scaled_normal = rotate(XYZ {
+                    x: normal.x / scale.x,
+                    y: normal.y / scale.y,
+                    z: normal.z / scale.z,
+                });
+                let length = scaled_normal.magnitude();
+                let world_normal = scaled_normal * (1.0 / length);
+                ColliderType::PLANE {
+                    normal: world_normal,
+                    distance: *distance / length + world_normal.dot(transform.position),
+                }
+            }
+            // Children are stored already positioned, so that they needn't be transformed again
+            // every time they're tested
+            ColliderType::COMPOUND { children } => ColliderType::COMPOUND {
+                children: children
+                    .iter()
+                    .map(|child| ChildCollider {
+                        transform: Transform::IDENTITY,
+                        collider: child.positioned().transformed(transform),
+                    })
+                    .collect(),
+            },
+            ColliderType::NONE => ColliderType::NONE,
+        }
+    }
+
+    /// Returns the collider's volume. Polygons are flat, so we treat them as being one unit thick,
+    /// i.e. their volume is their area. Segments have no volume, and planes have infinite volume.
+    /// The children of a compound are assumed not to overlap, since overlaps are counted once per
+    /// child they're in.
+    pub fn volume(&self) -> f32 {
+        match self {
+            ColliderType::SPHERE { radius, .. } => sphere_volume(*radius),
+            ColliderType::PLANE { .. } => f32::INFINITY,
+            ColliderType::AABB { half_extents, .. } | ColliderType::OBB { half_extents, .. } => {
+                8.0 * half_extents.x * half_extents.y * half_extents.z
+            }
+            ColliderType::CAPSULE { start, end, radius } => {
+                // A cylinder, plus a hemisphere at each end
+                let cylinder = std::f32::consts::PI * radius * radius * (*end - *start).magnitude();
+                cylinder + sphere_volume(*radius)
+            }
+            ColliderType::SEGMENT { .. } | ColliderType::NONE => 0.0,
+            ColliderType::POLYGON { vertices } => polygon_area_and_centroid(vertices).0,
+            ColliderType::HULL { vertices } => hull_volume_and_centroid(vertices).0,
+            ColliderType::COMPOUND { children } => {
+                children.iter().map(|c| c.positioned().volume()).sum()
+            }
+        }
+    }
+
+    /// Returns the area of the collider's shadow on a plane perpendicular to `direction`, i.e. the area
+    /// it presents to a fluid flowing past it in that direction. Polygons are flat, so only present
+    /// their full area face on. The shadows of a compound's children are assumed not to overlap.
+    pub fn cross_sectional_area(&self, direction: XYZ) -> f32 {
+        let direction = direction.normalize();
+        match self {
+            ColliderType::SPHERE { radius, .. } => std::f32::consts::PI * radius * radius,
+            ColliderType::PLANE { .. } => f32::INFINITY,
+            ColliderType::AABB { half_extents, .. } => {
+                box_cross_sectional_area(*half_extents, Quat::IDENTITY, direction)
+            }
+            ColliderType::OBB {
+                half_extents,
+                rotation,
+                ..
+            } => box_cross_sectional_area(*half_extents, *rotation, direction),
+            ColliderType::CAPSULE { start, end, radius } => {
+                // The cylinder's shadow is a rectangle, and the hemispheres' a circle
+                let cylinder = 2.0 * radius * (*end - *start).cross(direction).magnitude();
+                cylinder + std::f32::consts::PI * radius * radius
+            }
+            ColliderType::SEGMENT { .. } | ColliderType::NONE => 0.0,
+            ColliderType::POLYGON { vertices } => {
+                let mut area = 0.0;
+                for idx in 1..vertices.len().saturating_sub(1) {
+                    let (a, b, c) = (vertices[0], vertices[idx], vertices[idx + 1]);
+                    area += 0.5 * (b - a).cross(c - a).dot(direction).abs();
+                }
+                area
+            }
+            ColliderType::HULL { vertices } => {
+                // Each point of the shadow is covered by exactly two faces, one on either side
+                let mut area = 0.0;
+                for [a, b, c] in hull_triangles(vertices) {
+                    area += 0.25 * (b - a).cross(c - a).dot(direction).abs();
+                }
+                area
+            }
+            ColliderType::COMPOUND { children } => children
+                .iter()
+                .map(|c| c.positioned().cross_sectional_area(direction))
+                .sum(),
+        }
+    }
+
+    /// Returns the collider's centroid, i.e. its center of mass if its density is uniform. Planes
+    /// have no centroid, so for them we return the point on the plane closest to the origin.
+    pub fn centroid(&self) -> XYZ {
+        match self {
+            ColliderType::SPHERE { center, .. }
+            | ColliderType::AABB { center, .. }
+            | ColliderType::OBB { center, .. } => *center,
+            ColliderType::PLANE { normal, distance } => normal.normalize() * *distance,
+            ColliderType::CAPSULE { start, end, .. } | ColliderType::SEGMENT { start, end } => {
+                (*start + *end) * 0.5
+            }
+            ColliderType::POLYGON { vertices } => polygon_area_and_centroid(vertices).1,
+            ColliderType::HULL { vertices } => hull_volume_and_centroid(vertices).1,
+            ColliderType::COMPOUND { children } => {
+                let positioned: Vec<ColliderType> =
+                    children.iter().map(|c| c.positioned()).collect();
+                let total_volume: f32 = positioned.iter().map(|c| c.volume()).sum();
+                let mut centroid = XYZ::default();
+                if total_volume > 0.0 && total_volume.is_finite() {
+                    // Weight each child's centroid by its share of the volume
+                    for child in &positioned {
+                        centroid += child.centroid() * (child.volume() / total_volume);
+                    }
+                } else if !positioned.is_empty() {
+                    // The children have no volume to weigh them by, so weigh them equally
+                    for child in &positioned {
+                        centroid += child.centroid() * (1.0 / positioned.len() as f32);
+                    }
+                }
+                centroid
+            }
+            ColliderType::NONE => XYZ::default(),
+        }
+    }
+
+    /// Returns the collider's inertia tensor about `point`, if `mass` is spread evenly through it.
+    ///
+    /// Segments and polygons are treated as thin rods and plates, since they have no volume to spread
+    /// the mass through. Planes and `NONE` can't be spun, so have an inertia tensor of zero.
+    pub fn inertia_tensor(&self, mass: f32, point: XYZ) -> Mat3 {
+        let about_centroid = match self {
+            ColliderType::SPHERE { radius, .. } => {
+                Mat3::from_diagonal(glam::Vec3::splat(0.4 * mass * radius * radius))
+            }
+            ColliderType::AABB { half_extents, .. } => {
+                box_inertia(mass, *half_extents, Quat::IDENTITY)
+            }
+            ColliderType::OBB {
+                half_extents,
+                rotation,
+                ..
+            } => box_inertia(mass, *half_extents, *rotation),
+            ColliderType::CAPSULE { start, end, radius } => {
+                capsule_inertia(mass, *start, *end, *radius)
+            }
+            ColliderType::SEGMENT { start, end } => capsule_inertia(mass, *start, *end, 0.0),
+            ColliderType::POLYGON { vertices } => polygon_inertia(mass, vertices),
+            ColliderType::HULL { vertices } => hull_inertia(mass, vertices),
+            ColliderType::COMPOUND { children } => {
+                // Each child takes its share of the mass, and its own offset from the point
+                let positioned: Vec<ColliderType> =
+                    children.iter().map(|c| c.positioned()).collect();
+                let total_volume: f32 = positioned.iter().map(|c| c.volume()).sum();
+                let mut inertia = Mat3::ZERO;
+                for child in &positioned {
+                    let share = if total_volume > 0.0 && total_volume.is_finite() {
+                        child.volume() / total_volume
+                    } else {
+                        1.0 / positioned.len() as f32
+                    };
+                    inertia += child.inertia_tensor(mass * share, point);
+                }
+                return inertia;
+            }
+            ColliderType::PLANE { .. } | ColliderType::NONE => return Mat3::ZERO,
+        };
+        // For spinning about a point other than the centroid
+        return parallel_axis(about_centroid, mass, self.centroid() - point);
+    }
+
+    /// Returns an axis-aligned box which fully contains the collider, for use in the broadphase
+    pub fn bounding_box(&self) -> BoundingBox {
+        match self {
+            ColliderType::SPHERE { center, radius } => {
+                let extent = XYZ {
+                    x: *radius,
+                    y: *radius,
+                    z: *radius,
+                };
+                BoundingBox {
+                    min: *center - extent,
+                    max: *center + extent,
+                }
+            }
+            ColliderType::AABB {
+                center,
+                half_extents,
+            } => BoundingBox {
+                min: *center - *half_extents,
+                max: *center + *half_extents,
+            },
+            ColliderType::OBB {
+                center,
+                half_extents,
+                rotation,
+            } => {
+                // Each world axis extent is the sum of the box axes' projections onto it
+                let mut extent = XYZ::default();
+                for (i, axis) in box_axes(*rotation).iter().enumerate() {
+                    let half_axis = *axis * half_extent(*half_extents, i);
+                    extent.x += half_axis.x.abs();
+                    extent.y += half_axis.y.abs();
+                    extent.z += half_axis.z.abs();
+                }
+                BoundingBox {
+                    min: *center - extent,
+                    max: *center + extent,
+                }
+            }
+            ColliderType::CAPSULE { start, end, radius } => {
+                let extent = XYZ {
+                    x: *radius,
+                    y: *radius,
+                    z: *radius,
+                };
+                BoundingBox {
+                    min: XYZ {
+                        x: start.x.min(end.x),
+                        y: start.y.min(end.y),
+                        z: start.z.min(end.z),
+                    } - extent,
+                    max: XYZ {
+                        x: start.x.max(end.x),
+                        y: start.y.max(end.y),
+                        z: start.z.max(end.z),
+                    } + extent,
+                }
+            }
+            ColliderType::SEGMENT { start, end } => ColliderType::CAPSULE {
+                start: *start,
+                end: *end,
+                radius: 0.0,
+            }
+            .bounding_box(),
+            ColliderType::POLYGON { vertices } | ColliderType::HULL { vertices } => {
+                let mut min = XYZ {
+                    x: f32::INFINITY,
+                    y: f32::INFINITY,
+                    z: f32::INFINITY,
+                };
+                let mut max = -min;
+                for v in vertices {
+                    min = XYZ {
+                        x: min.x.min(v.x),
+                        y: min.y.min(v.y),
+                        z: min.z.min(v.z),
+                    };
+                    max = XYZ {
+                        x: max.x.max(v.x),
+                        y: max.y.max(v.y),
+                        z: max.z.max(v.z),
+                    };
+                }
+                BoundingBox { min, max }
+            }
+            ColliderType::COMPOUND { children } => {
+                let mut bounds = ColliderType::NONE.bounding_box();
+                for child in children {
+                    let child_bounds = child.positioned().bounding_box();
+                    bounds = BoundingBox {
+                        min: XYZ {
+                            x: bounds.min.x.min(child_bounds.min.x),
+                            y: bounds.min.y.min(child_bounds.min.y),
+                            z: bounds.min.z.min(child_bounds.min.z),
+                        },
+                        max: XYZ {
+                            x: bounds.max.x.max(child_bounds.max.x),
+                            y: bounds.max.y.max(child_bounds.max.y),
+                            z: bounds.max.z.max(child_bounds.max.z),
+                        },
+                    };
+                }
+                bounds
+            }
+            // An empty box, which contains nothing
+            ColliderType::NONE => BoundingBox {
+                min: XYZ {
+                    x: f32::INFINITY,
+                    y: f32::INFINITY,
+                    z: f32::INFINITY,
+                },
+                max: XYZ {
+                    x: f32::NEG_INFINITY,
+                    y: f32::NEG_INFINITY,
+                    z: f32::NEG_INFINITY,
+                },
+            },
+            // TODO: a plane aligned with an axis could have finite bounds along that axis
+            ColliderType::PLANE { .. } => BoundingBox {
+                min: XYZ {
+                    x: f32::NEG_INFINITY,
+                    y: f32::NEG_INFINITY,
+                    z: f32::NEG_INFINITY,
+                },
+                max: XYZ {
+                    x: f32::INFINITY,
+                    y: f32::INFINITY,
+                    z: f32::INFINITY,
+                },
+            },
+        }
+    }
 }
 
 impl fmt::Display for ColliderType {
@@ -48,10 +514,51 @@ impl fmt::Display for ColliderType {
             ColliderType::PLANE { normal, distance } => {
                 write!(
                     f,
-                    "ColliderType::SPHERE(center={},radius={}",
+                    "ColliderType::PLANE(normal={},distance={}",
                     normal, distance
                 )
             }
+            ColliderType::AABB {
+                center,
+                half_extents,
+            } => {
+                write!(
+                    f,
+                    "ColliderType::AABB(center={},half_extents={}",
+                    center, half_extents
+                )
+            }
+            ColliderType::OBB {
+                center,
+                half_extents,
+                rotation,
+            } => {
+                write!(
+                    f,
+                    "ColliderType::OBB(center={},half_extents={},rotation={}",
+                    center, half_extents, rotation
+                )
+            }
+            ColliderType::CAPSULE { start, end, radius } => {
+                write!(
+                    f,
+                    "ColliderType::CAPSULE(start={},end={},radius={}",
+                    start, end, radius
+                )
+            }
+            ColliderType::SEGMENT { start, end } => {
+                write!(f, "ColliderType::SEGMENT(start={},end={}", start, end)
+            }
+            ColliderType::POLYGON { vertices } => {
+                write!(f, "ColliderType::POLYGON(vertices={}", vertices.len())
+            }
+            ColliderType::HULL { vertices } => {
+                write!(f, "ColliderType::HULL(vertices={}", vertices.len())
+            }
+            ColliderType::COMPOUND { children } => {
+                write!(f, "ColliderType::COMPOUND(children={}", children.len())
+            }
+            ColliderType::NONE => write!(f, "ColliderType::NONE"),
         }
     }
 }
@@ -60,11 +567,68 @@ pub trait TestCollision {
     fn test_collision(&self, collider: &ColliderType) -> CollisionPoints;
 }
 
+impl ColliderType {
+    /// Returns every collision between the colliders. For compound colliders, this means every
+    /// collision between each of their children, whereas `test_collision` only returns the deepest.
+    /// Results are from this collider's perspective, like those of `test_collision`.
+    pub fn test_collision_all(&self, collider: &ColliderType) -> Vec<CollisionPoints> {
+        return self
+            .child_collisions(collider)
+            .into_iter()
+            .filter(|points| points.has_collision)
+            .collect();
+    }
+
+    /// Test each of this collider's children, or this collider itself if it isn't a compound,
+    /// against each of the other collider's children, or the other collider itself
+    fn child_collisions(&self, collider: &ColliderType) -> Vec<CollisionPoints> {
+        match (self, collider) {
+            (ColliderType::COMPOUND { children }, _) => children
+                .iter()
+                .flat_map(|child| child.positioned().child_collisions(collider))
+                .collect(),
+            (_, ColliderType::COMPOUND { children }) => children
+                .iter()
+                .flat_map(|child| self.child_collisions(&child.positioned()))
+                .collect(),
+            _ => vec![self.test_collision(collider)],
+        }
+    }
+}
+
 impl TestCollision for ColliderType {
     fn test_collision(&self, collider: &ColliderType) -> CollisionPoints {
         use ColliderType::*;
-        // todo: make this more legible
+        // Each unordered pair of collider types is handled by one arm below. The other order is
+        // handled by the catch-all arm at the end, which swaps the arguments and the result.
         match (self, collider) {
+            (NONE, _) | (_, NONE) => no_collision_points(),
+            // A polygon or hull without any vertices has no points to collide with
+            (POLYGON { vertices } | HULL { vertices }, _)
+            | (_, POLYGON { vertices } | HULL { vertices })
+                if vertices.is_empty() =>
+            {
+                no_collision_points()
+            }
+
+            // Compounds collide where their deepest colliding child does. Without any colliding
+            // children, they report the smallest gap, if a child reports one as a negative depth.
+            // Other non-colliding depths aren't comparable, e.g. a sphere far behind a plane.
+            (COMPOUND { .. }, _) | (_, COMPOUND { .. }) => {
+                let (colliding, separate): (Vec<CollisionPoints>, Vec<CollisionPoints>) = self
+                    .child_collisions(collider)
+                    .into_iter()
+                    .partition(|points| points.has_collision);
+                let deepest = |points: Vec<CollisionPoints>| {
+                    points
+                        .into_iter()
+                        .max_by(|p, q| p.depth.total_cmp(&q.depth))
+                };
+                deepest(colliding)
+                    .or_else(|| deepest(separate.into_iter().filter(|p| p.depth <= 0.0).collect()))
+                    .unwrap_or_else(no_collision_points)
+            }
+
             (
                 PLANE {
                     normal: n1,
@@ -77,75 +641,343 @@ impl TestCollision for ColliderType {
             ) => plane_plane_collision_points(n1, d1, n2, d2),
 
             (
+                SPHERE {
+                    center: c1,
+                    radius: r1,
+                },
                 PLANE {
                     normal: n1,
                     distance: d1,
                 },
+            ) => plane_sphere_collision_points(*n1, *d1, *c1, *r1),
+
+            (
                 SPHERE {
                     center: c1,
                     radius: r1,
                 },
-            )
-            | (
                 SPHERE {
-                    center: c1,
+                    center: c2,
+                    radius: r2,
+                },
+            ) => sphere_sphere_collision_points(c1, r1, c2, r2),
+
+            // Polygons and hulls have no bespoke collision functions, and are handled generically
+            // through their support functions instead. Planes are unbounded, so have no support
+            // function, but only the deepest point of a convex shape matters against a plane.
+            (POLYGON { .. } | HULL { .. }, PLANE { normal, distance }) => {
+                convex_plane_collision_points(self, *normal, *distance)
+            }
+            (PLANE { .. }, POLYGON { .. } | HULL { .. }) => collider.test_collision(self).swapped(),
+            (POLYGON { .. } | HULL { .. }, _) | (_, POLYGON { .. } | HULL { .. }) => {
+                gjk_epa_collision_points(self, collider)
+            }
+
+            // An axis-aligned box is just an oriented box with no rotation
+            (
+                AABB {
+                    center,
+                    half_extents,
+                },
+                _,
+            ) => OBB {
+                center: *center,
+                half_extents: *half_extents,
+                rotation: Quat::IDENTITY,
+            }
+            .test_collision(collider),
+            (
+                _,
+                AABB {
+                    center,
+                    half_extents,
+                },
+            ) => self.test_collision(&OBB {
+                center: *center,
+                half_extents: *half_extents,
+                rotation: Quat::IDENTITY,
+            }),
+
+            // A segment is just a capsule with no radius
+            (SEGMENT { start, end }, _) => CAPSULE {
+                start: *start,
+                end: *end,
+                radius: 0.0,
+            }
+            .test_collision(collider),
+            (_, SEGMENT { start, end }) => self.test_collision(&CAPSULE {
+                start: *start,
+                end: *end,
+                radius: 0.0,
+            }),
+
+            (
+                CAPSULE {
+                    start: s1,
+                    end: e1,
+                    radius: r1,
+                },
+                CAPSULE {
+                    start: s2,
+                    end: e2,
+                    radius: r2,
+                },
+            ) => {
+                let (p1, p2) = segment_segment_closest_points(*s1, *e1, *s2, *e2);
+                sphere_sphere_collision_points(&p1, r1, &p2, r2)
+            }
+
+            (
+                CAPSULE {
+                    start: s1,
+                    end: e1,
+                    radius: r1,
+                },
+                SPHERE {
+                    center: c2,
+                    radius: r2,
+                },
+            ) => {
+                let p1 = segment_closest_point(*s1, *e1, *c2);
+                sphere_sphere_collision_points(&p1, r1, c2, r2)
+            }
+
+            (
+                CAPSULE {
+                    start: s1,
+                    end: e1,
                     radius: r1,
                 },
                 PLANE {
-                    normal: n1,
-                    distance: d1,
+                    normal: n2,
+                    distance: d2,
                 },
-            ) => plane_sphere_collision_points(*n1, *d1, *c1, *r1),
+            ) => capsule_plane_collision_points(*s1, *e1, *r1, *n2, *d2),
+
+            (
+                CAPSULE {
+                    start: s1,
+                    end: e1,
+                    radius: r1,
+                },
+                OBB {
+                    center: c2,
+                    half_extents: h2,
+                    rotation: r2,
+                },
+            ) => capsule_box_collision_points(*s1, *e1, *r1, &OrientedBox::new(*c2, *h2, *r2)),
+
+            (
+                OBB {
+                    center: c1,
+                    half_extents: h1,
+                    rotation: r1,
+                },
+                OBB {
+                    center: c2,
+                    half_extents: h2,
+                    rotation: r2,
+                },
+            ) => box_box_collision_points(
+                &OrientedBox::new(*c1, *h1, *r1),
+                &OrientedBox::new(*c2, *h2, *r2),
+            ),
 
             (
                 SPHERE {
                     center: c1,
                     radius: r1,
                 },
-                SPHERE {
+                OBB {
                     center: c2,
-                    radius: r2,
+                    half_extents: h2,
+                    rotation: r2,
                 },
-            ) => sphere_sphere_collision_points(c1, r1, c2, r2),
+            ) => sphere_box_collision_points(*c1, *r1, &OrientedBox::new(*c2, *h2, *r2)),
+
+            (
+                OBB {
+                    center: c1,
+                    half_extents: h1,
+                    rotation: r1,
+                },
+                PLANE {
+                    normal: n2,
+                    distance: d2,
+                },
+            ) => box_plane_collision_points(&OrientedBox::new(*c1, *h1, *r1), *n2, *d2),
+
+            (PLANE { .. }, SPHERE { .. })
+            | (PLANE { .. }, OBB { .. })
+            | (OBB { .. }, SPHERE { .. })
+            | (SPHERE { .. } | PLANE { .. } | OBB { .. }, CAPSULE { .. }) => {
+                collider.test_collision(self).swapped()
+            }
         }
     }
 }
 
-// todo?
+impl Support for ColliderType {
+    /// Planes are unbounded, so have no support point. For them, we return the point of the plane
+    /// closest to the origin, but they should be tested against with their own collision functions.
+    /// `NONE` has no points at all, so we return the origin, as do empty compounds.
+    fn support(&self, direction: XYZ) -> XYZ {
+        match self {
+            ColliderType::SPHERE { center, radius } => *center + direction.normalize() * *radius,
+            ColliderType::PLANE { normal, distance } => normal.normalize() * *distance,
+            ColliderType::AABB {
+                center,
+                half_extents,
+            } => OrientedBox::new(*center, *half_extents, Quat::IDENTITY).support(direction),
+            ColliderType::OBB {
+                center,
+                half_extents,
+                rotation,
+            } => OrientedBox::new(*center, *half_extents, *rotation).support(direction),
+            ColliderType::CAPSULE { start, end, radius } => {
+                ColliderType::SEGMENT {
+                    start: *start,
+                    end: *end,
+                }
+                .support(direction)
+                    + direction.normalize() * *radius
+            }
+            ColliderType::SEGMENT { start, end } => {
+                if end.dot(direction) > start.dot(direction) {
+                    *end
+                } else {
+                    *start
+                }
+            }
+            ColliderType::POLYGON { vertices } | ColliderType::HULL { vertices } => {
+                // Without any vertices, there's no furthest point, so we use the origin, as for an
+                // empty compound
+                let mut furthest = vertices.first().copied().unwrap_or_default();
+                for v in vertices.iter().skip(1) {
+                    if v.dot(direction) > furthest.dot(direction) {
+                        furthest = *v;
+                    }
+                }
+                furthest
+            }
+            // The support function of a union of shapes describes the convex hull of that union
+            ColliderType::COMPOUND { children } => {
+                let mut furthest = XYZ::default();
+                let mut furthest_distance = f32::NEG_INFINITY;
+                for child in children {
+                    let point = child.positioned().support(direction);
+                    if point.dot(direction) > furthest_distance {
+                        furthest = point;
+                        furthest_distance = point.dot(direction);
+                    }
+                }
+                furthest
+            }
+            ColliderType::NONE => XYZ::default(),
+        }
+    }
+}
+
+/// How two planes intersect, if at all
+#[derive(Debug, Clone, PartialEq)]
+pub enum PlaneIntersection {
+    /// The planes are parallel, and don't touch. `separation` is the offset from plane B to plane A.
+    Parallel { separation: XYZ },
+    /// The planes are the same plane, although their normals may point in opposite directions
+    Coincident,
+    /// The planes cross along the line through `point` along `direction`, where `point` is the
+    /// line's closest point to the origin, and `direction` is normalized
+    Line { point: XYZ, direction: XYZ },
+}
+
+// How close to parallel two planes must be to be considered parallel, as the sine of the angle
+// between them, and how close parallel planes must be to be considered coincident
+const PARALLEL_TOLERANCE: f32 = 1e-6;
+const COINCIDENT_TOLERANCE: f32 = 1e-4;
+
+/// Returns how plane A (`normal_1`, `distance_1`) intersects plane B (`normal_2`, `distance_2`)
+pub fn plane_plane_intersection(
+    normal_1: XYZ,
+    distance_1: f32,
+    normal_2: XYZ,
+    distance_2: f32,
+) -> PlaneIntersection {
+    let n1 = normal_1.normalize();
+    let n2 = normal_2.normalize();
+    let direction = n1.cross(n2);
+    let sin_angle = direction.magnitude();
+
+    if sin_angle <= PARALLEL_TOLERANCE {
+        // Describe plane B with A's normal, which may mean flipping it
+        let distance_2_along_n1 = if n1.dot(n2) < 0.0 {
+            -distance_2
+        } else {
+            distance_2
+        };
+        let gap = distance_1 - distance_2_along_n1;
+        if gap.abs() <= COINCIDENT_TOLERANCE {
+            return PlaneIntersection::Coincident;
+        }
+        return PlaneIntersection::Parallel {
+            separation: n1 * gap,
+        };
+    }
+
+    // The point on both planes closest to the origin. See Ericson, Real-Time Collision Detection,
+    // section 5.4.4.
+    let point = (n2.cross(direction) * distance_1 + direction.cross(n1) * distance_2)
+        * (1.0 / (sin_angle * sin_angle));
+    return PlaneIntersection::Line {
+        point,
+        direction: direction * (1.0 / sin_angle),
+    };
+}
+
+/// Returns the CollisionPoints for two planes, based on `plane_plane_intersection`.
+///
+/// Parallel planes don't collide, and `a` and `b` are the points on each plane closest to the
+/// origin's projection onto A. Planes which do intersect can't be separated by moving either of
+/// them, so we report a depth of 0 along B's normal, at the point of the intersection closest to
+/// the origin.
 fn plane_plane_collision_points(
-    _normal_1: &XYZ,
-    _distance_1: &f32,
-    _normal_2: &XYZ,
-    _distance_2: &f32,
+    normal_1: &XYZ,
+    distance_1: &f32,
+    normal_2: &XYZ,
+    distance_2: &f32,
 ) -> CollisionPoints {
-    return CollisionPoints {
-        a: XYZ {
-            x: 0.0,
-            y: 0.0,
-            z: 0.0,
-        },
-        b: XYZ {
-            x: 0.0,
-            y: 0.0,
-            z: 0.0,
-        },
-        normal: XYZ {
-            x: 0.0,
-            y: 0.0,
-            z: 0.0,
-        }, // TODO
+    let on_plane_1 = normal_1.normalize() * *distance_1;
+    let touching = |point: XYZ| CollisionPoints {
+        a: point,
+        b: point,
+        normal: normal_2.normalize(),
         depth: 0.0,
-        has_collision: false,
+        has_collision: true,
     };
+    match plane_plane_intersection(*normal_1, *distance_1, *normal_2, *distance_2) {
+        PlaneIntersection::Parallel { separation } => CollisionPoints {
+            a: on_plane_1,
+            b: on_plane_1 - separation,
+            normal: separation.normalize(),
+            depth: -separation.magnitude(),
+            has_collision: false,
+        },
+        PlaneIntersection::Coincident => touching(on_plane_1),
+        PlaneIntersection::Line { point, .. } => touching(point),
+    }
 }
 
-fn dot_product(a: XYZ, b: XYZ) -> f32 {
-    // todo: make this more generic and move it elsewhere
-    let dot_a_b = a.x * b.x + a.y * b.y + a.z * b.z;
-    return dot_a_b;
+/// Returns the CollisionPoints for a collider which can't collide, i.e. `ColliderType::NONE`
+fn no_collision_points() -> CollisionPoints {
+    return CollisionPoints {
+        a: XYZ::default(),
+        b: XYZ::default(),
+        normal: XYZ::default(),
+        depth: 0.0,
+        has_collision: false,
+    };
 }
 
-/// Determine all collision points between a sphere and plane, if any.
+/// Determine all collision points between a sphere and plane, if any. The sphere is treated as A.
 fn plane_sphere_collision_points(
     plane_normal: XYZ,
     plane_distance: f32,
@@ -159,7 +991,7 @@ fn plane_sphere_collision_points(
 
     // distance from center of sphere to plane surface
     // println!("Sphere_center - on_plane \n\t= {} - {} \n\t= {}", sphere_center, on_plane, sphere_center - on_plane);
-    let distance = dot_product(sphere_center - on_plane, p_normal);
+    let distance = (sphere_center - on_plane).dot(p_normal);
     // println!("Distance between objects {}", distance);
 
     let has_collision = f32::abs(distance) <= sphere_radius;
@@ -180,92 +1012,621 @@ fn plane_sphere_collision_points(
     return res;
 }
 
-/// Returns the CollisionPoints for the intersection of two spheres
-/// The furthest collision point of a sphere A into another sphere B, is B's center
+/// Returns the CollisionPoints for the intersection of two spheres.
+///
+/// The spheres collide if and only if the distance between their centers is at most the sum of
+/// their radii. All results are measured along the line between the two centers: the normal points
+/// from sphere 2's center to sphere 1's center, and the collision points are where each sphere's
+/// surface crosses that line, on the side facing the other sphere. When the spheres don't collide,
+/// the depth is negative, and its magnitude is the gap between them.
+///
+/// If the centers coincide, there's no line between them, so we fall back to the same default
+/// direction that `NormalizeXyz::normalize` uses for zero vectors, pushing the larger sphere along it
+/// and the smaller sphere against it. This keeps A against B the mirror of B against A, except for
+/// two identical spheres, which can't be told apart.
 fn sphere_sphere_collision_points(
     sphere_1_center: &XYZ,
     sphere_1_radius: &f32,
     sphere_2_center: &XYZ,
     sphere_2_radius: &f32,
 ) -> CollisionPoints {
-    let larger_radius = f32::max(*sphere_1_radius, *sphere_2_radius);
-    let smaller_radius = f32::min(*sphere_1_radius, *sphere_2_radius);
+    let center_offset = *sphere_1_center - *sphere_2_center;
+    let distance = center_offset.magnitude();
+    let radius_sum = sphere_1_radius + sphere_2_radius;
 
-    let x_collision = (sphere_1_center.x - sphere_2_center.x) < larger_radius;
-    let y_collision = (sphere_1_center.y - sphere_2_center.y) < larger_radius;
-    let z_collision = (sphere_1_center.z - sphere_2_center.z) < larger_radius;
+    let mut normal = center_offset.normalize();
+    if distance == 0.0 && sphere_1_radius < sphere_2_radius {
+        normal = -normal;
+    }
+    // Furthest point of sphere 1 into sphere 2, and vice versa
+    let a = *sphere_1_center - normal * *sphere_1_radius;
+    let b = *sphere_2_center + normal * *sphere_2_radius;
 
-    let s1_furthest_x_into_s2 = if x_collision && sphere_1_center.x <= sphere_2_center.x {
-        f32::min(sphere_1_center.x + smaller_radius, sphere_2_center.x)
-    } else if x_collision && sphere_1_center.x > sphere_2_center.x {
-        f32::max(sphere_1_center.x - smaller_radius, sphere_2_center.x)
-    } else {
-        0.0
+    return CollisionPoints {
+        a,
+        b,
+        normal,
+        depth: radius_sum - distance,
+        has_collision: distance <= radius_sum,
     };
+}
+
+/// Returns the CollisionPoints for the intersection of any bounded convex shape (A) and a plane (B).
+///
+/// Like `plane_sphere_collision_points`, the plane is treated as infinitely thin, so the shape
+/// collides if it has points on or either side of it. The shape is pushed out along the plane's
+/// normal, by the depth of its deepest point.
+fn convex_plane_collision_points(
+    shape: &dyn Support,
+    plane_normal: XYZ,
+    plane_distance: f32,
+) -> CollisionPoints {
+    let p_normal = plane_normal.normalize();
+    let deepest = shape.support(-p_normal);
+    let min_distance = deepest.dot(p_normal) - plane_distance;
+    let max_distance = shape.support(p_normal).dot(p_normal) - plane_distance;
+    return CollisionPoints {
+        a: deepest,
+        b: deepest - p_normal * min_distance,
+        normal: p_normal,
+        depth: -min_distance,
+        has_collision: min_distance <= 0.0 && max_distance >= 0.0,
+    };
+}
 
-    let s1_furthest_y_into_s2 = if y_collision && sphere_1_center.y <= sphere_2_center.y {
-        f32::min(sphere_1_center.y + smaller_radius, sphere_2_center.y)
-    } else if y_collision && sphere_1_center.y > sphere_2_center.y {
-        f32::max(sphere_1_center.y - smaller_radius, sphere_2_center.y)
+/// Returns the point on the segment from `start` to `end` which is closest to `point`
+fn segment_closest_point(start: XYZ, end: XYZ, point: XYZ) -> XYZ {
+    let direction = end - start;
+    let length_squared = direction.dot(direction);
+    if length_squared <= f32::EPSILON {
+        return start;
+    }
+    let t = ((point - start).dot(direction) / length_squared).clamp(0.0, 1.0);
+    return start + direction * t;
+}
+
+/// Returns the closest pair of points between two segments, one on each segment. Where there are
+/// many such pairs, e.g. for parallel segments, any of them may be returned.
+///
+/// See Ericson, Real-Time Collision Detection, section 5.1.9.
+fn segment_segment_closest_points(
+    start_1: XYZ,
+    end_1: XYZ,
+    start_2: XYZ,
+    end_2: XYZ,
+) -> (XYZ, XYZ) {
+    let d1 = end_1 - start_1;
+    let d2 = end_2 - start_2;
+    let r = start_1 - start_2;
+    let a = d1.dot(d1);
+    let e = d2.dot(d2);
+    let f = d2.dot(r);
+
+    // Either segment may be degenerate, i.e. a point
+    if a <= f32::EPSILON && e <= f32::EPSILON {
+        return (start_1, start_2);
+    }
+    if a <= f32::EPSILON {
+        return (start_1, segment_closest_point(start_2, end_2, start_1));
+    }
+    let c = d1.dot(r);
+    if e <= f32::EPSILON {
+        return (segment_closest_point(start_1, end_1, start_2), start_2);
+    }
+
+    let b = d1.dot(d2);
+    let denominator = a * e - b * b;
+    // Closest point on the first segment's line to the second's, or any point if they're parallel
+    let mut s = if denominator > f32::EPSILON {
+        ((b * f - c * e) / denominator).clamp(0.0, 1.0)
     } else {
         0.0
     };
+    let mut t = (b * s + f) / e;
+    // If that's beyond the end of the second segment, clamp it, and find the first segment's point again
+    if t < 0.0 {
+        t = 0.0;
+        s = (-c / a).clamp(0.0, 1.0);
+    } else if t > 1.0 {
+        t = 1.0;
+        s = ((b - c) / a).clamp(0.0, 1.0);
+    }
+    return (start_1 + d1 * s, start_2 + d2 * t);
+}
 
-    let s1_furthest_z_into_s2 = if z_collision && sphere_1_center.z <= sphere_2_center.z {
-        f32::min(sphere_1_center.z + smaller_radius, sphere_2_center.z)
-    } else if z_collision && sphere_1_center.z > sphere_2_center.z {
-        f32::max(sphere_1_center.z - smaller_radius, sphere_2_center.z)
+/// Returns the CollisionPoints for the intersection of a capsule (A) and a plane (B).
+///
+/// The capsule's deepest point is around whichever end of its segment is deeper, so it's pushed out
+/// like a sphere at that end. Like `plane_sphere_collision_points`, the plane is treated as infinitely
+/// thin, so the capsule collides if any part of it is on the plane, from either side.
+fn capsule_plane_collision_points(
+    start: XYZ,
+    end: XYZ,
+    radius: f32,
+    plane_normal: XYZ,
+    plane_distance: f32,
+) -> CollisionPoints {
+    let p_normal = plane_normal.normalize();
+    let start_distance = start.dot(p_normal) - plane_distance;
+    let end_distance = end.dot(p_normal) - plane_distance;
+    let (deepest, min_distance) = if end_distance < start_distance {
+        (end, end_distance)
     } else {
-        0.0
+        (start, start_distance)
     };
+    let max_distance = f32::max(start_distance, end_distance);
 
-    let s2_furthest_x_into_s1 = if x_collision && sphere_2_center.x <= sphere_1_center.x {
-        f32::min(sphere_2_center.x + smaller_radius, sphere_1_center.x)
-    } else if x_collision && sphere_2_center.x > sphere_1_center.x {
-        f32::max(sphere_2_center.x - smaller_radius, sphere_1_center.x)
+    return CollisionPoints {
+        a: deepest - p_normal * radius,
+        b: deepest - p_normal * min_distance,
+        normal: p_normal,
+        depth: radius - min_distance,
+        has_collision: min_distance <= radius && max_distance >= -radius,
+    };
+}
+
+// Iterations used to find the closest points between a capsule's segment and a box. The search
+// converges quickly, and a capsule is usually short relative to the boxes it touches.
+const CAPSULE_BOX_ITERATIONS: usize = 8;
+
+/// Returns the CollisionPoints for the intersection of a capsule (A) and a box (B).
+///
+/// We find the point on the capsule's segment closest to the box by alternately projecting onto the
+/// segment and the box, then collide a sphere of the capsule's radius at that point with the box.
+fn capsule_box_collision_points(
+    start: XYZ,
+    end: XYZ,
+    radius: f32,
+    oriented_box: &OrientedBox,
+) -> CollisionPoints {
+    let mut on_segment = segment_closest_point(start, end, oriented_box.center);
+    for _ in 0..CAPSULE_BOX_ITERATIONS {
+        let on_box = oriented_box.closest_point(on_segment);
+        on_segment = segment_closest_point(start, end, on_box);
+    }
+    return sphere_box_collision_points(on_segment, radius, oriented_box);
+}
+
+/// Returns u v^T, the matrix whose (i, j)th entry is u_i * v_j
+fn outer(u: XYZ, v: XYZ) -> Mat3 {
+    let u = glam::Vec3::from(u);
+    return Mat3::from_cols(u * v.x, u * v.y, u * v.z);
+}
+
+/// Moves an inertia tensor from an object's center of mass to a point `offset` away from it, by the
+/// parallel axis theorem
+pub(crate) fn parallel_axis(inertia: Mat3, mass: f32, offset: XYZ) -> Mat3 {
+    return inertia
+        + (Mat3::from_diagonal(glam::Vec3::splat(offset.dot(offset))) - outer(offset, offset))
+            * mass;
+}
+
+/// Returns the inertia tensor of an object from the second moment of its mass, i.e. the sum of
+/// m x x^T over all its mass, measured from the point it spins about
+fn inertia_from_second_moment(second_moment: Mat3) -> Mat3 {
+    let trace = second_moment.x_axis.x + second_moment.y_axis.y + second_moment.z_axis.z;
+    return Mat3::from_diagonal(glam::Vec3::splat(trace)) - second_moment;
+}
+
+fn box_inertia(mass: f32, half_extents: XYZ, rotation: Quat) -> Mat3 {
+    let (x2, y2, z2) = (
+        half_extents.x * half_extents.x,
+        half_extents.y * half_extents.y,
+        half_extents.z * half_extents.z,
+    );
+    let local = Mat3::from_diagonal(glam::Vec3::new(y2 + z2, x2 + z2, x2 + y2) * (mass / 3.0));
+    let rotation = Mat3::from_quat(rotation);
+    return rotation * local * rotation.transpose();
+}
+
+/// The inertia of a cylinder with a hemisphere at each end, each taking a share of the mass in
+/// proportion to its volume. Across the axis, each hemisphere is offset from the capsule's center,
+/// which the parallel axis theorem accounts for.
+fn capsule_inertia(mass: f32, start: XYZ, end: XYZ, radius: f32) -> Mat3 {
+    let length = (end - start).magnitude();
+    let cylinder_volume = std::f32::consts::PI * radius * radius * length;
+    let total_volume = cylinder_volume + sphere_volume(radius);
+    // Without any volume, it's a thin rod
+    let cylinder_mass = if total_volume > 0.0 {
+        mass * cylinder_volume / total_volume
     } else {
-        0.0
+        mass
     };
+    let spheres_mass = mass - cylinder_mass;
+    let r2 = radius * radius;
 
-    let s2_furthest_y_into_s1 = if y_collision && sphere_2_center.y <= sphere_1_center.y {
-        f32::min(sphere_2_center.y + smaller_radius, sphere_1_center.y)
-    } else if y_collision && sphere_2_center.y > sphere_1_center.y {
-        f32::max(sphere_2_center.y - smaller_radius, sphere_1_center.y)
+    let about_axis = cylinder_mass * r2 / 2.0 + spheres_mass * 0.4 * r2;
+    let across_axis = cylinder_mass * (r2 / 4.0 + length * length / 12.0)
+        + spheres_mass * (0.4 * r2 + length * length / 4.0 + 3.0 * length * radius / 8.0);
+    let axis = if length > 0.0 {
+        (end - start) * (1.0 / length)
     } else {
-        0.0
+        XYZ {
+            x: 1.0,
+            y: 0.0,
+            z: 0.0,
+        }
     };
+    let along = outer(axis, axis);
+    return (Mat3::IDENTITY - along) * across_axis + along * about_axis;
+}
 
-    let s2_furthest_z_into_s1 = if z_collision && sphere_2_center.z <= sphere_1_center.z {
-        f32::min(sphere_2_center.z + smaller_radius, sphere_1_center.z)
-    } else if z_collision && sphere_2_center.z > sphere_1_center.z {
-        f32::max(sphere_2_center.z - smaller_radius, sphere_1_center.z)
+/// The inertia of a thin plate. Each triangle of the plate contributes A/12 (a a^T + b b^T + c c^T
+/// + s s^T) to the second moment of its area, where s = a + b + c.
+fn polygon_inertia(mass: f32, vertices: &[XYZ]) -> Mat3 {
+    let (area, centroid) = polygon_area_and_centroid(vertices);
+    if area <= 0.0 {
+        return Mat3::ZERO;
+    }
+    let mut second_moment = Mat3::ZERO;
+    for idx in 1..vertices.len() - 1 {
+        let (a, b, c) = (
+            vertices[0] - centroid,
+            vertices[idx] - centroid,
+            vertices[idx + 1] - centroid,
+        );
+        let triangle_area = 0.5 * (b - a).cross(c - a).magnitude();
+        let s = a + b + c;
+        second_moment +=
+            (outer(a, a) + outer(b, b) + outer(c, c) + outer(s, s)) * (triangle_area / 12.0);
+    }
+    return inertia_from_second_moment(second_moment * (mass / area));
+}
+
+/// The inertia of a solid hull. Each tetrahedron joining the centroid to a face contributes
+/// V/20 (a a^T + b b^T + c c^T + s s^T) to the second moment of its volume, where s = a + b + c.
+fn hull_inertia(mass: f32, vertices: &[XYZ]) -> Mat3 {
+    let (volume, centroid) = hull_volume_and_centroid(vertices);
+    if volume <= 0.0 {
+        return Mat3::ZERO;
+    }
+    let mut second_moment = Mat3::ZERO;
+    for [a, b, c] in hull_triangles(vertices) {
+        let (a, b, c) = (a - centroid, b - centroid, c - centroid);
+        let tetrahedron_volume = a.dot(b.cross(c)) / 6.0;
+        let s = a + b + c;
+        second_moment +=
+            (outer(a, a) + outer(b, b) + outer(c, c) + outer(s, s)) * (tetrahedron_volume / 20.0);
+    }
+    return inertia_from_second_moment(second_moment * (mass / volume));
+}
+
+fn sphere_volume(radius: f32) -> f32 {
+    return 4.0 / 3.0 * std::f32::consts::PI * radius * radius * radius;
+}
+
+/// Returns the area and centroid of a convex polygon, by splitting it into a fan of triangles
+fn polygon_area_and_centroid(vertices: &[XYZ]) -> (f32, XYZ) {
+    let mut area = 0.0;
+    let mut weighted_centroid = XYZ::default();
+    for idx in 1..vertices.len().saturating_sub(1) {
+        let (a, b, c) = (vertices[0], vertices[idx], vertices[idx + 1]);
+        let triangle_area = 0.5 * (b - a).cross(c - a).magnitude();
+        area += triangle_area;
+        weighted_centroid += (a + b + c) * (triangle_area / 3.0);
+    }
+    if area <= 0.0 {
+        return (0.0, mean_point(vertices));
+    }
+    return (area, weighted_centroid * (1.0 / area));
+}
+
+/// Returns the volume and centroid of the convex hull of the vertices, by splitting it into
+/// tetrahedra, each joining a face of the hull to a point inside it
+fn hull_volume_and_centroid(vertices: &[XYZ]) -> (f32, XYZ) {
+    let inside = mean_point(vertices);
+    let mut volume = 0.0;
+    let mut weighted_centroid = XYZ::default();
+    for [a, b, c] in hull_triangles(vertices) {
+        let tetrahedron_volume = (a - inside).dot((b - inside).cross(c - inside)) / 6.0;
+        volume += tetrahedron_volume;
+        weighted_centroid += (inside + a + b + c) * (tetrahedron_volume / 4.0);
+    }
+    if volume <= 0.0 {
+        return (0.0, inside);
+    }
+    return (volume, weighted_centroid * (1.0 / volume));
+}
+
+fn mean_point(points: &[XYZ]) -> XYZ {
+    let mut sum = XYZ::default();
+    for p in points {
+        sum += *p;
+    }
+    return sum * (1.0 / points.len().max(1) as f32);
+}
+
+/// Returns the faces of the convex hull of the points, split into triangles whose vertices are
+/// counterclockwise when seen from outside the hull.
+///
+/// Any plane through 3 of the points which has all the points on one side of it contains a face of
+/// the hull, so we simply try every such plane. This takes O(n^4) time, which is fine for hulls of
+/// a few dozen points, but no more.
+fn hull_triangles(points: &[XYZ]) -> Vec<[XYZ; 3]> {
+    let extent = points
+        .iter()
+        .map(|p| (*p - points[0]).magnitude())
+        .fold(0.0, f32::max);
+    let tolerance = 1e-5 * f32::max(extent, 1.0);
+
+    let mut triangles = Vec::new();
+    let mut found_faces: Vec<Vec<usize>> = Vec::new();
+    for i in 0..points.len() {
+        for j in (i + 1)..points.len() {
+            for k in (j + 1)..points.len() {
+                let normal = (points[j] - points[i]).cross(points[k] - points[i]);
+                if normal.magnitude() <= tolerance * tolerance {
+                    continue;
+                }
+                let mut normal = normal.normalize();
+                let distances: Vec<f32> = points
+                    .iter()
+                    .map(|p| (*p - points[i]).dot(normal))
+                    .collect();
+                if distances.iter().all(|d| *d >= -tolerance) {
+                    normal = -normal;
+                } else if !distances.iter().all(|d| *d <= tolerance) {
+                    continue;
+                }
+
+                // Faces may have more than 3 points, so we find all of them, and skip the face if
+                // another 3 of them have already found it
+                let face: Vec<usize> = (0..points.len())
+                    .filter(|idx| distances[*idx].abs() <= tolerance)
+                    .collect();
+                if found_faces.contains(&face) {
+                    continue;
+                }
+
+                // Sort the face's points counterclockwise around its middle, then split it into a
+                // fan of triangles
+                let middle = mean_point(&face.iter().map(|idx| points[*idx]).collect::<Vec<_>>());
+                let u = (points[face[0]] - middle).normalize();
+                let v = normal.cross(u);
+                let angle = |idx: &usize| {
+                    let offset = points[*idx] - middle;
+                    return offset.dot(v).atan2(offset.dot(u));
+                };
+                let mut ordered = face.clone();
+                ordered.sort_by(|p, q| angle(p).total_cmp(&angle(q)));
+                for idx in 1..ordered.len() - 1 {
+                    triangles.push([
+                        points[ordered[0]],
+                        points[ordered[idx]],
+                        points[ordered[idx + 1]],
+                    ]);
+                }
+                found_faces.push(face);
+            }
+        }
+    }
+    return triangles;
+}
+
+/// Returns the area of a box's shadow on a plane perpendicular to the unit vector `direction`, which is
+/// the area of each face, scaled by how squarely it faces the direction
+fn box_cross_sectional_area(half_extents: XYZ, rotation: Quat, direction: XYZ) -> f32 {
+    let [x_axis, y_axis, z_axis] = box_axes(rotation);
+    let h = half_extents;
+    return 4.0
+        * (x_axis.dot(direction).abs() * h.y * h.z
+            + y_axis.dot(direction).abs() * h.x * h.z
+            + z_axis.dot(direction).abs() * h.x * h.y);
+}
+
+/// Returns the unit vectors along the X, Y and Z axes of a box rotated by `rotation`
+fn box_axes(rotation: Quat) -> [XYZ; 3] {
+    return [
+        XYZ::from(rotation * glam::Vec3::X),
+        XYZ::from(rotation * glam::Vec3::Y),
+        XYZ::from(rotation * glam::Vec3::Z),
+    ];
+}
+
+fn half_extent(half_extents: XYZ, axis: usize) -> f32 {
+    match axis {
+        0 => half_extents.x,
+        1 => half_extents.y,
+        _ => half_extents.z,
+    }
+}
+
+/// A box in world space, with its axes precomputed, for use in the box collision functions
+struct OrientedBox {
+    center: XYZ,
+    axes: [XYZ; 3],
+    half_extents: [f32; 3],
+}
+
+impl OrientedBox {
+    fn new(center: XYZ, half_extents: XYZ, rotation: Quat) -> OrientedBox {
+        return OrientedBox {
+            center,
+            axes: box_axes(rotation),
+            half_extents: [half_extents.x, half_extents.y, half_extents.z],
+        };
+    }
+
+    /// Half the length of the box's projection onto `axis`
+    fn projected_radius(&self, axis: XYZ) -> f32 {
+        return (0..3)
+            .map(|i| self.half_extents[i] * self.axes[i].dot(axis).abs())
+            .sum();
+    }
+
+    /// Returns the point of the box furthest in `direction`. Where a whole edge or face is
+    /// equally far, we return its center, so that the result doesn't depend on floating point noise.
+    fn support(&self, direction: XYZ) -> XYZ {
+        let mut point = self.center;
+        for i in 0..3 {
+            let alignment = self.axes[i].dot(direction);
+            if alignment.abs() > 1e-6 {
+                point += self.axes[i] * (self.half_extents[i] * alignment.signum());
+            }
+        }
+        return point;
+    }
+
+    /// Returns the point within the box closest to `point`, which is `point` itself if it's inside
+    fn closest_point(&self, point: XYZ) -> XYZ {
+        let offset = point - self.center;
+        let mut closest = self.center;
+        for i in 0..3 {
+            let h = self.half_extents[i];
+            closest += self.axes[i] * offset.dot(self.axes[i]).clamp(-h, h);
+        }
+        return closest;
+    }
+
+    fn corners(&self) -> Vec<XYZ> {
+        let mut corners = Vec::with_capacity(8);
+        for signs in 0..8 {
+            let mut corner = self.center;
+            for i in 0..3 {
+                let sign = if signs & (1 << i) == 0 { -1.0 } else { 1.0 };
+                corner += self.axes[i] * (self.half_extents[i] * sign);
+            }
+            corners.push(corner);
+        }
+        return corners;
+    }
+}
+
+/// Returns the CollisionPoints for the intersection of two boxes, using the separating axis
+/// theorem: two convex shapes are disjoint if and only if there's an axis onto which their
+/// projections don't overlap. For boxes, the only axes worth testing are each box's 3 face normals,
+/// and the 9 cross products of an axis of each box.
+///
+/// The normal is the axis with the least overlap, since that's the shortest way to separate the boxes.
+fn box_box_collision_points(box_1: &OrientedBox, box_2: &OrientedBox) -> CollisionPoints {
+    let mut candidate_axes: Vec<XYZ> = Vec::with_capacity(15);
+    candidate_axes.extend(box_1.axes);
+    candidate_axes.extend(box_2.axes);
+    for axis_1 in box_1.axes {
+        for axis_2 in box_2.axes {
+            let edge_axis = axis_1.cross(axis_2);
+            // Parallel axes have no cross product, and their face axes are already being tested
+            if edge_axis.magnitude() > 1e-4 {
+                candidate_axes.push(edge_axis.normalize());
+            }
+        }
+    }
+
+    let center_offset = box_1.center - box_2.center;
+    let mut best_axis = candidate_axes[0];
+    let mut best_overlap = f32::INFINITY;
+    for (idx, axis) in candidate_axes.iter().enumerate() {
+        let overlap = box_1.projected_radius(*axis) + box_2.projected_radius(*axis)
+            - center_offset.dot(*axis).abs();
+        // Edge axes (after the 6 face axes) must be noticeably better to be chosen, since face
+        // contacts are far more common, and give more stable normals
+        let bias = if idx < 6 { 0.0 } else { 1e-4 };
+        if overlap + bias < best_overlap {
+            best_overlap = overlap;
+            best_axis = *axis;
+        }
+    }
+
+    // Point the normal from box 2 towards box 1, i.e. the direction box 1 must move to separate
+    let normal = if center_offset.dot(best_axis) < 0.0 {
+        -best_axis
     } else {
-        0.0
+        best_axis
     };
 
-    let a = XYZ {
-        x: s1_furthest_x_into_s2,
-        y: s1_furthest_y_into_s2,
-        z: s1_furthest_z_into_s2,
+    return CollisionPoints {
+        a: box_1.support(-normal),
+        b: box_2.support(normal),
+        normal,
+        depth: best_overlap,
+        has_collision: best_overlap >= 0.0,
     };
-    let b = XYZ {
-        x: s2_furthest_x_into_s1,
-        y: s2_furthest_y_into_s1,
-        z: s2_furthest_z_into_s1,
+}
+
+/// Returns the CollisionPoints for the intersection of a sphere (A) and a box (B).
+///
+/// If the sphere's center is outside the box, the collision is along the line from the closest
+/// point on the box to the sphere's center. Otherwise, the sphere is pushed out through the
+/// nearest face.
+fn sphere_box_collision_points(
+    sphere_center: XYZ,
+    sphere_radius: f32,
+    oriented_box: &OrientedBox,
+) -> CollisionPoints {
+    let offset = sphere_center - oriented_box.center;
+    // The sphere center's coordinates along each of the box's axes
+    let local = [0, 1, 2].map(|i| offset.dot(oriented_box.axes[i]));
+    let inside = (0..3).all(|i| local[i].abs() <= oriented_box.half_extents[i]);
+
+    if !inside {
+        let closest = oriented_box.closest_point(sphere_center);
+        let separation = sphere_center - closest;
+        let distance = separation.magnitude();
+        let normal = separation.normalize();
+        return CollisionPoints {
+            a: sphere_center - normal * sphere_radius,
+            b: closest,
+            normal,
+            depth: sphere_radius - distance,
+            has_collision: distance <= sphere_radius,
+        };
+    }
+
+    // Find the face which the center is closest to
+    let mut nearest_axis = 0;
+    for i in 1..3 {
+        if oriented_box.half_extents[i] - local[i].abs()
+            < oriented_box.half_extents[nearest_axis] - local[nearest_axis].abs()
+        {
+            nearest_axis = i;
+        }
+    }
+    let side = if local[nearest_axis] < 0.0 { -1.0 } else { 1.0 };
+    let normal = oriented_box.axes[nearest_axis] * side;
+    let distance_to_face = oriented_box.half_extents[nearest_axis] - local[nearest_axis].abs();
+    return CollisionPoints {
+        a: sphere_center - normal * sphere_radius,
+        b: sphere_center + normal * distance_to_face,
+        normal,
+        depth: sphere_radius + distance_to_face,
+        has_collision: true,
     };
-    let diff = b - a;
-    let normal = diff.normalize();
-    let depth = diff.magnitude();
-    let has_collision = x_collision || y_collision || z_collision;
+}
 
-    // println!("xc={},yc={},zc={}", x_collision, y_collision, z_collision);
-    let res = CollisionPoints {
+/// Returns the CollisionPoints for the intersection of a box (A) and a plane (B).
+///
+/// Like `plane_sphere_collision_points`, the plane is treated as infinitely thin, so the box
+/// collides if it has corners on or either side of it. The box is pushed out along the plane's
+/// normal, by the depth of its deepest corner.
+fn box_plane_collision_points(
+    oriented_box: &OrientedBox,
+    plane_normal: XYZ,
+    plane_distance: f32,
+) -> CollisionPoints {
+    let p_normal = plane_normal.normalize();
+    // Signed distances of each corner from the plane
+    let corners = oriented_box.corners();
+    let distances: Vec<f32> = corners
+        .iter()
+        .map(|c| c.dot(p_normal) - plane_distance)
+        .collect();
+
+    let mut deepest = 0;
+    for (idx, distance) in distances.iter().enumerate() {
+        if *distance < distances[deepest] {
+            deepest = idx;
+        }
+    }
+    let min_distance = distances[deepest];
+    let max_distance = distances.iter().cloned().fold(f32::NEG_INFINITY, f32::max);
+
+    // Where a whole edge or face is equally deep, use its center
+    let a = oriented_box.support(-p_normal);
+    return CollisionPoints {
         a,
-        b,
-        normal,
-        depth,
-        has_collision,
+        b: a - p_normal * min_distance,
+        normal: p_normal,
+        depth: -min_distance,
+        has_collision: min_distance <= 0.0 && max_distance >= 0.0,
     };
-    println!("{}", res);
-    return res;
 }
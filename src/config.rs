@@ -0,0 +1,241 @@
+//! Runtime configuration for a simulation run
+use crate::broadphase::BroadphaseType;
+use crate::integrators::IntegratorType;
+use crate::material::{CombineRules, Material};
+use std::fmt;
+
+/// Everything that shapes a simulation run. Use `SimConfig::default()` for the standard arena, and
+/// override individual fields with struct update syntax, e.g.
+/// `SimConfig { fixed_timestep_hz: 60.0, ..SimConfig::default() }`.
+///
+/// Call `validate` before using a hand-built config, since nonsensical values (e.g. a negative bounce
+/// coefficient) will otherwise produce nonsensical simulations.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SimConfig {
+    pub screen_width: f32,
+    pub screen_height: f32,
+    // TODO: implement behavior for Z axis
+    // pub screen_depth: f32,
+    /// The default radius in pixels of a particle
+    pub particle_radius_px: f32,
+    pub pixels_per_meter: f32,
+    /// The downward acceleration in m/s^2 of the gravity which a world starts with, see `forces::Gravity`.
+    /// It only acts through the world's force generators, so replacing them removes it.
+    pub gravity_ms: f32,
+    /// The surface of the arena's edges. Its restitution must be within [0, 1).
+    pub arena_material: Material,
+    /// How the materials of two touching surfaces are combined
+    pub material_combine: CombineRules,
+    /// How many physics steps to run per simulated second when using a `FixedTimestep`
+    pub fixed_timestep_hz: f32,
+    /// The most physics steps a `FixedTimestep` will run for a single frame. Any time beyond that is
+    /// dropped, so that a slow frame can't snowball into ever slower frames.
+    pub max_substeps_per_frame: u32,
+    /// The numerical integrator used to advance velocities and positions each tick
+    pub integrator: IntegratorType,
+    /// How candidate pairs of colliding objects are found each tick
+    pub broadphase: BroadphaseType,
+    /// Seeds the world's random number generator, so that runs with random forces or thermostats
+    /// (e.g. `brownian::BrownianForce` or `thermostat::Langevin`) are reproducible
+    pub seed: u64,
+}
+
+impl Default for SimConfig {
+    fn default() -> Self {
+        return SimConfig {
+            screen_width: 1080.0,
+            screen_height: 720.0,
+            particle_radius_px: 10.0,
+            pixels_per_meter: 10.0,
+            gravity_ms: 9.8,
+            arena_material: Material::default(),
+            material_combine: CombineRules::default(),
+            fixed_timestep_hz: 120.0,
+            max_substeps_per_frame: 8,
+            integrator: IntegratorType::SemiImplicitEuler,
+            broadphase: BroadphaseType::SweepAndPrune,
+            seed: 0,
+        };
+    }
+}
+
+impl SimConfig {
+    /// Check for any unsupported parameter values that aren't obviously ridiculous.
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        let fields = [
+            ("screen_width", self.screen_width),
+            ("screen_height", self.screen_height),
+            ("particle_radius_px", self.particle_radius_px),
+            ("pixels_per_meter", self.pixels_per_meter),
+            ("gravity_ms", self.gravity_ms),
+            (
+                "arena_material.restitution",
+                self.arena_material.restitution,
+            ),
+            (
+                "arena_material.static_friction",
+                self.arena_material.static_friction,
+            ),
+            (
+                "arena_material.dynamic_friction",
+                self.arena_material.dynamic_friction,
+            ),
+            (
+                "arena_material.rolling_resistance",
+                self.arena_material.rolling_resistance,
+            ),
+            ("fixed_timestep_hz", self.fixed_timestep_hz),
+        ];
+        for (name, value) in fields {
+            if !value.is_finite() {
+                return Err(ConfigError::NonFiniteValue(name));
+            }
+        }
+
+        if self.screen_width <= 0.0 || self.screen_height <= 0.0 {
+            return Err(ConfigError::NonPositiveScreenSize {
+                width: self.screen_width,
+                height: self.screen_height,
+            });
+        }
+        // We use this as a divisor when converting pixels to meters
+        if self.pixels_per_meter <= 0.0 {
+            return Err(ConfigError::NonPositivePixelsPerMeter(
+                self.pixels_per_meter,
+            ));
+        }
+        if self.particle_radius_px <= 0.0 {
+            return Err(ConfigError::NonPositiveParticleRadius(
+                self.particle_radius_px,
+            ));
+        }
+        // A particle has to fit within the arena, otherwise it's permanently out of bounds
+        if 2.0 * self.particle_radius_px > f32::min(self.screen_width, self.screen_height) {
+            return Err(ConfigError::ParticleLargerThanScreen(
+                self.particle_radius_px,
+            ));
+        }
+        // A negative bounce coefficient makes no sense. Either an object bounces (val > 0) or doesn't (val == 0).
+        // A coefficient of 1 or more means infinite bouncing, which our bounce calculations can't terminate on.
+        let restitution = self.arena_material.restitution;
+        if !(0.0..1.0).contains(&restitution) {
+            return Err(ConfigError::BounceCoefficientOutOfRange(restitution));
+        }
+        // Negative friction or rolling resistance would speed objects up, rather than slowing them
+        let coefficients = [
+            (
+                "arena_material.static_friction",
+                self.arena_material.static_friction,
+            ),
+            (
+                "arena_material.dynamic_friction",
+                self.arena_material.dynamic_friction,
+            ),
+            (
+                "arena_material.rolling_resistance",
+                self.arena_material.rolling_resistance,
+            ),
+        ];
+        for (name, value) in coefficients {
+            if value < 0.0 {
+                return Err(ConfigError::NegativeFrictionCoefficient(name, value));
+            }
+        }
+        if self.fixed_timestep_hz <= 0.0 {
+            return Err(ConfigError::NonPositiveTimestepHz(self.fixed_timestep_hz));
+        }
+        // With no substeps allowed, the simulation would never advance
+        if self.max_substeps_per_frame == 0 {
+            return Err(ConfigError::ZeroMaxSubsteps);
+        }
+        if let BroadphaseType::UniformGrid { cell_size } = self.broadphase {
+            if !cell_size.is_finite() || cell_size <= 0.0 {
+                return Err(ConfigError::NonPositiveGridCellSize(cell_size));
+            }
+        }
+        return Ok(());
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConfigError {
+    NonFiniteValue(&'static str),
+    NonPositiveScreenSize { width: f32, height: f32 },
+    NonPositivePixelsPerMeter(f32),
+    NonPositiveParticleRadius(f32),
+    ParticleLargerThanScreen(f32),
+    BounceCoefficientOutOfRange(f32),
+    NegativeFrictionCoefficient(&'static str, f32),
+    NonPositiveTimestepHz(f32),
+    ZeroMaxSubsteps,
+    NonPositiveGridCellSize(f32),
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ConfigError::NonFiniteValue(name) => {
+                write!(f, "Error: config value {} must be a finite number", name)
+            }
+            ConfigError::NonPositiveScreenSize { width, height } => {
+                write!(
+                    f,
+                    "Error: screen size (W={},H={}) must be greater than 0",
+                    width, height
+                )
+            }
+            ConfigError::NonPositivePixelsPerMeter(value) => {
+                write!(
+                    f,
+                    "Error: pixels per meter ({}) must be greater than 0",
+                    value
+                )
+            }
+            ConfigError::NonPositiveParticleRadius(value) => {
+                write!(
+                    f,
+                    "Error: particle radius ({}) must be greater than 0",
+                    value
+                )
+            }
+            ConfigError::ParticleLargerThanScreen(value) => {
+                write!(
+                    f,
+                    "Error: particle radius ({}) is too large to fit on screen",
+                    value
+                )
+            }
+            ConfigError::BounceCoefficientOutOfRange(value) => {
+                write!(
+                    f,
+                    "Error: bounce coefficient ({}) must be within [0, 1)",
+                    value
+                )
+            }
+            ConfigError::NegativeFrictionCoefficient(name, value) => {
+                write!(
+                    f,
+                    "Error: config value {} ({}) must not be negative",
+                    name, value
+                )
+            }
+            ConfigError::NonPositiveTimestepHz(value) => {
+                write!(
+                    f,
+                    "Error: fixed timestep frequency ({}Hz) must be greater than 0",
+                    value
+                )
+            }
+            ConfigError::ZeroMaxSubsteps => {
+                write!(f, "Error: max substeps per frame must be at least 1")
+            }
+            ConfigError::NonPositiveGridCellSize(value) => {
+                write!(
+                    f,
+                    "Error: broadphase grid cell size ({}) must be greater than 0",
+                    value
+                )
+            }
+        }
+    }
+}
@@ -0,0 +1,186 @@
+//! Collision response between objects
+use crate::broadphase::{BoundingBox, Broadphase};
+use crate::colliders::{ColliderType, CollisionPoints, TestCollision};
+use crate::material::{CombineRules, Material};
+use crate::objects::Object;
+use crate::transform::Mat3;
+use crate::xyz::{CrossProductXyz, DotProductXyz, MagnitudeXyz, XYZ};
+use glam::Vec3;
+
+// The fraction of penetration depth corrected per tick. Correcting all of it at once tends to
+// make resting contacts jitter.
+const PENETRATION_CORRECTION_PERCENT: f32 = 0.8;
+// Penetration depth which we tolerate without correction, again to avoid jitter
+const PENETRATION_SLOP: f32 = 0.01;
+
+/// Test every pair of objects which the broadphase considers close enough to collide, and resolve
+/// any collisions that are found, so that momentum is transferred between the colliding objects.
+///
+/// * `objects` - the objects to test against one another.
+/// * `combine` - how the materials of each pair of colliding objects are combined, e.g. to find the
+///   bounciness of their collision.
+/// * `broadphase` - finds the candidate pairs of objects to test.
+pub fn resolve_contacts(
+    objects: &mut [Object],
+    combine: &CombineRules,
+    broadphase: &mut dyn Broadphase,
+) {
+    // Objects without a collider can't collide, so there's no need for the broadphase to see them
+    let colliding: Vec<usize> = (0..objects.len())
+        .filter(|idx| !matches!(objects[*idx].collider, ColliderType::NONE))
+        .collect();
+    let bounds: Vec<BoundingBox> = colliding
+        .iter()
+        .map(|idx| objects[*idx].world_collider().bounding_box())
+        .collect();
+    let mut pairs: Vec<(usize, usize)> = broadphase
+        .candidate_pairs(&bounds)
+        .into_iter()
+        .map(|(i, j)| (colliding[i], colliding[j]))
+        .collect();
+    // The broadphase doesn't guarantee any order, so sort the pairs to keep results deterministic
+    pairs.sort_unstable();
+
+    for (i, j) in pairs {
+        let points = objects[i]
+            .world_collider()
+            .test_collision(&objects[j].world_collider());
+        if !points.has_collision {
+            continue;
+        }
+        let material = objects[i].material.combine(&objects[j].material, combine);
+        // i < j, so object i is at the end of the left slice, and object j is at the start of the right slice
+        let (left, right) = objects.split_at_mut(j);
+        resolve_contact(&mut left[i], &mut right[0], &points, &material);
+    }
+}
+
+/// Resolve a single collision between A and B, where `points` is the result of testing A against B,
+/// and `material` is the combination of both objects' materials.
+///
+/// We apply equal and opposite impulses along the contact normal at the contact point, weighted by
+/// each object's inverse mass and inverse inertia, so that both momentum and angular momentum are
+/// conserved. Contacts which aren't in line with an object's position make it spin. Friction then
+/// applies a second impulse along the contact's surface, against the objects sliding over each
+/// other, which is limited by the normal impulse (see `coulomb_friction`), and rolling resistance
+/// applies an angular impulse against them rolling over each other, which is also limited by the
+/// normal impulse. Finally, we push the
+/// objects apart to correct their penetration, so that they don't sink into each other over time.
+pub fn resolve_contact(
+    a: &mut Object,
+    b: &mut Object,
+    points: &CollisionPoints,
+    material: &Material,
+) {
+    let inverse_mass_a = a.inverse_mass();
+    let inverse_mass_b = b.inverse_mass();
+    let inverse_mass_sum = inverse_mass_a + inverse_mass_b;
+    if inverse_mass_sum == 0.0 {
+        // Two immovable objects can't respond to a collision
+        return;
+    }
+    let inverse_inertia_a = a.inverse_inertia_tensor();
+    let inverse_inertia_b = b.inverse_inertia_tensor();
+    let normal = points.normal;
+
+    // The collision happens between the deepest points of each object, and each object is pushed
+    // at the contact point's offset from the point it rotates about
+    let contact = (points.a + points.b) * 0.5;
+    let offset_a = contact - a.transform.position;
+    let offset_b = contact - b.transform.position;
+    let point_velocity = |o: &Object, offset: XYZ| o.velocity + o.angular_velocity.cross(offset);
+
+    // The normal is the direction in which A must move to separate from B. If A is already moving
+    // that way relative to B, the objects are separating, and we shouldn't pull them back together.
+    let velocity_along_normal =
+        (point_velocity(a, offset_a) - point_velocity(b, offset_b)).dot(normal);
+    if velocity_along_normal < 0.0 {
+        // How much an impulse along a direction changes the contact's velocity along that
+        // direction, from each object's movement and spin
+        let effective_inverse_mass = |direction: XYZ| {
+            let spin_response = |inverse_inertia: Mat3, offset: XYZ| {
+                let spin = XYZ::from(inverse_inertia * Vec3::from(offset.cross(direction)));
+                return spin.cross(offset).dot(direction);
+            };
+            return inverse_mass_sum
+                + spin_response(inverse_inertia_a, offset_a)
+                + spin_response(inverse_inertia_b, offset_b);
+        };
+        let apply_impulse = |a: &mut Object, b: &mut Object, impulse: XYZ| {
+            a.velocity += impulse * inverse_mass_a;
+            b.velocity -= impulse * inverse_mass_b;
+            a.angular_velocity +=
+                XYZ::from(inverse_inertia_a * Vec3::from(offset_a.cross(impulse)));
+            b.angular_velocity -=
+                XYZ::from(inverse_inertia_b * Vec3::from(offset_b.cross(impulse)));
+        };
+
+        let normal_impulse =
+            -(1.0 + material.restitution) * velocity_along_normal / effective_inverse_mass(normal);
+        apply_impulse(a, b, normal * normal_impulse);
+
+        // Friction acts against whatever sliding is left after the bounce
+        let relative_velocity = point_velocity(a, offset_a) - point_velocity(b, offset_b);
+        let sliding = relative_velocity - normal * relative_velocity.dot(normal);
+        let sliding_speed = sliding.magnitude();
+        if sliding_speed > 0.0 {
+            let tangent = &sliding / sliding_speed;
+            // The impulse which would stop the sliding altogether
+            let stopping_impulse = &sliding / effective_inverse_mass(tangent);
+            let friction = coulomb_friction(stopping_impulse, normal_impulse, material);
+            apply_impulse(a, b, friction);
+        }
+
+        // Rolling resistance acts against the objects' relative spin about the axes along the
+        // contact surface, i.e. their rolling over each other, but not spinning in place
+        let relative_spin = a.angular_velocity - b.angular_velocity;
+        let rolling = relative_spin - normal * relative_spin.dot(normal);
+        let rolling_speed = rolling.magnitude();
+        if rolling_speed > 0.0 {
+            let axis = &rolling / rolling_speed;
+            let spin_response = |inverse_inertia: Mat3| {
+                return axis.dot(XYZ::from(inverse_inertia * Vec3::from(axis)));
+            };
+            let inverse_inertia_sum =
+                spin_response(inverse_inertia_a) + spin_response(inverse_inertia_b);
+            if inverse_inertia_sum > 0.0 {
+                // The resisting torque is the coefficient times the push, acting at the rolling
+                // radius, which is mostly that of the lighter object
+                let radius = (offset_a.magnitude() * inverse_mass_a
+                    + offset_b.magnitude() * inverse_mass_b)
+                    / inverse_mass_sum;
+                let resistance = material.rolling_resistance * normal_impulse * radius;
+                // Never more than it takes to stop the rolling altogether
+                let angular_impulse = axis * resistance.min(rolling_speed / inverse_inertia_sum);
+                a.angular_velocity -= XYZ::from(inverse_inertia_a * Vec3::from(angular_impulse));
+                b.angular_velocity += XYZ::from(inverse_inertia_b * Vec3::from(angular_impulse));
+            }
+        }
+    }
+
+    let correction = f32::max(points.depth - PENETRATION_SLOP, 0.0)
+        * PENETRATION_CORRECTION_PERCENT
+        / inverse_mass_sum;
+    a.transform.position += normal * (correction * inverse_mass_a);
+    b.transform.position -= normal * (correction * inverse_mass_b);
+}
+
+/// Returns the friction which acts on a contact, given how hard it would have to push to stop the
+/// surfaces sliding over each other, and how hard the contact pushes the surfaces apart. These can be
+/// impulses, or velocity changes for an object against something immovable, as long as both are in
+/// the same terms.
+///
+/// This is the Coulomb model: if stopping the sliding takes no more than the static friction
+/// coefficient times the push, the surfaces stick. Otherwise they slide, and dynamic friction takes
+/// the dynamic coefficient times the push off the sliding, at most.
+pub fn coulomb_friction(stopping: XYZ, normal: f32, material: &Material) -> XYZ {
+    let stopping_magnitude = stopping.magnitude();
+    if stopping_magnitude == 0.0 {
+        return XYZ::default();
+    }
+    if stopping_magnitude <= material.static_friction * normal {
+        return -stopping;
+    }
+    let sliding_friction = material.dynamic_friction * normal;
+    return stopping * -(sliding_friction / stopping_magnitude).min(1.0);
+}
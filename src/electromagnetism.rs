@@ -0,0 +1,107 @@
+//! Forces on electrically charged objects
+use crate::forces::ForceGenerator;
+use crate::objects::Object;
+use crate::xyz::{DotProductXyz, XYZ};
+
+/// The electrostatic force between every pair of charged objects, k q1 q2 / r^2, so that like charges
+/// repel and opposite charges attract. Every pair is summed directly, in O(n^2) time.
+pub struct CoulombForce {
+    // k, in N px^2 / C^2
+    pub coulomb_constant: f32,
+    // See `nbody::NBodyGravity::softening`
+    pub softening: f32,
+    // Pairs further apart than this exert no force on each other, which trades accuracy for speed when
+    // charges are screened, e.g. in an electrolyte. None means every pair interacts.
+    pub cutoff: Option<f32>,
+}
+
+impl ForceGenerator for CoulombForce {
+    fn accumulate(&mut self, objects: &[Object], forces: &mut [XYZ]) {
+        let charged: Vec<usize> = (0..objects.len())
+            .filter(|idx| objects[*idx].charge != 0.0)
+            .collect();
+        let cutoff_squared = self.cutoff.map(|cutoff| cutoff * cutoff);
+        for (n, i) in charged.iter().enumerate() {
+            for j in &charged[n + 1..] {
+                let (a, b) = (&objects[*i], &objects[*j]);
+                let offset = b.transform.position - a.transform.position;
+                let separation_squared = offset.dot(offset);
+                if let Some(cutoff_squared) = cutoff_squared {
+                    if separation_squared > cutoff_squared {
+                        continue;
+                    }
+                }
+                let distance_squared = separation_squared + self.softening * self.softening;
+                if distance_squared == 0.0 {
+                    continue;
+                }
+                // k q1 q2 / d^2, along the unit vector offset / d. This pushes B away from A when the
+                // charges have the same sign.
+                let scale = self.coulomb_constant * a.charge * b.charge
+                    / (distance_squared * distance_squared.sqrt());
+                forces[*i] -= offset * scale;
+                forces[*j] += offset * scale;
+            }
+        }
+    }
+}
+
+/// An electric field which is the same everywhere, e.g. between two large charged plates, which pushes
+/// each object with a force of qE
+pub struct UniformElectricField {
+    // E, in N / C
+    pub field: XYZ,
+}
+
+impl ForceGenerator for UniformElectricField {
+    fn accumulate(&mut self, objects: &[Object], forces: &mut [XYZ]) {
+        for (p, force) in objects.iter().zip(forces.iter_mut()) {
+            *force += self.field * p.charge;
+        }
+    }
+}
+
+/// A magnetic field, which bends the paths of moving charged objects with the Lorentz force q v x B.
+/// Fields are registered on a `World`, and unlike force generators, they're handed to the integrator
+/// separately, so that e.g. `Boris` can rotate velocities about them exactly.
+pub trait MagneticField {
+    /// Returns the field B at `position`, in Tesla
+    fn field_at(&self, position: XYZ) -> XYZ;
+}
+
+/// Any closure from a position to the field there is a magnetic field, for fields which vary through
+/// space, e.g. a magnetic bottle
+impl<F> MagneticField for F
+where
+    F: Fn(XYZ) -> XYZ,
+{
+    fn field_at(&self, position: XYZ) -> XYZ {
+        return self(position);
+    }
+}
+
+/// A magnetic field which is the same everywhere, e.g. inside a long solenoid
+pub struct UniformMagneticField {
+    // B, in Tesla
+    pub field: XYZ,
+}
+
+impl MagneticField for UniformMagneticField {
+    fn field_at(&self, _position: XYZ) -> XYZ {
+        return self.field;
+    }
+}
+
+/// Returns the total field of `fields` at each object's position, in the same order as `objects`
+pub fn magnetic_fields_at(fields: &[Box<dyn MagneticField>], objects: &[Object]) -> Vec<XYZ> {
+    return objects
+        .iter()
+        .map(|p| {
+            let mut total = XYZ::default();
+            for field in fields {
+                total += field.field_at(p.transform.position);
+            }
+            return total;
+        })
+        .collect();
+}
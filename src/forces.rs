@@ -0,0 +1,195 @@
+//! Force generators, which push objects around each tick, e.g. gravity, drag and springs
+use crate::config::SimConfig;
+use crate::objects::Object;
+use crate::random::Rng;
+use crate::xyz::{DotProductXyz, MagnitudeXyz, XYZ};
+
+/// Exerts forces on objects. Generators are registered on a `World`, and each tick, the forces of
+/// every generator are added to each object's own `force`, so that new physics can be added without
+/// touching the tick itself.
+///
+/// Integrators may ask for forces several times per tick with intermediate states, e.g. to evaluate
+/// a spring at the middle of the tick, so generators should only depend on the objects they're given.
+pub trait ForceGenerator {
+    /// Adds the force which this generator exerts on each object to that object's entry in `forces`,
+    /// which is in the same order as `objects`.
+    fn accumulate(&mut self, objects: &[Object], forces: &mut [XYZ]);
+
+    /// Called once at the start of every tick, before any forces are accumulated, with the world's
+    /// random number generator. Random forces should draw their randomness here, so that it stays the
+    /// same however many times the integrator asks for forces during the tick.
+    fn start_tick(&mut self, _objects: &[Object], _time_elapsed_seconds: f32, _rng: &mut Rng) {}
+}
+
+/// Any closure over the objects and their forces is a force generator, for one-off forces
+impl<F> ForceGenerator for F
+where
+    F: FnMut(&[Object], &mut [XYZ]),
+{
+    fn accumulate(&mut self, objects: &[Object], forces: &mut [XYZ]) {
+        self(objects, forces);
+    }
+}
+
+/// Returns the force generators which a world starts with, i.e. the config's downward gravity
+pub fn default_force_generators(config: &SimConfig) -> Vec<Box<dyn ForceGenerator>> {
+    return vec![Box::new(Gravity::from_config(config))];
+}
+
+/// Accelerates every object equally, in any direction
+pub struct Gravity {
+    // The acceleration, in the same units as `SimConfig::gravity_ms`
+    pub acceleration: XYZ,
+}
+
+impl Gravity {
+    /// Returns gravity pulling down the screen, as strong as the config's `gravity_ms`
+    pub fn from_config(config: &SimConfig) -> Gravity {
+        return Gravity {
+            acceleration: XYZ {
+                x: 0.0,
+                y: config.gravity_ms,
+                z: 0.0,
+            },
+        };
+    }
+}
+
+impl ForceGenerator for Gravity {
+    fn accumulate(&mut self, objects: &[Object], forces: &mut [XYZ]) {
+        for (p, force) in objects.iter().zip(forces.iter_mut()) {
+            // Immovable objects would need an infinite force, which they'd ignore anyway
+            if p.inverse_mass() == 0.0 {
+                continue;
+            }
+            *force += self.acceleration * p.mass;
+        }
+    }
+}
+
+/// Slows every object in proportion to its speed, i.e. F = -kv, as for small objects moving slowly
+/// through a thick fluid
+pub struct LinearDrag {
+    pub coefficient: f32,
+}
+
+impl ForceGenerator for LinearDrag {
+    fn accumulate(&mut self, objects: &[Object], forces: &mut [XYZ]) {
+        for (p, force) in objects.iter().zip(forces.iter_mut()) {
+            *force -= p.velocity * self.coefficient;
+        }
+    }
+}
+
+/// Slows every object in proportion to the square of its speed, i.e. F = -k|v|v, as for large or
+/// fast objects moving through air
+pub struct QuadraticDrag {
+    pub coefficient: f32,
+}
+
+impl ForceGenerator for QuadraticDrag {
+    fn accumulate(&mut self, objects: &[Object], forces: &mut [XYZ]) {
+        for (p, force) in objects.iter().zip(forces.iter_mut()) {
+            *force -= p.velocity * (self.coefficient * p.velocity.magnitude());
+        }
+    }
+}
+
+/// The drag on objects moving through a still fluid such as air or water, which depends on each
+/// object's shape, i.e. its cross-section in the direction it's moving and its drag coefficient.
+///
+/// This is the sum of two forces which oppose the object's velocity:
+/// * Linear (Stokes) drag, 6 pi mu r v, which dominates for small, slow objects in a viscous fluid.
+///   This is exact for a sphere of radius r, and we treat other shapes as a sphere with the same
+///   cross-section.
+/// * Quadratic drag, 0.5 rho Cd A v^2, which dominates for large, fast objects.
+///
+/// Either can be turned off by setting its fluid property to 0.
+pub struct AerodynamicDrag {
+    // rho, in kilograms per cubic pixel
+    pub fluid_density: f32,
+    // mu, the fluid's dynamic viscosity, in kilograms per pixel second
+    pub viscosity: f32,
+}
+
+impl ForceGenerator for AerodynamicDrag {
+    fn accumulate(&mut self, objects: &[Object], forces: &mut [XYZ]) {
+        for (p, force) in objects.iter().zip(forces.iter_mut()) {
+            let speed = p.velocity.magnitude();
+            if speed == 0.0 || p.inverse_mass() == 0.0 {
+                continue;
+            }
+            let area = p.world_collider().cross_sectional_area(p.velocity);
+            if !area.is_finite() {
+                continue;
+            }
+            let radius = (area / std::f32::consts::PI).sqrt();
+            let linear = 6.0 * std::f32::consts::PI * self.viscosity * radius;
+            let quadratic = 0.5 * self.fluid_density * p.drag_coefficient * area * speed;
+            *force -= p.velocity * (linear + quadratic);
+        }
+    }
+}
+
+/// Pulls every object towards a fixed point, with an inverse square law like gravity's. A negative
+/// strength pushes objects away instead.
+pub struct PointAttractor {
+    pub position: XYZ,
+    // The acceleration of an object one unit away from the point
+    pub strength: f32,
+    // Stops the pull growing without bound as an object reaches the point, by acting as if each
+    // object were always at least this far from it
+    pub softening: f32,
+}
+
+impl ForceGenerator for PointAttractor {
+    fn accumulate(&mut self, objects: &[Object], forces: &mut [XYZ]) {
+        for (p, force) in objects.iter().zip(forces.iter_mut()) {
+            if p.inverse_mass() == 0.0 {
+                continue;
+            }
+            let offset = self.position - p.transform.position;
+            let distance_squared = offset.dot(offset) + self.softening * self.softening;
+            if distance_squared == 0.0 {
+                continue;
+            }
+            // strength / d^2, along the unit vector offset / d
+            let scale = self.strength * p.mass / (distance_squared * distance_squared.sqrt());
+            *force += offset * scale;
+        }
+    }
+}
+
+/// Connects two objects, given by their index, with a damped spring which pulls them towards its
+/// rest length. Springs between objects which don't exist exert no force.
+pub struct Spring {
+    pub a: usize,
+    pub b: usize,
+    pub rest_length: f32,
+    // The force per unit of stretch, in Newtons per pixel
+    pub stiffness: f32,
+    // The force per unit of speed at which the ends move apart, which stops the spring oscillating forever
+    pub damping: f32,
+}
+
+impl ForceGenerator for Spring {
+    fn accumulate(&mut self, objects: &[Object], forces: &mut [XYZ]) {
+        let (a, b) = match (objects.get(self.a), objects.get(self.b)) {
+            (Some(a), Some(b)) if self.a != self.b => (a, b),
+            _ => return,
+        };
+        let offset = b.transform.position - a.transform.position;
+        let length = offset.magnitude();
+        if length == 0.0 {
+            // The spring has no direction to push in
+            return;
+        }
+        let direction = &offset / length;
+        let stretch = length - self.rest_length;
+        let separating_speed = (b.velocity - a.velocity).dot(direction);
+        // Positive tension pulls the ends together
+        let tension = self.stiffness * stretch + self.damping * separating_speed;
+        forces[self.a] += direction * tension;
+        forces[self.b] -= direction * tension;
+    }
+}
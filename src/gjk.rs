@@ -0,0 +1,526 @@
+//! Collision detection between arbitrary convex shapes, which only need to describe themselves
+//! through a support function (see `Support`), so that new shapes don't need a bespoke collision
+//! function for every other shape.
+//!
+//! Two shapes A and B overlap if and only if their Minkowski difference A - B (every point of A
+//! minus every point of B) contains the origin. GJK searches the Minkowski difference for its
+//! point closest to the origin, which gives the distance between the shapes, or shows that they
+//! overlap. If they overlap, EPA expands GJK's final simplex outwards until it finds the surface of
+//! the Minkowski difference closest to the origin, which gives the penetration depth and normal.
+//!
+//! See Ericson, Real-Time Collision Detection, section 9.5, and van den Bergen, Collision Detection
+//! in Interactive 3D Environments.
+use crate::colliders::CollisionPoints;
+use crate::xyz::{CrossProductXyz, DotProductXyz, MagnitudeXyz, NormalizeXyz, XYZ};
+
+/// A convex shape, described by its support function
+pub trait Support {
+    /// Returns the point of the shape furthest along `direction`, which need not be normalized.
+    /// Where several points are equally far, any of them may be returned.
+    fn support(&self, direction: XYZ) -> XYZ;
+}
+
+// Upper bound on GJK and EPA iterations. Both usually converge in far fewer, but curved shapes
+// (e.g. spheres) are only ever approximated, so could otherwise keep refining forever.
+const MAX_ITERATIONS: usize = 64;
+// Convergence tolerance, relative to the size of the Minkowski difference
+const RELATIVE_TOLERANCE: f32 = 1e-5;
+
+/// A point of the Minkowski difference, along with the points of A and B it was made from, so that
+/// we can recover the contact points on each shape
+#[derive(Debug, Copy, Clone)]
+struct Vertex {
+    point: XYZ,
+    a: XYZ,
+    b: XYZ,
+}
+
+fn minkowski_support(a: &dyn Support, b: &dyn Support, direction: XYZ) -> Vertex {
+    let on_a = a.support(direction);
+    let on_b = b.support(-direction);
+    return Vertex {
+        point: on_a - on_b,
+        a: on_a,
+        b: on_b,
+    };
+}
+
+/// Returns the CollisionPoints for two convex shapes, following the same conventions as
+/// `TestCollision`. When the shapes don't overlap, `a` and `b` are the closest points between
+/// them, and the depth is negative, with its magnitude being the distance between them.
+pub fn gjk_epa_collision_points(a: &dyn Support, b: &dyn Support) -> CollisionPoints {
+    let first = minkowski_support(
+        a,
+        b,
+        XYZ {
+            x: 1.0,
+            y: 0.0,
+            z: 0.0,
+        },
+    );
+    let mut scale = f32::max(first.point.magnitude(), 1.0);
+    let mut simplex = vec![first];
+
+    let mut closest = first.point;
+    let mut weights = vec![1.0];
+    for _ in 0..MAX_ITERATIONS {
+        let tolerance = RELATIVE_TOLERANCE * scale;
+        (closest, simplex, weights) = closest_point_on_simplex(&simplex, tolerance);
+        let distance = closest.magnitude();
+        if distance <= tolerance {
+            return epa(a, b, simplex, scale);
+        }
+
+        let vertex = minkowski_support(a, b, -closest);
+        scale = f32::max(scale, vertex.point.magnitude());
+        // If the new support point gets us no closer to the origin, we've found the closest point
+        let progress = distance - closest.dot(vertex.point) / distance;
+        let is_duplicate = simplex
+            .iter()
+            .any(|v| (v.point - vertex.point).magnitude() <= tolerance);
+        if progress <= RELATIVE_TOLERANCE * scale || is_duplicate {
+            break;
+        }
+        simplex.push(vertex);
+    }
+    return separated_collision_points(closest, &simplex, &weights);
+}
+
+fn separated_collision_points(
+    closest: XYZ,
+    simplex: &[Vertex],
+    weights: &[f32],
+) -> CollisionPoints {
+    let (a, b) = interpolate_shape_points(simplex, weights);
+    let distance = closest.magnitude();
+    return CollisionPoints {
+        a,
+        b,
+        // `closest` is A's closest point minus B's, so A separates further by moving along it
+        normal: closest.normalize(),
+        depth: -distance,
+        has_collision: false,
+    };
+}
+
+/// Returns the points on A and B corresponding to the given weighted sum of Minkowski vertices
+fn interpolate_shape_points(vertices: &[Vertex], weights: &[f32]) -> (XYZ, XYZ) {
+    let mut a = XYZ::default();
+    let mut b = XYZ::default();
+    for (vertex, weight) in vertices.iter().zip(weights) {
+        a += vertex.a * *weight;
+        b += vertex.b * *weight;
+    }
+    return (a, b);
+}
+
+/// Returns the point of the simplex closest to the origin, along with the smallest sub-simplex
+/// containing that point, and the weights of that sub-simplex's vertices which sum to that point
+fn closest_point_on_simplex(simplex: &[Vertex], tolerance: f32) -> (XYZ, Vec<Vertex>, Vec<f32>) {
+    let points: Vec<XYZ> = simplex.iter().map(|v| v.point).collect();
+    let (closest, weights) = match points.len() {
+        1 => (points[0], vec![1.0]),
+        2 => closest_point_on_segment(points[0], points[1]),
+        3 => closest_point_on_triangle(points[0], points[1], points[2]),
+        _ => closest_point_on_tetrahedron(&points, tolerance),
+    };
+
+    // Drop the vertices which don't contribute to the closest point
+    let mut reduced_simplex = Vec::with_capacity(simplex.len());
+    let mut reduced_weights = Vec::with_capacity(simplex.len());
+    for (vertex, weight) in simplex.iter().zip(weights) {
+        if weight > 0.0 {
+            reduced_simplex.push(*vertex);
+            reduced_weights.push(weight);
+        }
+    }
+    return (closest, reduced_simplex, reduced_weights);
+}
+
+fn closest_point_on_segment(a: XYZ, b: XYZ) -> (XYZ, Vec<f32>) {
+    let ab = b - a;
+    let length_squared = ab.dot(ab);
+    if length_squared <= f32::EPSILON {
+        return (a, vec![1.0, 0.0]);
+    }
+    let t = (-a.dot(ab) / length_squared).clamp(0.0, 1.0);
+    return (a + ab * t, vec![1.0 - t, t]);
+}
+
+/// See Ericson, Real-Time Collision Detection, section 5.1.5
+fn closest_point_on_triangle(a: XYZ, b: XYZ, c: XYZ) -> (XYZ, Vec<f32>) {
+    let ab = b - a;
+    let ac = c - a;
+
+    // In vertex region A
+    let d1 = ab.dot(-a);
+    let d2 = ac.dot(-a);
+    if d1 <= 0.0 && d2 <= 0.0 {
+        return (a, vec![1.0, 0.0, 0.0]);
+    }
+    // In vertex region B
+    let d3 = ab.dot(-b);
+    let d4 = ac.dot(-b);
+    if d3 >= 0.0 && d4 <= d3 {
+        return (b, vec![0.0, 1.0, 0.0]);
+    }
+    // In edge region AB
+    let vc = d1 * d4 - d3 * d2;
+    if vc <= 0.0 && d1 >= 0.0 && d3 <= 0.0 {
+        let v = d1 / (d1 - d3);
+        return (a + ab * v, vec![1.0 - v, v, 0.0]);
+    }
+    // In vertex region C
+    let d5 = ab.dot(-c);
+    let d6 = ac.dot(-c);
+    if d6 >= 0.0 && d5 <= d6 {
+        return (c, vec![0.0, 0.0, 1.0]);
+    }
+    // In edge region AC
+    let vb = d5 * d2 - d1 * d6;
+    if vb <= 0.0 && d2 >= 0.0 && d6 <= 0.0 {
+        let w = d2 / (d2 - d6);
+        return (a + ac * w, vec![1.0 - w, 0.0, w]);
+    }
+    // In edge region BC
+    let va = d3 * d6 - d5 * d4;
+    if va <= 0.0 && (d4 - d3) >= 0.0 && (d5 - d6) >= 0.0 {
+        let w = (d4 - d3) / ((d4 - d3) + (d5 - d6));
+        return (b + (c - b) * w, vec![0.0, 1.0 - w, w]);
+    }
+
+    let sum = va + vb + vc;
+    if sum <= 0.0 {
+        // A degenerate (collinear) triangle, which the regions above should have caught, but may
+        // not have due to floating point error
+        return (a, vec![1.0, 0.0, 0.0]);
+    }
+    // Inside the face
+    let v = vb / sum;
+    let w = vc / sum;
+    return (a + ab * v + ac * w, vec![1.0 - v - w, v, w]);
+}
+
+fn closest_point_on_tetrahedron(points: &[XYZ], tolerance: f32) -> (XYZ, Vec<f32>) {
+    // Each face, along with the vertex opposite it
+    const FACES: [([usize; 3], usize); 4] = [
+        ([0, 1, 2], 3),
+        ([0, 2, 3], 1),
+        ([0, 3, 1], 2),
+        ([1, 3, 2], 0),
+    ];
+
+    let mut best: Option<(XYZ, Vec<f32>)> = None;
+    for (face, opposite) in FACES {
+        let [i, j, k] = face;
+        let normal = (points[j] - points[i])
+            .cross(points[k] - points[i])
+            .normalize();
+        let origin_side = (-points[i]).dot(normal);
+        let opposite_side = (points[opposite] - points[i]).dot(normal);
+        // The origin can only be closest to this face if it's on the other side of it from the
+        // rest of the tetrahedron. If the tetrahedron is flat, we can't tell, so check every face.
+        let is_flat = opposite_side.abs() <= tolerance;
+        if !is_flat && origin_side * opposite_side >= 0.0 {
+            continue;
+        }
+
+        let (closest, face_weights) = closest_point_on_triangle(points[i], points[j], points[k]);
+        if best
+            .as_ref()
+            .is_none_or(|(best_closest, _)| closest.magnitude() < best_closest.magnitude())
+        {
+            let mut weights = vec![0.0; 4];
+            for (idx, weight) in face.iter().zip(face_weights) {
+                weights[*idx] = weight;
+            }
+            best = Some((closest, weights));
+        }
+    }
+
+    return match best {
+        Some(best) => best,
+        // The origin is inside the tetrahedron
+        None => (XYZ::default(), vec![0.25; 4]),
+    };
+}
+
+/// Run EPA, given a simplex which contains the origin, on its surface or within it
+fn epa(a: &dyn Support, b: &dyn Support, mut simplex: Vec<Vertex>, scale: f32) -> CollisionPoints {
+    let tolerance = RELATIVE_TOLERANCE * scale;
+    let plane_normal = expand_to_tetrahedron(a, b, &mut simplex, tolerance);
+    return match (simplex.len(), plane_normal) {
+        (4, _) => epa_3d(a, b, simplex, scale),
+        // The Minkowski difference is flat, e.g. for two polygons in the same plane
+        (3, Some(plane_normal)) => epa_2d(a, b, simplex, plane_normal, scale),
+        // The Minkowski difference is a point or segment, so the shapes can only be touching
+        _ => {
+            let (a, b) = interpolate_shape_points(&simplex[..1], &[1.0]);
+            CollisionPoints {
+                a,
+                b,
+                normal: XYZ {
+                    x: 0.0,
+                    y: 1.0,
+                    z: 0.0,
+                },
+                depth: 0.0,
+                has_collision: true,
+            }
+        }
+    };
+}
+
+/// Add vertices to the simplex until it's a tetrahedron, by searching for support points off the
+/// line or plane of the existing vertices. This fails if the Minkowski difference itself has
+/// fewer dimensions, in which case the simplex is left as large as possible. If it's a triangle,
+/// we return the normal of the plane containing the whole Minkowski difference.
+fn expand_to_tetrahedron(
+    a: &dyn Support,
+    b: &dyn Support,
+    simplex: &mut Vec<Vertex>,
+    tolerance: f32,
+) -> Option<XYZ> {
+    let axes = [
+        XYZ {
+            x: 1.0,
+            y: 0.0,
+            z: 0.0,
+        },
+        XYZ {
+            x: 0.0,
+            y: 1.0,
+            z: 0.0,
+        },
+        XYZ {
+            x: 0.0,
+            y: 0.0,
+            z: 1.0,
+        },
+    ];
+    let try_directions =
+        |simplex: &mut Vec<Vertex>, directions: &[XYZ], is_new: &dyn Fn(XYZ) -> bool| {
+            for direction in directions {
+                for sign in [1.0, -1.0] {
+                    let vertex = minkowski_support(a, b, *direction * sign);
+                    if is_new(vertex.point) {
+                        simplex.push(vertex);
+                        return;
+                    }
+                }
+            }
+        };
+
+    if simplex.len() == 1 {
+        let p0 = simplex[0].point;
+        try_directions(simplex, &axes, &|p| (p - p0).magnitude() > tolerance);
+    }
+    if simplex.len() == 2 {
+        let p0 = simplex[0].point;
+        let line = (simplex[1].point - p0).normalize();
+        let perpendiculars: Vec<XYZ> = axes
+            .iter()
+            .map(|axis| line.cross(*axis))
+            .filter(|perpendicular| perpendicular.magnitude() > 0.1)
+            .collect();
+        let distance_from_line = |p: XYZ| {
+            let offset = p - p0;
+            return (offset - line * offset.dot(line)).magnitude();
+        };
+        try_directions(simplex, &perpendiculars, &|p| {
+            distance_from_line(p) > tolerance
+        });
+    }
+    if simplex.len() == 3 {
+        let p0 = simplex[0].point;
+        let normal = (simplex[1].point - p0)
+            .cross(simplex[2].point - p0)
+            .normalize();
+        try_directions(simplex, &[normal], &|p| {
+            (p - p0).dot(normal).abs() > tolerance
+        });
+        if simplex.len() == 3 {
+            return Some(normal);
+        }
+    }
+    return None;
+}
+
+/// A triangular face of the EPA polytope, with its outward normal, and its distance from the origin
+struct Face {
+    vertices: [usize; 3],
+    normal: XYZ,
+    distance: f32,
+}
+
+fn make_face(points: &[XYZ], vertices: [usize; 3], interior: XYZ) -> Face {
+    let [i, j, k] = vertices;
+    let mut normal = (points[j] - points[i])
+        .cross(points[k] - points[i])
+        .normalize();
+    let mut vertices = vertices;
+    if normal.dot(points[i] - interior) < 0.0 {
+        normal = -normal;
+        vertices = [i, k, j];
+    }
+    return Face {
+        vertices,
+        normal,
+        distance: normal.dot(points[i]),
+    };
+}
+
+fn epa_3d(a: &dyn Support, b: &dyn Support, simplex: Vec<Vertex>, scale: f32) -> CollisionPoints {
+    let mut vertices = simplex;
+    let mut points: Vec<XYZ> = vertices.iter().map(|v| v.point).collect();
+    // The polytope only ever grows, so the initial tetrahedron's centroid always stays inside it,
+    // which tells us which way each face's normal should point
+    let interior = (points[0] + points[1] + points[2] + points[3]) * 0.25;
+    let mut faces: Vec<Face> = [[0, 1, 2], [0, 2, 3], [0, 3, 1], [1, 3, 2]]
+        .iter()
+        .map(|f| make_face(&points, *f, interior))
+        .collect();
+
+    let mut scale = scale;
+    let mut closest_idx = 0;
+    for _ in 0..MAX_ITERATIONS {
+        closest_idx = 0;
+        for (idx, face) in faces.iter().enumerate() {
+            if face.distance < faces[closest_idx].distance {
+                closest_idx = idx;
+            }
+        }
+        let closest = &faces[closest_idx];
+        let vertex = minkowski_support(a, b, closest.normal);
+        scale = f32::max(scale, vertex.point.magnitude());
+        // If we can't expand the closest face any further, it's on the Minkowski difference's surface
+        if vertex.point.dot(closest.normal) - closest.distance <= RELATIVE_TOLERANCE * scale {
+            break;
+        }
+
+        // Remove every face the new vertex can see, leaving a hole bounded by the horizon edges,
+        // i.e. the edges which belonged to exactly one removed face
+        let mut horizon: Vec<(usize, usize)> = Vec::new();
+        faces.retain(|face| {
+            if face.normal.dot(vertex.point - points[face.vertices[0]]) <= 0.0 {
+                return true;
+            }
+            let [i, j, k] = face.vertices;
+            for edge in [(i, j), (j, k), (k, i)] {
+                match horizon.iter().position(|e| *e == (edge.1, edge.0)) {
+                    Some(shared) => {
+                        horizon.swap_remove(shared);
+                    }
+                    None => horizon.push(edge),
+                }
+            }
+            return false;
+        });
+
+        // Patch the hole with new faces, joining the horizon to the new vertex
+        let new_idx = vertices.len();
+        vertices.push(vertex);
+        points.push(vertex.point);
+        for (i, j) in horizon {
+            faces.push(make_face(&points, [i, j, new_idx], interior));
+        }
+        if faces.is_empty() {
+            break;
+        }
+    }
+
+    let face = &faces[closest_idx.min(faces.len() - 1)];
+    let [i, j, k] = face.vertices;
+    let weights = barycentric(face.normal * face.distance, points[i], points[j], points[k]);
+    let (on_a, on_b) = interpolate_shape_points(&[vertices[i], vertices[j], vertices[k]], &weights);
+    return penetrating_collision_points(on_a, on_b, face.normal, face.distance);
+}
+
+/// EPA within a plane, for when the whole Minkowski difference lies in that plane, so we expand a
+/// polygon rather than a polytope
+fn epa_2d(
+    a: &dyn Support,
+    b: &dyn Support,
+    simplex: Vec<Vertex>,
+    plane_normal: XYZ,
+    scale: f32,
+) -> CollisionPoints {
+    // Kept in order around the polygon
+    let mut vertices = simplex;
+    let interior = (vertices[0].point + vertices[1].point + vertices[2].point) * (1.0 / 3.0);
+    let edge_normal = |p: XYZ, q: XYZ| {
+        let normal = (q - p).cross(plane_normal).normalize();
+        if normal.dot(p - interior) < 0.0 {
+            return -normal;
+        }
+        return normal;
+    };
+
+    let mut scale = scale;
+    let mut closest_edge = 0;
+    let mut normal = XYZ::default();
+    let mut distance = 0.0;
+    for _ in 0..MAX_ITERATIONS {
+        distance = f32::INFINITY;
+        for idx in 0..vertices.len() {
+            let p = vertices[idx].point;
+            let q = vertices[(idx + 1) % vertices.len()].point;
+            let n = edge_normal(p, q);
+            if n.dot(p) < distance {
+                distance = n.dot(p);
+                normal = n;
+                closest_edge = idx;
+            }
+        }
+        let vertex = minkowski_support(a, b, normal);
+        scale = f32::max(scale, vertex.point.magnitude());
+        if vertex.point.dot(normal) - distance <= RELATIVE_TOLERANCE * scale {
+            break;
+        }
+        vertices.insert(closest_edge + 1, vertex);
+    }
+
+    let p = vertices[closest_edge];
+    let q = vertices[(closest_edge + 1) % vertices.len()];
+    let (_, weights) = closest_point_on_segment(p.point, q.point);
+    let (on_a, on_b) = interpolate_shape_points(&[p, q], &weights);
+    return penetrating_collision_points(on_a, on_b, normal, distance);
+}
+
+/// `surface_normal` is the outward normal of the Minkowski difference's surface closest to the
+/// origin, and `distance` is how far that surface is from the origin
+fn penetrating_collision_points(
+    on_a: XYZ,
+    on_b: XYZ,
+    surface_normal: XYZ,
+    distance: f32,
+) -> CollisionPoints {
+    return CollisionPoints {
+        a: on_a,
+        b: on_b,
+        // Moving A by -distance along the surface normal moves the origin onto that surface
+        normal: -surface_normal,
+        depth: distance,
+        has_collision: true,
+    };
+}
+
+/// Returns the weights of a triangle's vertices which sum to `point`, having projected `point`
+/// onto the triangle's plane
+fn barycentric(point: XYZ, a: XYZ, b: XYZ, c: XYZ) -> Vec<f32> {
+    let ab = b - a;
+    let ac = c - a;
+    let ap = point - a;
+    let d00 = ab.dot(ab);
+    let d01 = ab.dot(ac);
+    let d11 = ac.dot(ac);
+    let d20 = ap.dot(ab);
+    let d21 = ap.dot(ac);
+    let denominator = d00 * d11 - d01 * d01;
+    if denominator.abs() <= f32::EPSILON {
+        return vec![1.0 / 3.0; 3];
+    }
+    let v = (d11 * d20 - d01 * d21) / denominator;
+    let w = (d00 * d21 - d01 * d20) / denominator;
+    return vec![1.0 - v - w, v, w];
+}
@@ -0,0 +1,243 @@
+//! Numerical integrators, which advance objects' velocities and positions over a time step
+use crate::objects::Object;
+use crate::transform::Quat;
+use crate::xyz::{CrossProductXyz, DotProductXyz, XYZ};
+
+/// Advances the velocity and position of every object by one time step.
+///
+/// * `objects` - the objects to update in place.
+/// * `time_elapsed_seconds` - the size of the time step.
+/// * `accelerations` - returns the acceleration of each object, in the same order as the objects,
+///   for the objects' current state. Integrators may call this several times per step with
+///   intermediate states, so it should only depend on the objects it's given.
+pub trait Integrator {
+    fn integrate(
+        &self,
+        objects: &mut [Object],
+        time_elapsed_seconds: f32,
+        accelerations: &mut dyn FnMut(&[Object]) -> Vec<XYZ>,
+    );
+
+    /// Like `integrate`, but charged objects also feel the Lorentz force q v x B of a magnetic field.
+    /// `magnetic_fields` returns the field B at each object, in the same order as the objects, and
+    /// like `accelerations`, may be called several times per step.
+    ///
+    /// By default, this adds the Lorentz force to the accelerations, which suits integrators that
+    /// handle velocity-dependent forces well. `Boris` overrides it to rotate velocities exactly.
+    fn integrate_in_magnetic_field(
+        &self,
+        objects: &mut [Object],
+        time_elapsed_seconds: f32,
+        accelerations: &mut dyn FnMut(&[Object]) -> Vec<XYZ>,
+        magnetic_fields: &mut dyn FnMut(&[Object]) -> Vec<XYZ>,
+    ) {
+        let mut with_lorentz_force = |objects: &[Object]| -> Vec<XYZ> {
+            let a = accelerations(objects);
+            let fields = magnetic_fields(objects);
+            return objects
+                .iter()
+                .zip(a.into_iter().zip(fields))
+                .map(|(p, (a, field))| a + p.velocity.cross(field) * (p.charge * p.inverse_mass()))
+                .collect();
+        };
+        self.integrate(objects, time_elapsed_seconds, &mut with_lorentz_force);
+    }
+}
+
+/// Advances the angular velocity of every object by its torque, then its orientation by its new
+/// angular velocity, i.e. with semi-implicit Euler. Torques are constant over a tick, so unlike
+/// positions, rotation gains nothing from the higher order integrators.
+///
+/// This includes the gyroscopic term, which makes e.g. a spinning box tumble when it isn't
+/// spinning about one of its axes of symmetry.
+pub fn integrate_rotation(objects: &mut [Object], time_elapsed_seconds: f32) {
+    let dt = time_elapsed_seconds;
+    for p in objects.iter_mut() {
+        let inverse_inertia = p.inverse_inertia_tensor();
+        if inverse_inertia == glam::Mat3::ZERO {
+            continue;
+        }
+        let omega = glam::Vec3::from(p.angular_velocity);
+        let angular_momentum = XYZ::from(p.inertia_tensor() * omega);
+        let gyroscopic = p.angular_velocity.cross(angular_momentum);
+        let angular_acceleration = inverse_inertia * glam::Vec3::from(p.torque - gyroscopic);
+        p.angular_velocity += XYZ::from(angular_acceleration) * dt;
+
+        if p.angular_velocity == XYZ::default() {
+            continue;
+        }
+        // An unset (e.g. all-zero) rotation is treated as no rotation
+        let rotation = if p.transform.rotation.is_normalized() {
+            p.transform.rotation
+        } else {
+            Quat::IDENTITY
+        };
+        let step = Quat::from_scaled_axis(glam::Vec3::from(p.angular_velocity) * dt);
+        // Renormalize, so that floating point error doesn't accumulate over many steps
+        p.transform.rotation = (step * rotation).normalize();
+    }
+}
+
+/// Selects which integrator a world uses. See the individual integrators for their trade-offs.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum IntegratorType {
+    SemiImplicitEuler,
+    VelocityVerlet,
+    RK4,
+    Boris,
+}
+
+impl IntegratorType {
+    pub fn integrator(&self) -> &'static dyn Integrator {
+        match self {
+            IntegratorType::SemiImplicitEuler => &SemiImplicitEuler,
+            IntegratorType::VelocityVerlet => &VelocityVerlet,
+            IntegratorType::RK4 => &RK4,
+            IntegratorType::Boris => &Boris,
+        }
+    }
+}
+
+/// Updates velocity first, then moves by the new velocity. This is as cheap as explicit Euler, but
+/// unlike explicit Euler, doesn't steadily gain energy in oscillating systems such as springs and
+/// orbits. It's only first-order accurate though, so e.g. a projectile's position drifts from the
+/// analytic solution by an amount proportional to the time step.
+pub struct SemiImplicitEuler;
+
+impl Integrator for SemiImplicitEuler {
+    fn integrate(
+        &self,
+        objects: &mut [Object],
+        time_elapsed_seconds: f32,
+        accelerations: &mut dyn FnMut(&[Object]) -> Vec<XYZ>,
+    ) {
+        let dt = time_elapsed_seconds;
+        let a = accelerations(objects);
+        for (p, a) in objects.iter_mut().zip(a) {
+            p.velocity += a * dt;
+            p.transform.position += p.velocity * dt;
+        }
+    }
+}
+
+/// Second-order accurate, and like semi-implicit Euler, keeps the energy of oscillating systems
+/// bounded. Exact for constant accelerations, such as a projectile under uniform gravity.
+///
+/// Velocity-dependent accelerations (e.g. drag) are evaluated at the half-step velocity.
+pub struct VelocityVerlet;
+
+impl Integrator for VelocityVerlet {
+    fn integrate(
+        &self,
+        objects: &mut [Object],
+        time_elapsed_seconds: f32,
+        accelerations: &mut dyn FnMut(&[Object]) -> Vec<XYZ>,
+    ) {
+        let dt = time_elapsed_seconds;
+        let a_start = accelerations(objects);
+        for (p, a) in objects.iter_mut().zip(a_start.iter()) {
+            // x(t + dt) = x(t) + v(t)dt + 0.5a(t)dt^2, and v(t + dt/2) = v(t) + 0.5a(t)dt
+            p.velocity += a * (0.5 * dt);
+            p.transform.position += p.velocity * dt;
+        }
+        let a_end = accelerations(objects);
+        for (p, a) in objects.iter_mut().zip(a_end) {
+            p.velocity += a * (0.5 * dt);
+        }
+    }
+}
+
+/// The classic fourth-order Runge-Kutta method. The most accurate integrator here for a given time
+/// step, at the cost of evaluating accelerations four times per step. It slowly loses energy in
+/// oscillating systems rather than conserving it, but far more slowly than the Euler methods drift.
+pub struct RK4;
+
+impl Integrator for RK4 {
+    fn integrate(
+        &self,
+        objects: &mut [Object],
+        time_elapsed_seconds: f32,
+        accelerations: &mut dyn FnMut(&[Object]) -> Vec<XYZ>,
+    ) {
+        let dt = time_elapsed_seconds;
+        let start_positions: Vec<XYZ> = objects.iter().map(|p| p.transform.position).collect();
+        let start_velocities: Vec<XYZ> = objects.iter().map(|p| p.velocity).collect();
+
+        // Each k is the (velocity, acceleration) derivative of an object's (position, velocity) state
+        let mut k_velocities: Vec<[XYZ; 4]> = vec![[XYZ::default(); 4]; objects.len()];
+        let mut k_accelerations: Vec<[XYZ; 4]> = vec![[XYZ::default(); 4]; objects.len()];
+
+        // How far into the step each derivative is evaluated, and the state it's evaluated at
+        // is the start state, advanced by the previous derivative over that fraction of the step
+        let step_fractions = [0.0, 0.5, 0.5, 1.0];
+        for (k, fraction) in step_fractions.iter().enumerate() {
+            if k > 0 {
+                for (idx, p) in objects.iter_mut().enumerate() {
+                    p.transform.position =
+                        start_positions[idx] + k_velocities[idx][k - 1] * (fraction * dt);
+                    p.velocity =
+                        start_velocities[idx] + k_accelerations[idx][k - 1] * (fraction * dt);
+                }
+            }
+            let a = accelerations(objects);
+            for (idx, p) in objects.iter().enumerate() {
+                k_velocities[idx][k] = p.velocity;
+                k_accelerations[idx][k] = a[idx];
+            }
+        }
+
+        for (idx, p) in objects.iter_mut().enumerate() {
+            let kv = k_velocities[idx];
+            let ka = k_accelerations[idx];
+            p.transform.position =
+                start_positions[idx] + (kv[0] + kv[1] * 2.0 + kv[2] * 2.0 + kv[3]) * (dt / 6.0);
+            p.velocity =
+                start_velocities[idx] + (ka[0] + ka[1] * 2.0 + ka[2] * 2.0 + ka[3]) * (dt / 6.0);
+        }
+    }
+}
+
+/// The Boris pusher, the standard integrator for charged particles in magnetic fields. Each step
+/// kicks the velocity by half of the other accelerations, rotates it about the magnetic field, kicks
+/// it by the other half, then moves by the new velocity.
+///
+/// The rotation never changes an object's speed, so unlike the other integrators, which spiral
+/// slowly inwards or outwards, gyration radii stay the same over any number of steps. Its only error
+/// is a slight lag in the phase of the gyration, which shrinks with the square of the time step.
+/// Without a magnetic field, this is the same as semi-implicit Euler.
+pub struct Boris;
+
+impl Integrator for Boris {
+    fn integrate(
+        &self,
+        objects: &mut [Object],
+        time_elapsed_seconds: f32,
+        accelerations: &mut dyn FnMut(&[Object]) -> Vec<XYZ>,
+    ) {
+        SemiImplicitEuler.integrate(objects, time_elapsed_seconds, accelerations);
+    }
+
+    fn integrate_in_magnetic_field(
+        &self,
+        objects: &mut [Object],
+        time_elapsed_seconds: f32,
+        accelerations: &mut dyn FnMut(&[Object]) -> Vec<XYZ>,
+        magnetic_fields: &mut dyn FnMut(&[Object]) -> Vec<XYZ>,
+    ) {
+        let dt = time_elapsed_seconds;
+        let a = accelerations(objects);
+        let fields = magnetic_fields(objects);
+        for (p, (a, field)) in objects.iter_mut().zip(a.into_iter().zip(fields)) {
+            let half_kick = a * (0.5 * dt);
+            let v_minus = p.velocity + half_kick;
+            // The rotation by the angle q B dt / m, via the tangent of half of it, which keeps the
+            // rotated velocity exactly as long as the original
+            let t = field * (0.5 * dt * p.charge * p.inverse_mass());
+            let s = t * (2.0 / (1.0 + t.dot(t)));
+            let v_prime = v_minus + v_minus.cross(t);
+            let v_plus = v_minus + v_prime.cross(s);
+            p.velocity = v_plus + half_kick;
+            p.transform.position += p.velocity * dt;
+        }
+    }
+}
@@ -1,54 +1,50 @@
 //! A realistic particle simulator
+// We prefer explicit returns throughout this crate
+#![allow(clippy::needless_return)]
 
+pub mod broadphase;
+pub mod brownian;
 pub mod colliders;
+pub mod config;
+pub mod contacts;
+pub mod electromagnetism;
+pub mod forces;
+pub mod gjk;
+pub mod integrators;
+pub mod mass;
+pub mod material;
+pub mod molecular;
+pub mod nbody;
 pub mod objects;
+pub mod random;
+#[cfg(feature = "render")]
+pub mod render;
+pub mod thermostat;
+pub mod timestep;
 pub mod transform;
+pub mod world;
 pub mod xyz;
+use crate::broadphase::BoundingBox;
+use crate::config::SimConfig;
+use crate::contacts::coulomb_friction;
+use crate::material::Material;
 use crate::objects::Object;
-use crate::transform::Transform;
-use crate::xyz::XYZ;
-use macroquad::prelude::*;
+use crate::xyz::{DotProductXyz, MagnitudeXyz, XYZ};
+use glam::Vec3;
 use std::fmt;
 
-pub const SCREEN_WIDTH: f32 = 1080.0;
-pub const SCREEN_HEIGHT: f32 = 720.0;
-// TODO: implement behavior for Z axis
-// pub const SCREEN_DEPTH: f32 = 720.0;
-
-// The default diameter in pixels of a particle
-pub const PARTICLE_RADIUS_PX: f32 = 10.0;
-
-// The default color of a particle
-const PARTICLE_COLOR: Color = RED;
-
-// Simulation parameters
-pub const PIXELS_PER_METER: f32 = 10.0;
-const GRAVITY_MS: f32 = 9.8;
-// const FRICTION_STATIC_COEFFICIENT: f32 = 0.02;
-const FRICTION_DYNAMIC_COEFFICIENT: f32 = 0.005;
-const BOUNCE_COEFFICIENT: f32 = 0.9;
-
 /* Todo: consider...
 - sliders
-- make friction apply on bounces
-- implement spin, and update bounce logic etc accordingly
 - use signed distance functions or similar to calculate when a particle may be out of bounds
 - bouncing with object compressibility (more complicated)
 - bouncing within a restricted space (bounding object)
 - emitters (e.g. mouse emitter) + lifetimes
-- collision with other particles / momentum transfer
 - colored particles based on properties, e.g. velocity
 - performance tests / logging
-- air resistance
 - shaders
 - etc
 */
 
-// Display parameters
-const STATS_FONT_SIZE: f32 = 30.0;
-const STATS_X_ANCHOR: f32 = SCREEN_WIDTH - (0.4 * SCREEN_WIDTH);
-const STATS_COLOR: Color = GREEN;
-
 #[derive(Debug, Clone)]
 pub enum BounceError {
     CalculationDepthExceeded,
@@ -57,8 +53,8 @@ pub enum BounceError {
 
 #[derive(Debug, Clone)]
 pub struct OutOfBoundsError {
-    object_location_x: f32,
-    object_location_y: f32,
+    pub(crate) object_location_x: f32,
+    pub(crate) object_location_y: f32,
 }
 
 impl fmt::Display for OutOfBoundsError {
@@ -74,8 +70,8 @@ impl fmt::Display for OutOfBoundsError {
 #[derive(Debug, Clone)]
 struct CalculationDepthExceeded;
 
-/// Returns true if the point falls within a circle, else false
-/// The following formula is used: a^2 + b^2 = c^2. If c <= radius, then the point is considered to be within the circle
+// Returns true if the point falls within a circle, else false
+// The following formula is used: a^2 + b^2 = c^2. If c <= radius, then the point is considered to be within the circle
 // pub fn does_circle_intersect(circle_center: XYZ, circle_radius: f32, point: XYZ) -> bool {
 //     // tolerance is to account for floating point imprecision
 //     let tolerance = 0.0001;
@@ -85,17 +81,6 @@ struct CalculationDepthExceeded;
 //     return (circle_radius.powf(2.0) + tolerance) >= (a_2 + b_2);
 // }
 
-pub fn draw_particles(particles: &Vec<Object>) {
-    for p in particles {
-        draw_circle(
-            p.transform.position.x.floor(),
-            p.transform.position.y.floor(),
-            PARTICLE_RADIUS_PX,
-            PARTICLE_COLOR,
-        );
-    }
-}
-
 pub fn convert_meters_to_pixels(meters: f32, pixels_per_meter: f32) -> f32 {
     return meters * pixels_per_meter;
 }
@@ -114,49 +99,170 @@ pub fn calculate_particle_acceleration(
     return (new_velocity - old_velocity) / time_elapsed_seconds;
 }
 
-/// Returns a True if the input particle is touching the ground, else False. This function is not suitable for off-screen particles.
-pub fn particle_touching_ground(particle: &Object) -> bool {
-    // Edge case: returns true if a particle has fallen off the bottom of the screen.
-    return (particle.transform.position.y + PARTICLE_RADIUS_PX) >= SCREEN_HEIGHT;
+/// Returns how far the input particle reaches from its position in each direction, i.e. the bounds of
+/// its collider in the world, relative to its position. Particles with infinite colliders (e.g.
+/// planes) or without a collider are treated as spheres of the configured radius. The arena's edges
+/// are checked against these extents, so that they agree with collisions and rendering on where the
+/// particle is.
+pub fn arena_extents(particle: &Object, config: &SimConfig) -> BoundingBox {
+    let position = particle.transform.position;
+    let bounds = particle.world_collider().bounding_box();
+    if bounds.is_finite() {
+        return BoundingBox {
+            min: bounds.min - position,
+            max: bounds.max - position,
+        };
+    }
+    let radius = config.particle_radius_px;
+    let extent = XYZ {
+        x: radius,
+        y: radius,
+        z: radius,
+    };
+    return BoundingBox {
+        min: -extent,
+        max: extent,
+    };
 }
 
-/// Calculate the signed velocity change due to friction for a particle. Returns a value <= 0 if object is moving right, else >= 0.
-/// For realistic friction, the coefficients should be positive values.
-/// For now, we only apply friction in the horizontal dimension and for particles in contact with the ground.
-pub fn calculate_friction_deceleration(
+/// Returns how far `extents` (see `arena_extents`) reach from the particle's position along the unit
+/// vector `direction`
+fn extents_reach(extents: &BoundingBox, direction: XYZ) -> f32 {
+    let along = |min: f32, max: f32, d: f32| f32::max(min * d, max * d);
+    return along(extents.min.x, extents.max.x, direction.x)
+        + along(extents.min.y, extents.max.y, direction.y)
+        + along(extents.min.z, extents.max.z, direction.z);
+}
+
+/// Returns a True if the input particle, with the given `extents` (see `arena_extents`), is touching
+/// the ground, else False. This function is not suitable for off-screen particles.
+pub fn particle_touching_ground(
     particle: &Object,
-    friction_dynamic_coefficient: f32,
-) -> f32 {
-    // We use the following formula: F=ma
-    // Todo: implement static coefficient
+    extents: &BoundingBox,
+    config: &SimConfig,
+) -> bool {
+    // Edge case: returns true if a particle has fallen off the bottom of the screen.
+    return (particle.transform.position.y + extents.max.y) >= config.screen_height;
+}
 
-    if !particle_touching_ground(particle) {
-        return 0.0;
+/// Returns the inward-facing normal of each arena edge that the input particle, with the given
+/// `extents` (see `arena_extents`), is touching, i.e. the floor, the ceiling and the walls. This
+/// function is not suitable for off-screen particles.
+pub fn touching_arena_edges(
+    particle: &Object,
+    extents: &BoundingBox,
+    config: &SimConfig,
+) -> Vec<XYZ> {
+    let position = particle.transform.position;
+    let mut normals = Vec::new();
+    // Edge case: as with particle_touching_ground, a particle past an edge counts as touching it
+    if position.y + extents.max.y >= config.screen_height {
+        normals.push(XYZ {
+            x: 0.0,
+            y: -1.0,
+            z: 0.0,
+        });
     }
-
-    // Arbitrarily chosen value used to decide whether something counts as "moving" or not
-    let cutoff_velocity = 0.0001;
-    if particle.velocity.x.abs() < cutoff_velocity {
-        return 0.0;
+    if position.y + extents.min.y <= 0.0 {
+        normals.push(XYZ {
+            x: 0.0,
+            y: 1.0,
+            z: 0.0,
+        });
     }
+    if position.x + extents.min.x <= 0.0 {
+        normals.push(XYZ {
+            x: 1.0,
+            y: 0.0,
+            z: 0.0,
+        });
+    }
+    if position.x + extents.max.x >= config.screen_width {
+        normals.push(XYZ {
+            x: -1.0,
+            y: 0.0,
+            z: 0.0,
+        });
+    }
+    return normals;
+}
 
-    // friction coefficient * mass * gravity
-    let f = friction_dynamic_coefficient;
-    let friction_force = f * particle.mass * GRAVITY_MS;
-    let friction_deceleration = friction_force / particle.mass;
-
-    // The -1.0 multipliers let us oppose the object's velocity
-    if friction_deceleration > particle.velocity.x.abs() {
-        return -1.0 * particle.velocity.x;
+/// Calculate the signed velocity change due to friction for a particle resting or sliding against the
+/// arena's edges, which opposes its velocity along each edge it's pressed into. For realistic friction,
+/// the coefficients should be positive values. The coefficients are those of the contact between the
+/// particle and the arena, i.e. both their materials combined, see `arena_contact_material`.
+///
+/// A particle is pressed into an edge by the total force on it, including gravity, so e.g. a particle
+/// resting on the floor has friction, whereas a particle brushing the ceiling doesn't. `World::step`
+/// adds every force generator's force to the particle's before calling this. If the friction needed to
+/// hold the particle still is within the static limit, it sticks. Otherwise it slides, and dynamic
+/// friction slows it. The push builds up over `time_elapsed_seconds`, so the friction over a second
+/// is the same however many ticks it's split into. The particle reaches as far as its `extents`, see
+/// `arena_extents`.
+pub fn calculate_friction_deceleration(
+    particle: &Object,
+    contact: &Material,
+    extents: &BoundingBox,
+    config: &SimConfig,
+    time_elapsed_seconds: f32,
+) -> XYZ {
+    // We use the following formula: F=ma, so the friction per unit mass is the coefficient times the
+    // velocity which the acceleration pressing the particle into the edge builds up over the tick
+    let acceleration = particle.force * particle.inverse_mass();
+
+    let mut velocity = particle.velocity;
+    for normal in touching_arena_edges(particle, extents, config) {
+        let pressure = -acceleration.dot(normal);
+        if pressure <= 0.0 {
+            continue;
+        }
+        let sliding = velocity - normal * velocity.dot(normal);
+        velocity += coulomb_friction(sliding, pressure * time_elapsed_seconds, contact);
     }
-    if particle.velocity.x > 0.0 {
-        return -1.0 * friction_deceleration;
+    return velocity - particle.velocity;
+}
+
+/// Calculate the signed angular velocity change due to rolling resistance for a particle rolling
+/// against the arena's edges. Like `calculate_friction_deceleration`, this only applies to edges the
+/// particle is pressed into, and is in proportion to how hard it's pressed into them over
+/// `time_elapsed_seconds`. It opposes the particle's spin about the axes along each edge, at most
+/// stopping it, but not its spin in place against the edge. The particle reaches as far as its
+/// `extents`, see `arena_extents`.
+pub fn calculate_rolling_resistance(
+    particle: &Object,
+    contact: &Material,
+    extents: &BoundingBox,
+    config: &SimConfig,
+    time_elapsed_seconds: f32,
+) -> XYZ {
+    let acceleration = particle.force * particle.inverse_mass();
+    let inverse_inertia = particle.inverse_inertia_tensor();
+
+    let mut angular_velocity = particle.angular_velocity;
+    for normal in touching_arena_edges(particle, extents, config) {
+        let pressure = -acceleration.dot(normal);
+        let rolling = angular_velocity - normal * angular_velocity.dot(normal);
+        let rolling_speed = rolling.magnitude();
+        if pressure <= 0.0 || rolling_speed == 0.0 {
+            continue;
+        }
+        let axis = &rolling / rolling_speed;
+        let inverse_inertia_along_axis = axis.dot(XYZ::from(inverse_inertia * Vec3::from(axis)));
+        // The resisting torque is the coefficient times the push into the edge, acting at the
+        // particle's reach towards the edge
+        let angular_impulse = contact.rolling_resistance
+            * particle.mass
+            * pressure
+            * time_elapsed_seconds
+            * extents_reach(extents, -normal);
+        angular_velocity -=
+            axis * (angular_impulse * inverse_inertia_along_axis).min(rolling_speed);
     }
-    return friction_deceleration;
+    return angular_velocity - particle.angular_velocity;
 }
 
-/// Calculate the effect of gravity in meters over the elapsed timeframe, if it's not resting on any surface
-/// (currently we only check for the ground)
+// Calculate the effect of gravity in meters over the elapsed timeframe, if it's not resting on any surface
+// (currently we only check for the ground)
 // pub fn calculate_gravity_effect_on_velocity(
 //     particle: &Particle,
 //     gravity_acceleration_ms: f32,
@@ -179,17 +285,22 @@ pub struct BounceResult {
 /// A particle may bounce 0 or more times. If the input particle's position is out of bounds, or its velocity so extreme that it exceeds calculation limits, then return an error.
 /// * `particle` - the object for which bounce values should be calculated.
 /// * `time_elapsed_seconds` - the time, in seconds, over which to calculate the bounce values.
-/// * `bounce_coefficient` - the bounciness of the object, where 0 means no bounce, and 1 means infinite bouncing. This value must be between 0 and 1 inclusive!
+/// * `contact` - the material of the contact between the object and the arena's edges, see `arena_contact_material`. Its restitution is the bounciness of the object, where 0 means no bounce, and 1 means infinite bouncing. This value must be within [0, 1)! Its friction slows the object along each edge it bounces off.
+/// * `extents` - how far the object reaches from its position towards the arena's edges, see `arena_extents`.
+/// * `config` - the simulation config, which describes the arena bounds.
 ///
 /// Output - an object representing the updated position and velocities of the input object.
 pub fn calculate_bounce(
     particle: &Object,
-    bounce_coefficient: f32,
+    contact: &Material,
+    extents: &BoundingBox,
     time_elapsed_seconds: f64,
+    config: &SimConfig,
 ) -> Result<BounceResult, BounceError> {
-    // Todo: add bounce interactions between particles
+    // Bounce interactions between particles are handled separately, by contacts::resolve_contacts
 
     let p = particle;
+    let bounce_coefficient = contact.restitution;
     let mut result = BounceResult {
         position: XYZ {
             x: p.transform.position.x,
@@ -208,11 +319,10 @@ pub fn calculate_bounce(
         return Ok(result);
     }
 
-    // TODO: update this to work with new collision detection logic
-    if p.transform.position.x - PARTICLE_RADIUS_PX < 0.0
-        || p.transform.position.y - PARTICLE_RADIUS_PX < 0.0
-        || p.transform.position.x + PARTICLE_RADIUS_PX > SCREEN_WIDTH
-        || p.transform.position.y + PARTICLE_RADIUS_PX > SCREEN_HEIGHT
+    if p.transform.position.x + extents.min.x < 0.0
+        || p.transform.position.y + extents.min.y < 0.0
+        || p.transform.position.x + extents.max.x > config.screen_width
+        || p.transform.position.y + extents.max.y > config.screen_height
     {
         return Err(BounceError::OutOfBoundsError(OutOfBoundsError {
             object_location_x: p.transform.position.x,
@@ -227,6 +337,8 @@ pub fn calculate_bounce(
         p.velocity.x,
         time_elapsed_seconds,
         bounce_coefficient,
+        extents,
+        config,
     );
     let partial_res_y = bounce_helper(
         Axis::Y,
@@ -234,38 +346,52 @@ pub fn calculate_bounce(
         p.velocity.y,
         time_elapsed_seconds,
         bounce_coefficient,
+        extents,
+        config,
     );
 
+    let x_velocity_change;
     match partial_res_x {
         Ok(partial) => {
             result.position.x = partial.axis_position;
             result.velocity.x = partial.axis_velocity;
+            x_velocity_change = partial.bounce_velocity_change;
         }
         Err(_e) => {
             return Err(BounceError::CalculationDepthExceeded);
         }
     }
 
+    let y_velocity_change;
     match partial_res_y {
         Ok(partial) => {
             result.position.y = partial.axis_position;
             result.velocity.y = partial.axis_velocity;
+            y_velocity_change = partial.bounce_velocity_change;
         }
         Err(_e) => {
             return Err(BounceError::CalculationDepthExceeded);
         }
     }
 
-    println!(
-        "Bounce input Y: {}, output Y: {}, input X: {}, output X: {}",
-        p.transform.position.y, result.position.y, p.transform.position.x, result.position.x
-    );
+    // Each bounce pushes the particle off an edge, and friction slows it along that edge in proportion
+    // to the push. We don't adjust the position for this, since the particle mostly travels before
+    // and after its bounces, rather than during them.
+    let mut along_x_edges = result.velocity;
+    along_x_edges.x = 0.0;
+    result.velocity += coulomb_friction(along_x_edges, x_velocity_change, contact);
+    let mut along_y_edges = result.velocity;
+    along_y_edges.y = 0.0;
+    result.velocity += coulomb_friction(along_y_edges, y_velocity_change, contact);
+
     return Ok(result);
 }
 
 struct PartialBounceResult {
     pub axis_position: f32,
     pub axis_velocity: f32,
+    // The total change in speed along the axis from every bounce, i.e. how hard the edges pushed back
+    pub bounce_velocity_change: f32,
 }
 
 enum Axis {
@@ -279,6 +405,8 @@ fn bounce_helper(
     axis_velocity: f32,
     time_elapsed_seconds: f64,
     bounce_coefficient: f32,
+    extents: &BoundingBox,
+    config: &SimConfig,
 ) -> Result<PartialBounceResult, CalculationDepthExceeded> {
     /*
         This function helps calculate bounces
@@ -287,6 +415,7 @@ fn bounce_helper(
     let mut res = PartialBounceResult {
         axis_position,
         axis_velocity,
+        bounce_velocity_change: 0.0,
     };
 
     let mut counter = 0;
@@ -294,31 +423,28 @@ fn bounce_helper(
     let max_bounce_calculations = 100;
 
     // These values here are signed, and indicate the direction in each axis that the particle can move
-    let directional_allowance_0;
-    let directional_allowance_1;
+    let min_allowed_position;
     let max_allowed_position;
     match axis {
         Axis::X => {
-            // How much distance the particle can legally move left and right respectively.
-            directional_allowance_0 = (PARTICLE_RADIUS_PX - axis_position).ceil();
-            directional_allowance_1 = ((SCREEN_WIDTH - PARTICLE_RADIUS_PX) - axis_position).floor();
-            max_allowed_position = SCREEN_WIDTH - PARTICLE_RADIUS_PX;
+            min_allowed_position = -extents.min.x;
+            max_allowed_position = config.screen_width - extents.max.x;
         }
         Axis::Y => {
-            // How much distance the particle can legally move up and down respectively.
-            directional_allowance_0 = (PARTICLE_RADIUS_PX - axis_position).ceil();
-            directional_allowance_1 =
-                ((SCREEN_HEIGHT - PARTICLE_RADIUS_PX) - axis_position).floor();
-            max_allowed_position = SCREEN_HEIGHT - PARTICLE_RADIUS_PX;
+            min_allowed_position = -extents.min.y;
+            max_allowed_position = config.screen_height - extents.max.y;
         }
     }
+    // How much distance the particle can legally move left and right (or up and down) respectively.
+    let directional_allowance_0 = (min_allowed_position - axis_position).ceil();
+    let directional_allowance_1 = (max_allowed_position - axis_position).floor();
     assert!(directional_allowance_0 <= 0.0);
     assert!(directional_allowance_1 >= 0.0);
 
     let time_multiplier = time_elapsed_seconds as f32;
     // Signed distance tracking the remaining amount of travel the particle can do in the examined timeframe
     let mut travel_remaining =
-        convert_meters_to_pixels(axis_velocity * time_multiplier, PIXELS_PER_METER);
+        convert_meters_to_pixels(axis_velocity * time_multiplier, config.pixels_per_meter);
     let mut new_velocity = axis_velocity;
     loop {
         // Break if remaining travel distance falls within limits for travel in a given direction,
@@ -336,12 +462,9 @@ fn bounce_helper(
             return Err(CalculationDepthExceeded);
         }
 
-        travel_remaining = -1.0 * travel_remaining * bounce_coefficient;
-        new_velocity = -1.0 * new_velocity * bounce_coefficient;
-        println!(
-            "T: {},\t\t{}, \t\t{}, \t\t{}",
-            travel_remaining, new_velocity, directional_allowance_0, directional_allowance_1
-        );
+        travel_remaining = -travel_remaining * bounce_coefficient;
+        res.bounce_velocity_change += new_velocity.abs() * (1.0 + bounce_coefficient);
+        new_velocity = -new_velocity * bounce_coefficient;
     }
     res.axis_position = axis_position + travel_remaining;
     res.axis_velocity = new_velocity;
@@ -354,20 +477,22 @@ fn bounce_helper(
         res.axis_position = max_allowed_position;
         res.axis_velocity = 0.0;
     }
-    if res.axis_position < PARTICLE_RADIUS_PX {
-        res.axis_position = PARTICLE_RADIUS_PX;
+    if res.axis_position < min_allowed_position {
+        res.axis_position = min_allowed_position;
     }
 
     return Ok(res);
 }
 
-/// Update a particle's properties, while remaining within a range of acceptable values. Also reset the velocity of off-screen particles, and clamp it to be within arena bounds
+/// Update a particle's properties, while remaining within a range of acceptable values. Also reset the velocity of off-screen particles, and clamp it to be within arena bounds, given how far it reaches from its position (see `arena_extents`)
 pub fn set_particle_properties_within_bounds(
     particle: &mut Object,
     new_x_pos: f32,
     new_y_pos: f32,
     new_x_velocity: f32,
     new_y_velocity: f32,
+    extents: &BoundingBox,
+    config: &SimConfig,
 ) {
     let p = particle;
     p.transform.position.x = new_x_pos;
@@ -375,110 +500,89 @@ pub fn set_particle_properties_within_bounds(
     p.velocity.x = new_x_velocity;
     p.velocity.y = new_y_velocity;
 
-    if SCREEN_HEIGHT < (p.transform.position.y + PARTICLE_RADIUS_PX).floor() {
-        println!(
-            "DEBUG: particle fully or partially off-screen at Y={}",
-            p.transform.position.y
-        );
-        p.transform.position.y = SCREEN_HEIGHT - PARTICLE_RADIUS_PX;
+    if config.screen_height < (p.transform.position.y + extents.max.y).floor() {
+        p.transform.position.y = config.screen_height - extents.max.y;
         p.velocity.y = 0.0;
-    } else if 0.0 > (p.transform.position.y - PARTICLE_RADIUS_PX).ceil() {
-        println!(
-            "DEBUG: particle fully or partially off-screen at Y={}",
-            p.transform.position.y
-        );
-        p.transform.position.y = PARTICLE_RADIUS_PX;
+    } else if 0.0 > (p.transform.position.y + extents.min.y).ceil() {
+        p.transform.position.y = -extents.min.y;
         p.velocity.y = 0.0;
     }
 
-    if SCREEN_WIDTH < (p.transform.position.x + PARTICLE_RADIUS_PX).floor() {
-        println!(
-            "DEBUG: particle fully or partially off-screen at X={}",
-            p.transform.position.x
-        );
-        p.transform.position.x = SCREEN_WIDTH - PARTICLE_RADIUS_PX;
+    if config.screen_width < (p.transform.position.x + extents.max.x).floor() {
+        p.transform.position.x = config.screen_width - extents.max.x;
         p.velocity.x = 0.0;
-    } else if 0.0 > (p.transform.position.x - PARTICLE_RADIUS_PX).ceil() {
-        println!(
-            "DEBUG: particle fully or partially off-screen at X={}",
-            p.transform.position.x
-        );
-        p.transform.position.x = PARTICLE_RADIUS_PX;
+    } else if 0.0 > (p.transform.position.x + extents.min.x).ceil() {
+        p.transform.position.x = -extents.min.x;
         p.velocity.x = 0.0;
     }
 }
 
-/// Draw simulation stats to screen
-pub fn draw_stats(particles: &Vec<Object>) {
-    // TODO: fix sum_y_positions so that it doesn't overflow or nan or whatever with 1000 particles
-    let mut sum_y_velocity = 0.0;
-    let mut sum_x_velocity = 0.0;
-    let mut sum_y_positions = 0.0;
-    for p in particles {
-        sum_y_velocity += p.velocity.y;
-        sum_x_velocity += p.velocity.x;
-        sum_y_positions += p.transform.position.y;
-    }
-    let avg_y_velocity = sum_y_velocity / particles.len() as f32;
-    let avg_x_velocity = sum_x_velocity / particles.len() as f32;
-    let y_velocity_str = "Mean Y velocity: ".to_owned() + &avg_y_velocity.to_string();
-    let x_velocity_str = "Mean X velocity: ".to_owned() + &avg_x_velocity.to_string();
-
-    let sim_height_meters = SCREEN_HEIGHT / PIXELS_PER_METER;
-    let sim_height_meters_str = "Sim height meters: ".to_owned() + &sim_height_meters.to_string();
-
-    let particle_count_str = "Particle count: ".to_owned() + &particles.len().to_string();
-
-    let particle_mean_altitude_px = SCREEN_HEIGHT - (sum_y_positions / particles.len() as f32);
-    let particle_mean_altitude_meters =
-        convert_pixels_to_meters(particle_mean_altitude_px, PIXELS_PER_METER);
-    let particle_mean_altitude_str =
-        "Mean altitude (m): ".to_owned() + &particle_mean_altitude_meters.to_string();
-
-    let strings: [&String; 6] = [
-        &get_fps().to_string(),
-        &y_velocity_str,
-        &x_velocity_str,
-        &sim_height_meters_str,
-        &particle_mean_altitude_str,
-        &particle_count_str,
-    ];
-
-    let y_offset = 30.0;
-    for (idx, s) in strings.iter().enumerate() {
-        draw_text(
-            &s,
-            STATS_X_ANCHOR,
-            idx as f32 * y_offset + y_offset,
-            STATS_FONT_SIZE,
-            STATS_COLOR,
-        );
-    }
+// The bounciest an object may be against the arena's edges, since `calculate_bounce` can't terminate on
+// a bounce which loses no speed
+const MAX_ARENA_RESTITUTION: f32 = 0.99;
+
+/// Returns the properties of a contact between a particle and the arena's edges, by combining the
+/// particle's material with the arena's according to the config
+pub fn arena_contact_material(particle: &Object, config: &SimConfig) -> Material {
+    let mut material = particle
+        .material
+        .combine(&config.arena_material, &config.material_combine);
+    material.restitution = material.restitution.clamp(0.0, MAX_ARENA_RESTITUTION);
+    return material;
+}
+
+/// Move a particle which has moved past any of the arena's edges, e.g. while it was integrated, back
+/// onto them, given how far it reaches from its position (see `arena_extents`). Its velocity is
+/// kept, so that it then bounces off those edges.
+fn move_onto_arena_edges(particle: &mut Object, extents: &BoundingBox, config: &SimConfig) {
+    let position = &mut particle.transform.position;
+    position.x = position
+        .x
+        .max(-extents.min.x)
+        .min(config.screen_width - extents.max.x);
+    position.y = position
+        .y
+        .max(-extents.min.y)
+        .min(config.screen_height - extents.max.y);
 }
 
-pub fn simulation_tick(particles: &mut Vec<Object>, time_elapsed_seconds: f64) {
+/// Handle each particle's contact with the arena's edges over `time_elapsed_seconds`, after it's
+/// been integrated and has collided with the other particles: friction and rolling resistance against
+/// the edges it's pressed into, then bouncing off them. Clears each particle's force and torque, so
+/// that the next step starts afresh.
+///
+/// Returns how many particles were reset to the arena's center, with no velocity, since their bounces
+/// couldn't be calculated (see `BounceError`).
+pub(crate) fn resolve_arena_contacts(
+    particles: &mut [Object],
+    config: &SimConfig,
+    time_elapsed_seconds: f64,
+) -> u64 {
+    let mut resets = 0;
     for p in particles.iter_mut() {
-        println!(
-            "Before calculations: X={}, X_vel={}, Y={}, Y_vel={}",
-            p.transform.position.x, p.velocity.x, p.transform.position.y, p.velocity.y
-        );
-
-        // TODO: resume. Implement friction such that I can make gravity not be a global constant
-        p.force.y += p.mass * GRAVITY_MS;
-        p.velocity += &p.force / p.mass * time_elapsed_seconds as f32;
-        p.transform.position += &p.velocity * time_elapsed_seconds as f32;
-        println!( "{}; {}; {}; {}", p.force, p.velocity, p.transform.position, p.force);
+        let extents = arena_extents(p, config);
+        move_onto_arena_edges(p, &extents, config);
+        // Friction and rolling resistance depend on the force pressing the particle into the
+        // arena's edges, so apply them before clearing the force
+        let material = arena_contact_material(p, config);
+        let dt = time_elapsed_seconds as f32;
+        p.velocity += calculate_friction_deceleration(p, &material, &extents, config, dt);
+        p.angular_velocity += calculate_rolling_resistance(p, &material, &extents, config, dt);
+
         p.force = XYZ {
             x: 0.0,
             y: 0.0,
             z: 0.0,
         };
+        p.torque = XYZ {
+            x: 0.0,
+            y: 0.0,
+            z: 0.0,
+        };
 
         // p.velocity.y += calculate_gravity_effect_on_velocity(p, GRAVITY_MS, time_elapsed_seconds);
 
-        p.velocity.x += calculate_friction_deceleration(p, FRICTION_DYNAMIC_COEFFICIENT);
-
-        let bounce_result = calculate_bounce(p, BOUNCE_COEFFICIENT, time_elapsed_seconds);
+        let bounce_result = calculate_bounce(p, &material, &extents, time_elapsed_seconds, config);
 
         match bounce_result {
             Ok(bounce_result) => {
@@ -488,125 +592,24 @@ pub fn simulation_tick(particles: &mut Vec<Object>, time_elapsed_seconds: f64) {
                     bounce_result.position.y,
                     bounce_result.velocity.x,
                     bounce_result.velocity.y,
+                    &extents,
+                    config,
                 );
             }
-            Err(e) => {
-                match e {
-                    BounceError::CalculationDepthExceeded => {
-                        println!("Warning! Calculation depth exceeded when calculating bounces. Resetting particle parameters");
-                    }
-                    BounceError::OutOfBoundsError(oob) => {
-                        println!("Warning! Failed to calculate bounces. Input particle out of bounds. Resetting particle parameters");
-                        println!("{}", oob);
-                    }
-                }
+            Err(_) => {
+                // The caller decides whether to report these
+                resets += 1;
                 set_particle_properties_within_bounds(
                     p,
-                    0.5 * SCREEN_WIDTH,
-                    0.5 * SCREEN_HEIGHT,
+                    0.5 * config.screen_width,
+                    0.5 * config.screen_height,
                     0.0,
                     0.0,
+                    &extents,
+                    config,
                 );
             }
         }
-
-        println!(
-            "After calculations: Y={}, Y_vel={}, X={}, X_vel={}",
-            p.transform.position.y, p.velocity.y, p.transform.position.x, p.velocity.x
-        );
-    }
-}
-
-// FPS limiter copied from https://github.com/not-fl3/macroquad/issues/380#issuecomment-1026728046
-// fn limit_fps() {
-//     let minimum_frame_time = 1. / 1.; // 60 FPS
-//     let frame_time = get_frame_time();
-//     println!("Frame time: {}ms", frame_time * 1000.);
-//     if frame_time < minimum_frame_time {
-//         let time_to_sleep = (minimum_frame_time - frame_time) * 1000.;
-//         println!("Sleep for {}ms", time_to_sleep);
-//         std::thread::sleep(std::time::Duration::from_millis(time_to_sleep as u64));
-//     }
-// }
-
-pub async fn p_main() {
-    // Setup
-    request_new_screen_size(SCREEN_WIDTH, SCREEN_HEIGHT);
-    let mut particles: Vec<Object> = Vec::new();
-    particles.push(Object {
-        transform: Transform {
-            position: XYZ {
-                x: 0.5 * SCREEN_WIDTH,
-                y: convert_meters_to_pixels(72.0 - 50.0, PIXELS_PER_METER),
-                z: 0.0,
-            },
-            rotation: Quat {
-                w: 0.0,
-                x: 0.0,
-                y: 0.0,
-                z: 0.0,
-            },
-            scale: XYZ {
-                x: 1.0,
-                y: 1.0,
-                z: 1.0,
-            },
-        },
-        velocity: XYZ {
-            x: 55.0,
-            y: 1.0,
-            z: 0.0,
-        },
-        force: XYZ {
-            x: 0.0,
-            y: 0.0,
-            z: 0.0,
-        },
-        // TODO: unify this with the position stuff above
-        collider: colliders::ColliderType::SPHERE {
-            center: XYZ {
-                x: 0.0,
-                y: 0.0,
-                z: 0.0,
-            },
-            radius: 10.0,
-        },
-        mass: 1.0,
-    });
-
-    // As of 2024-05-09, 2550 is my maximum number of particles for constant >= 140 FPS
-    // for x in 1..500 {
-    //     particles.push(Particle {
-    //         x_pos: PARTICLE_RADIUS_PX + (x * 1) as f32,
-    //         y_pos: PARTICLE_RADIUS_PX +  (x * 1) as f32,
-    //         x_velocity_m_s: 250.0,
-    //         y_velocity_m_s: 250.0,
-    //     });
-    // }
-
-    // Constraint checks: check for any unsupported parameter values that aren't obviously ridiculous.
-    // A negative bounce coefficient makes no sense. Either an object bounces (val >=0) or doesn't (val == 0)
-    assert!(BOUNCE_COEFFICIENT >= 0.0);
-    // For accurate results, the particle should spawn fully within simulation bounds
-    assert!(particles[0].transform.position.x >= PARTICLE_RADIUS_PX);
-    assert!(particles[0].transform.position.y >= PARTICLE_RADIUS_PX);
-    assert!(particles[0].transform.position.x <= SCREEN_WIDTH - PARTICLE_RADIUS_PX);
-    assert!(particles[0].transform.position.y <= SCREEN_HEIGHT - PARTICLE_RADIUS_PX);
-
-    let mut last_tick_time = get_time();
-
-    // Main loop
-    loop {
-        let now = get_time();
-        let time_elapsed = now - last_tick_time;
-        simulation_tick(&mut particles, time_elapsed);
-
-        clear_background(BLACK);
-        draw_particles(&particles);
-        draw_stats(&particles);
-
-        last_tick_time = now;
-
-        next_frame().await
     }
+    return resets;
 }
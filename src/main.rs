@@ -1,4 +1,4 @@
 #[macroquad::main("BasicShapes")]
 async fn main() {
-    particle_sim::p_main().await;
+    particle_sim::render::p_main().await;
 }
@@ -0,0 +1,71 @@
+//! Mass, center of mass and inertia, derived from a collider's shape
+use crate::colliders::{parallel_axis, ColliderType};
+use crate::transform::Mat3;
+use crate::xyz::XYZ;
+
+/// How an object's mass is distributed, which determines how it responds to forces and torques.
+///
+/// These are usually derived from a collider and a density with `from_density`, so that e.g. a
+/// bigger sphere is heavier, and harder to spin. Any of them can then be overridden, e.g. for an
+/// object that's hollow, or weighted to one side.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct MassProperties {
+    // mass in kilograms, or f32::INFINITY for immovable objects
+    pub mass: f32,
+    // The point the mass is centered on, in the same space as the collider it was derived from
+    pub center_of_mass: XYZ,
+    // The inertia tensor about the center of mass, in kg px^2
+    pub inertia: Mat3,
+}
+
+impl MassProperties {
+    /// Returns the mass properties of a collider filled evenly with `density`, in kilograms per cubic
+    /// pixel. Colliders with infinite volume (i.e. planes) are immovable, and colliders with no volume
+    /// (i.e. segments and `NONE`) are massless; use `from_mass` to give them a mass.
+    pub fn from_density(collider: &ColliderType, density: f32) -> MassProperties {
+        let volume = collider.volume();
+        let mass = if volume.is_infinite() {
+            f32::INFINITY
+        } else {
+            density * volume
+        };
+        return MassProperties::from_mass(collider, mass);
+    }
+
+    /// Returns the mass properties of a collider with `mass` spread evenly through it
+    pub fn from_mass(collider: &ColliderType, mass: f32) -> MassProperties {
+        let center_of_mass = collider.centroid();
+        // Immovable objects can't be spun, however they're shaped
+        let inertia = if mass.is_finite() {
+            collider.inertia_tensor(mass, center_of_mass)
+        } else {
+            Mat3::ZERO
+        };
+        return MassProperties {
+            mass,
+            center_of_mass,
+            inertia,
+        };
+    }
+
+    /// Overrides the center of mass, keeping the inertia about it as it is
+    pub fn with_center_of_mass(self, center_of_mass: XYZ) -> MassProperties {
+        return MassProperties {
+            center_of_mass,
+            ..self
+        };
+    }
+
+    /// Overrides the inertia tensor about the center of mass
+    pub fn with_inertia(self, inertia: Mat3) -> MassProperties {
+        return MassProperties { inertia, ..self };
+    }
+
+    /// Returns the inertia tensor about `point` rather than the center of mass
+    pub fn inertia_about(&self, point: XYZ) -> Mat3 {
+        if !self.mass.is_finite() {
+            return Mat3::ZERO;
+        }
+        return parallel_axis(self.inertia, self.mass, self.center_of_mass - point);
+    }
+}
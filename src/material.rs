@@ -0,0 +1,83 @@
+//! Surface properties, which decide how objects bounce off and slide against each other
+
+/// How an object's surface responds to contact. Every object has one, including immovable ones
+/// such as walls, and so do the arena's edges (see `SimConfig::arena_material`).
+///
+/// When two surfaces touch, each property is combined from both materials, according to the
+/// `CombineRules` in the config.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Material {
+    /// The bounciness of the surface, where 0 means no bounce, and 1 means a perfectly elastic bounce
+    pub restitution: f32,
+    /// The friction coefficient which must be overcome for a resting object to start sliding
+    pub static_friction: f32,
+    /// The friction coefficient which slows an object that's already sliding
+    pub dynamic_friction: f32,
+    /// How strongly the surface resists an object rolling over it, as a coefficient of the normal force
+    pub rolling_resistance: f32,
+}
+
+impl Default for Material {
+    fn default() -> Self {
+        return Material {
+            restitution: 0.9,
+            static_friction: 0.01,
+            dynamic_friction: 0.005,
+            rolling_resistance: 0.0,
+        };
+    }
+}
+
+impl Material {
+    /// Returns the properties of a contact between this material and `other`
+    pub fn combine(&self, other: &Material, rules: &CombineRules) -> Material {
+        let friction = rules.friction;
+        return Material {
+            restitution: rules
+                .restitution
+                .combine(self.restitution, other.restitution),
+            static_friction: friction.combine(self.static_friction, other.static_friction),
+            dynamic_friction: friction.combine(self.dynamic_friction, other.dynamic_friction),
+            rolling_resistance: friction.combine(self.rolling_resistance, other.rolling_resistance),
+        };
+    }
+}
+
+/// Selects how each property of two touching materials is combined
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct CombineRules {
+    pub restitution: CombineMode,
+    /// Used for the friction coefficients and rolling resistance
+    pub friction: CombineMode,
+}
+
+impl Default for CombineRules {
+    fn default() -> Self {
+        return CombineRules {
+            restitution: CombineMode::Average,
+            friction: CombineMode::Average,
+        };
+    }
+}
+
+/// Selects how a property of two touching materials is combined into one value for the contact
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum CombineMode {
+    /// The smaller value wins, e.g. a ball bounces poorly off anything soft
+    Min,
+    /// The larger value wins, e.g. a ball bounces well off anything springy
+    Max,
+    Average,
+    Multiply,
+}
+
+impl CombineMode {
+    pub fn combine(&self, a: f32, b: f32) -> f32 {
+        match self {
+            CombineMode::Min => a.min(b),
+            CombineMode::Max => a.max(b),
+            CombineMode::Average => (a + b) * 0.5,
+            CombineMode::Multiply => a * b,
+        }
+    }
+}
@@ -0,0 +1,223 @@
+//! Molecular dynamics: the Lennard-Jones potential between neutral atoms, the neighbor lists which
+//! keep it fast, and the reduced units it's usually simulated in
+use crate::broadphase::{BoundingBox, Broadphase, SweepAndPrune};
+use crate::forces::ForceGenerator;
+use crate::objects::Object;
+use crate::xyz::{DotProductXyz, XYZ};
+
+/// Lennard-Jones simulations are usually described in reduced units, where the atoms' size sigma,
+/// their attraction epsilon and their mass are all 1. This converts reduced quantities into the
+/// simulation's units, so that results can be compared with the literature, e.g. a 2D fluid at a
+/// reduced temperature of 0.45 sits near its triple point.
+///
+/// Temperatures are in energy units, i.e. they're Boltzmann's constant times the temperature.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct ReducedUnits {
+    // The distance at which the potential between two atoms is zero, in px
+    pub sigma: f32,
+    // The depth of the potential well
+    pub epsilon: f32,
+    // The mass of one atom, in kg
+    pub mass: f32,
+}
+
+impl ReducedUnits {
+    pub fn length(&self, reduced: f32) -> f32 {
+        return reduced * self.sigma;
+    }
+
+    pub fn energy(&self, reduced: f32) -> f32 {
+        return reduced * self.epsilon;
+    }
+
+    /// The unit of time is sigma * sqrt(mass / epsilon), roughly how long an atom takes to cross
+    /// its neighbor's potential well
+    pub fn time(&self, reduced: f32) -> f32 {
+        return reduced * self.sigma * (self.mass / self.epsilon).sqrt();
+    }
+
+    pub fn velocity(&self, reduced: f32) -> f32 {
+        return reduced * (self.epsilon / self.mass).sqrt();
+    }
+
+    pub fn force(&self, reduced: f32) -> f32 {
+        return reduced * self.epsilon / self.sigma;
+    }
+
+    pub fn temperature(&self, reduced: f32) -> f32 {
+        return reduced * self.epsilon;
+    }
+}
+
+/// Remembers which pairs of objects are within `cutoff + skin` of each other, so that a pair
+/// potential only has to test those pairs rather than every pair. The list stays valid until some
+/// object has moved more than half the skin since it was built, since until then, no pair outside
+/// the list can have come within the cutoff. A larger skin means fewer rebuilds, but more pairs.
+pub struct NeighborList {
+    pub skin: f32,
+    pairs: Vec<(usize, usize)>,
+    // Where each object was when the list was built
+    reference_positions: Vec<XYZ>,
+    // The range the list was built for, which is the cutoff plus the skin
+    range: f32,
+    rebuilds: usize,
+}
+
+impl NeighborList {
+    pub fn new(skin: f32) -> NeighborList {
+        return NeighborList {
+            skin,
+            pairs: Vec::new(),
+            reference_positions: Vec::new(),
+            range: 0.0,
+            rebuilds: 0,
+        };
+    }
+
+    /// How many times the list has been built, e.g. to tune the skin
+    pub fn rebuilds(&self) -> usize {
+        return self.rebuilds;
+    }
+
+    /// Returns every pair of indices (i, j), where i < j, of movable objects which may be within
+    /// `cutoff` of each other, rebuilding the list first if it's out of date
+    pub fn pairs(&mut self, objects: &[Object], cutoff: f32) -> &[(usize, usize)] {
+        if self.needs_rebuild(objects, cutoff) {
+            self.rebuild(objects, cutoff);
+        }
+        return &self.pairs;
+    }
+
+    fn needs_rebuild(&self, objects: &[Object], cutoff: f32) -> bool {
+        if self.rebuilds == 0
+            || self.reference_positions.len() != objects.len()
+            || self.range != cutoff + self.skin
+        {
+            return true;
+        }
+        let max_displacement = 0.5 * self.skin;
+        return objects
+            .iter()
+            .zip(self.reference_positions.iter())
+            .any(|(p, reference)| {
+                let displacement = p.transform.position - *reference;
+                return displacement.dot(displacement) > max_displacement * max_displacement;
+            });
+    }
+
+    fn rebuild(&mut self, objects: &[Object], cutoff: f32) {
+        self.range = cutoff + self.skin;
+        self.reference_positions = objects.iter().map(|p| p.transform.position).collect();
+        self.rebuilds += 1;
+
+        // Boxes of half the range around each object overlap whenever the objects are within the
+        // range of each other along every axis, so the broadphase finds every candidate pair
+        let movable: Vec<usize> = (0..objects.len())
+            .filter(|idx| objects[*idx].inverse_mass() != 0.0)
+            .collect();
+        let half_range = 0.5 * self.range;
+        let extent = XYZ {
+            x: half_range,
+            y: half_range,
+            z: half_range,
+        };
+        let bounds: Vec<BoundingBox> = movable
+            .iter()
+            .map(|idx| {
+                let position = objects[*idx].transform.position;
+                return BoundingBox {
+                    min: position - extent,
+                    max: position + extent,
+                };
+            })
+            .collect();
+        let range_squared = self.range * self.range;
+        self.pairs = SweepAndPrune
+            .candidate_pairs(&bounds)
+            .into_iter()
+            .map(|(i, j)| (movable[i], movable[j]))
+            .map(|(i, j)| (usize::min(i, j), usize::max(i, j)))
+            .filter(|(i, j)| {
+                let offset = objects[*j].transform.position - objects[*i].transform.position;
+                return offset.dot(offset) <= range_squared;
+            })
+            .collect();
+        // The broadphase doesn't guarantee any order, so sort the pairs to keep results deterministic
+        self.pairs.sort_unstable();
+    }
+}
+
+/// The Lennard-Jones potential between every pair of movable objects, 4 epsilon ((sigma / r)^12 -
+/// (sigma / r)^6), which models neutral atoms: they repel strongly when they overlap, and attract
+/// weakly otherwise, most strongly at a distance of 2^(1/6) sigma.
+///
+/// Pairs further apart than the cutoff don't interact, and the potential is shifted up so that it's
+/// zero at the cutoff, so that energy doesn't jump when pairs cross it. The shift doesn't change the
+/// forces. Pairs are found with a `NeighborList`.
+pub struct LennardJones {
+    // The distance at which the potential is zero, in px
+    pub sigma: f32,
+    // The depth of the potential well
+    pub epsilon: f32,
+    // In px. 2.5 sigma is the usual choice.
+    pub cutoff: f32,
+    pub neighbor_list: NeighborList,
+}
+
+impl LennardJones {
+    /// Returns the potential for atoms of the given reduced units, with the cutoff and the neighbor
+    /// list's skin given in reduced units too
+    pub fn from_units(units: &ReducedUnits, cutoff: f32, skin: f32) -> LennardJones {
+        return LennardJones {
+            sigma: units.sigma,
+            epsilon: units.epsilon,
+            cutoff: units.length(cutoff),
+            neighbor_list: NeighborList::new(units.length(skin)),
+        };
+    }
+
+    /// Returns the potential energy of a pair of objects `distance` apart, including the shift
+    pub fn potential(&self, distance: f32) -> f32 {
+        if distance > self.cutoff {
+            return 0.0;
+        }
+        return self.unshifted_potential(distance) - self.unshifted_potential(self.cutoff);
+    }
+
+    fn unshifted_potential(&self, distance: f32) -> f32 {
+        let s6 = (self.sigma / distance).powi(6);
+        return 4.0 * self.epsilon * (s6 * s6 - s6);
+    }
+
+    /// Returns the total potential energy of every pair of objects
+    pub fn potential_energy(&mut self, objects: &[Object]) -> f32 {
+        let cutoff = self.cutoff;
+        let mut energy = 0.0;
+        for (i, j) in self.neighbor_list.pairs(objects, cutoff).to_vec() {
+            let offset = objects[j].transform.position - objects[i].transform.position;
+            energy += self.potential(offset.dot(offset).sqrt());
+        }
+        return energy;
+    }
+}
+
+impl ForceGenerator for LennardJones {
+    fn accumulate(&mut self, objects: &[Object], forces: &mut [XYZ]) {
+        let cutoff_squared = self.cutoff * self.cutoff;
+        let sigma_squared = self.sigma * self.sigma;
+        let epsilon = self.epsilon;
+        for (i, j) in self.neighbor_list.pairs(objects, self.cutoff) {
+            let offset = objects[*j].transform.position - objects[*i].transform.position;
+            let distance_squared = offset.dot(offset);
+            if distance_squared > cutoff_squared || distance_squared == 0.0 {
+                continue;
+            }
+            // -dV/dr = 24 epsilon (2 (sigma / r)^12 - (sigma / r)^6) / r, along the unit vector
+            // offset / r. This pushes J away from I when positive.
+            let s6 = (sigma_squared / distance_squared).powi(3);
+            let scale = 24.0 * epsilon * (2.0 * s6 * s6 - s6) / distance_squared;
+            forces[*i] -= offset * scale;
+            forces[*j] += offset * scale;
+        }
+    }
+}
@@ -0,0 +1,233 @@
+//! Mutual gravitational attraction between every pair of objects
+use crate::forces::ForceGenerator;
+use crate::objects::Object;
+use crate::xyz::{DotProductXyz, XYZ};
+
+// The deepest an octree node may be, so that objects at (almost) the same position don't make us
+// split forever. Deeper than this, a node's objects are summed directly.
+const MAX_OCTREE_DEPTH: u32 = 24;
+
+/// Selects how the attraction between every pair of objects is summed
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum NBodyMethod {
+    /// Sums every pair exactly, in O(n^2) time. Only suitable for small numbers of objects, or as a reference.
+    Direct,
+    /// Groups distant objects together in an octree, and attracts to each group's center of mass as a
+    /// whole, in O(n log n) time. A group is used if its width divided by its distance is less than the
+    /// `opening_angle`, so 0 gives the exact sum, and larger angles are faster but less accurate. 0.5 is
+    /// a common compromise.
+    BarnesHut { opening_angle: f32 },
+}
+
+/// Newtonian gravity, where every object attracts every other with a force of G m1 m2 / r^2. Immovable
+/// (infinitely heavy) objects take no part, since they'd attract everything infinitely strongly.
+pub struct NBodyGravity {
+    // G, in px^3 / (kg s^2)
+    pub gravitational_constant: f32,
+    // Stops the attraction growing without bound as objects pass close to each other, by acting as if
+    // every pair were always at least this far apart. This stands in for objects not really being points.
+    pub softening: f32,
+    pub method: NBodyMethod,
+}
+
+impl ForceGenerator for NBodyGravity {
+    fn accumulate(&mut self, objects: &[Object], forces: &mut [XYZ]) {
+        let bodies: Vec<usize> = (0..objects.len())
+            .filter(|idx| objects[*idx].inverse_mass() != 0.0)
+            .collect();
+        match self.method {
+            NBodyMethod::Direct => self.accumulate_direct(objects, &bodies, forces),
+            NBodyMethod::BarnesHut { opening_angle } => {
+                let octree = Octree::new(objects, bodies.clone());
+                for idx in bodies {
+                    forces[idx] += octree.attraction(self, objects, idx, opening_angle);
+                }
+            }
+        }
+    }
+}
+
+impl NBodyGravity {
+    /// Returns the force on a body of `mass` at `position` from a point mass elsewhere
+    fn attraction(&self, mass: f32, position: XYZ, other_mass: f32, other_position: XYZ) -> XYZ {
+        let offset = other_position - position;
+        let distance_squared = offset.dot(offset) + self.softening * self.softening;
+        if distance_squared == 0.0 {
+            return XYZ::default();
+        }
+        // G m1 m2 / d^2, along the unit vector offset / d
+        let scale = self.gravitational_constant * mass * other_mass
+            / (distance_squared * distance_squared.sqrt());
+        return offset * scale;
+    }
+
+    fn accumulate_direct(&self, objects: &[Object], bodies: &[usize], forces: &mut [XYZ]) {
+        for (n, i) in bodies.iter().enumerate() {
+            for j in &bodies[n + 1..] {
+                let (a, b) = (&objects[*i], &objects[*j]);
+                // Equal and opposite, so we only need to calculate each pair once
+                let force =
+                    self.attraction(a.mass, a.transform.position, b.mass, b.transform.position);
+                forces[*i] += force;
+                forces[*j] -= force;
+            }
+        }
+    }
+}
+
+/// A cube of space containing some of the objects, split into eight smaller cubes unless it's small
+/// enough to sum directly
+struct OctreeNode {
+    center: XYZ,
+    half_size: f32,
+    mass: f32,
+    center_of_mass: XYZ,
+    // Indices into the octree's nodes. Empty for leaves.
+    children: Vec<usize>,
+    // The objects in a leaf. Empty for other nodes.
+    bodies: Vec<usize>,
+}
+
+struct Octree {
+    // The root is the first node
+    nodes: Vec<OctreeNode>,
+}
+
+impl Octree {
+    fn new(objects: &[Object], bodies: Vec<usize>) -> Octree {
+        let mut octree = Octree { nodes: Vec::new() };
+        if bodies.is_empty() {
+            return octree;
+        }
+        // The root is the smallest cube around every object
+        let mut min = objects[bodies[0]].transform.position;
+        let mut max = min;
+        for idx in &bodies {
+            let p = objects[*idx].transform.position;
+            min = XYZ {
+                x: min.x.min(p.x),
+                y: min.y.min(p.y),
+                z: min.z.min(p.z),
+            };
+            max = XYZ {
+                x: max.x.max(p.x),
+                y: max.y.max(p.y),
+                z: max.z.max(p.z),
+            };
+        }
+        let center = (min + max) * 0.5;
+        let extent = max - min;
+        let half_size = 0.5 * extent.x.max(extent.y).max(extent.z);
+        octree.build(objects, bodies, center, half_size, 0);
+        return octree;
+    }
+
+    /// Adds a node containing `bodies`, and all of the nodes below it, returning its index
+    fn build(
+        &mut self,
+        objects: &[Object],
+        bodies: Vec<usize>,
+        center: XYZ,
+        half_size: f32,
+        depth: u32,
+    ) -> usize {
+        let mut mass = 0.0;
+        let mut weighted_position = XYZ::default();
+        for idx in &bodies {
+            mass += objects[*idx].mass;
+            weighted_position += objects[*idx].transform.position * objects[*idx].mass;
+        }
+        let center_of_mass = if mass > 0.0 {
+            &weighted_position / mass
+        } else {
+            center
+        };
+        let node_idx = self.nodes.len();
+        self.nodes.push(OctreeNode {
+            center,
+            half_size,
+            mass,
+            center_of_mass,
+            children: Vec::new(),
+            bodies: Vec::new(),
+        });
+        if bodies.len() <= 1 || depth == MAX_OCTREE_DEPTH || half_size <= 0.0 {
+            self.nodes[node_idx].bodies = bodies;
+            return node_idx;
+        }
+
+        // Split into octants, numbered by which side of the center they're on in each axis
+        let mut octants: Vec<Vec<usize>> = vec![Vec::new(); 8];
+        for idx in bodies {
+            let p = objects[idx].transform.position;
+            let octant = (p.x > center.x) as usize
+                | ((p.y > center.y) as usize) << 1
+                | ((p.z > center.z) as usize) << 2;
+            octants[octant].push(idx);
+        }
+        let quarter = 0.5 * half_size;
+        for (octant, octant_bodies) in octants.into_iter().enumerate() {
+            if octant_bodies.is_empty() {
+                continue;
+            }
+            let side = |bit: usize| if octant & bit == 0 { -quarter } else { quarter };
+            let child_center = center
+                + XYZ {
+                    x: side(1),
+                    y: side(2),
+                    z: side(4),
+                };
+            let child = self.build(objects, octant_bodies, child_center, quarter, depth + 1);
+            self.nodes[node_idx].children.push(child);
+        }
+        return node_idx;
+    }
+
+    /// Returns the attraction on object `idx` from every other object in the octree
+    fn attraction(
+        &self,
+        gravity: &NBodyGravity,
+        objects: &[Object],
+        idx: usize,
+        opening_angle: f32,
+    ) -> XYZ {
+        let mut total = XYZ::default();
+        if self.nodes.is_empty() {
+            return total;
+        }
+        let body = &objects[idx];
+        let position = body.transform.position;
+        let mut stack = vec![0];
+        while let Some(node_idx) = stack.pop() {
+            let node = &self.nodes[node_idx];
+            if node.children.is_empty() {
+                for other in &node.bodies {
+                    if *other != idx {
+                        let other = &objects[*other];
+                        total += gravity.attraction(
+                            body.mass,
+                            position,
+                            other.mass,
+                            other.transform.position,
+                        );
+                    }
+                }
+                continue;
+            }
+
+            // A node containing the object would attract it to itself, so we always open those
+            let offset = position - node.center;
+            let contains = offset.x.abs() <= node.half_size
+                && offset.y.abs() <= node.half_size
+                && offset.z.abs() <= node.half_size;
+            let to_mass = node.center_of_mass - position;
+            let distance = to_mass.dot(to_mass).sqrt();
+            if !contains && 2.0 * node.half_size < opening_angle * distance {
+                total += gravity.attraction(body.mass, position, node.mass, node.center_of_mass);
+            } else {
+                stack.extend(node.children.iter());
+            }
+        }
+        return total;
+    }
+}
@@ -1,15 +1,141 @@
 use crate::colliders::ColliderType;
-use crate::transform::Transform;
+use crate::mass::MassProperties;
+use crate::material::Material;
+use crate::transform::{Mat3, Transform};
 use crate::xyz::XYZ;
 
+/// The drag coefficient of a smooth sphere, which objects have unless told otherwise
+pub const DEFAULT_DRAG_COEFFICIENT: f32 = 0.47;
+
+#[derive(Debug, Clone)]
 pub struct Object {
     // signed velocity in m/s^2 in the X and Y axes
     pub velocity: XYZ,
     // signed force in Newtons in the X and Y axes
     pub force: XYZ,
-    // mass in kilograms
+    // signed angular velocity in radians per second, about each axis through the object's position
+    pub angular_velocity: XYZ,
+    // signed torque in Newton-pixels about each axis through the object's position. Like `force`,
+    // this accumulates over a tick, and is cleared after it.
+    pub torque: XYZ,
+    // mass in kilograms. Use f32::INFINITY for immovable objects, e.g. walls
     pub mass: f32,
+    // The inertia tensor in the object's local space, about its position. When None, it's derived
+    // from the collider and mass, as if the mass were spread evenly through the collider, and
+    // cached here by `from_density` or the first `World::step`. Set it back to None after changing
+    // the collider, scale or mass to have it derived again.
+    pub inertia: Option<Mat3>,
+    // How the object's surface bounces and slides against others
+    pub material: Material,
+    // How streamlined the object is, which scales the quadratic drag on it, see `forces::AerodynamicDrag`.
+    // Around 0.47 for a sphere, 1.05 for a cube, and 0.04 for a teardrop.
+    pub drag_coefficient: f32,
+    // electric charge in Coulombs, which electric and magnetic fields act on
+    pub charge: f32,
 
     pub transform: Transform,
+    // The collider's shape in the object's local space, i.e. relative to the object's position,
+    // rotation and scale. See `world_collider`.
     pub collider: ColliderType,
 }
+
+impl Object {
+    /// Returns a motionless object whose mass is derived from its collider and `density`, in
+    /// kilograms per cubic pixel, so that e.g. a bigger sphere is heavier. The collider is scaled by
+    /// the transform before its volume is measured.
+    pub fn from_density(transform: Transform, collider: ColliderType, density: f32) -> Object {
+        let scaled = collider.transformed(&Transform {
+            scale: transform.scale,
+            ..Transform::IDENTITY
+        });
+        let zero = XYZ::default();
+        let properties = MassProperties::from_density(&scaled, density);
+        return Object {
+            velocity: zero,
+            force: zero,
+            angular_velocity: zero,
+            torque: zero,
+            mass: properties.mass,
+            inertia: Some(properties.inertia_about(zero)),
+            material: Material::default(),
+            drag_coefficient: DEFAULT_DRAG_COEFFICIENT,
+            charge: 0.0,
+            transform,
+            collider,
+        };
+    }
+
+    /// Overrides the object's mass and inertia. The properties are in the object's local space,
+    /// after scaling, e.g. as returned by `MassProperties::from_density` for the scaled collider.
+    ///
+    /// Objects rotate about their position, so for realistic motion the center of mass should be at
+    /// the origin. If it isn't, the object spins as if pinned at its position.
+    pub fn set_mass_properties(&mut self, properties: &MassProperties) {
+        self.mass = properties.mass;
+        self.inertia = Some(properties.inertia_about(XYZ::default()));
+    }
+
+    /// Returns 1 / mass, or 0 for immovable (infinitely heavy) objects. Collision response is
+    /// weighted by this, so that an immovable object takes none of the impulse.
+    pub fn inverse_mass(&self) -> f32 {
+        if !self.mass.is_finite() || self.mass <= 0.0 {
+            return 0.0;
+        }
+        return 1.0 / self.mass;
+    }
+
+    /// Returns the object's inertia tensor in world space, derived from its collider and mass unless
+    /// overridden. Objects rotate about their position, so the tensor is measured about that too. For
+    /// realistic motion, the collider's centroid should be at the object's position.
+    pub fn inertia_tensor(&self) -> Mat3 {
+        let local = match self.inertia {
+            Some(local) => local,
+            None => self.local_inertia_tensor(),
+        };
+        // An unset (e.g. all-zero) rotation is treated as no rotation
+        let rotation = if self.transform.rotation.is_normalized() {
+            Mat3::from_quat(self.transform.rotation)
+        } else {
+            Mat3::IDENTITY
+        };
+        return rotation * local * rotation.transpose();
+    }
+
+    /// Returns the inertia tensor derived from the object's scaled collider and mass, in its local
+    /// space. This can be slow for complex colliders, so it's cached in `inertia` where possible.
+    pub fn local_inertia_tensor(&self) -> Mat3 {
+        let scaled = self.collider.transformed(&Transform {
+            scale: self.transform.scale,
+            ..Transform::IDENTITY
+        });
+        return scaled.inertia_tensor(self.mass, XYZ::default());
+    }
+
+    /// Returns the inverse of the inertia tensor, which maps torques to angular accelerations. Like
+    /// `inverse_mass`, this is 0 for immovable objects, as well as for colliders which can't spin.
+    pub fn inverse_inertia_tensor(&self) -> Mat3 {
+        if self.inverse_mass() == 0.0 {
+            return Mat3::ZERO;
+        }
+        let inertia = self.inertia_tensor();
+        let largest = inertia.x_axis.x.max(inertia.y_axis.y).max(inertia.z_axis.z);
+        if largest <= 0.0 || !largest.is_finite() {
+            return Mat3::ZERO;
+        }
+        // Thin shapes (e.g. segments) can't be spun about some axes, leaving the tensor singular,
+        // so we nudge it to be invertible
+        let regularized = inertia + Mat3::from_diagonal(glam::Vec3::splat(largest * 1e-6));
+        return regularized.inverse();
+    }
+
+    /// Returns the object's collider positioned in the world, by composing it with the object's transform
+    pub fn world_collider(&self) -> ColliderType {
+        return self.world_collider_at(&self.transform);
+    }
+
+    /// Returns the object's collider as it would be positioned in the world if the object had the
+    /// given transform, e.g. an interpolated transform for rendering
+    pub fn world_collider_at(&self, transform: &Transform) -> ColliderType {
+        return self.collider.transformed(transform);
+    }
+}
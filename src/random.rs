@@ -0,0 +1,39 @@
+//! A small seedable pseudo-random number generator, for the parts of the simulation which need
+//! noise, e.g. thermostats. Seeding makes those runs reproducible.
+
+/// The SplitMix64 generator. It's fast and statistically sound for simulation, though not for
+/// anything security related.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    /// Two generators with the same seed produce the same numbers
+    pub fn new(seed: u64) -> Rng {
+        return Rng { state: seed };
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E3779B97F4A7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D049BB133111EB);
+        return z ^ (z >> 31);
+    }
+
+    /// Returns a number drawn uniformly from [0, 1)
+    pub fn uniform(&mut self) -> f32 {
+        // The top 24 bits, which is as many as an f32 can hold exactly
+        return (self.next_u64() >> 40) as f32 / (1u64 << 24) as f32;
+    }
+
+    /// Returns a number drawn from the standard normal distribution, i.e. with a mean of 0 and a
+    /// standard deviation of 1, using the Box-Muller transform
+    pub fn normal(&mut self) -> f32 {
+        // 1 - uniform is in (0, 1], so the logarithm is finite
+        let u1 = 1.0 - self.uniform();
+        let u2 = self.uniform();
+        return (-2.0 * u1.ln()).sqrt() * (2.0 * std::f32::consts::PI * u2).cos();
+    }
+}
@@ -0,0 +1,321 @@
+//! Drawing and the windowed main loop. Only available with the `render` feature.
+use crate::colliders;
+use crate::colliders::ColliderType;
+use crate::config::SimConfig;
+use crate::material::Material;
+use crate::objects::{Object, DEFAULT_DRAG_COEFFICIENT};
+use crate::thermostat::kinetic_temperature;
+use crate::timestep::FixedTimestep;
+use crate::transform::Transform;
+use crate::world::World;
+use crate::xyz::XYZ;
+use crate::{convert_meters_to_pixels, convert_pixels_to_meters};
+use macroquad::prelude::*;
+
+// The default color of a particle
+const PARTICLE_COLOR: Color = RED;
+// The color of boxes and planes
+const SHAPE_COLOR: Color = BLUE;
+// Planes are infinite, so we draw them as a line this long, which covers any reasonable screen
+const PLANE_DRAW_LENGTH_PX: f32 = 10000.0;
+const PLANE_THICKNESS_PX: f32 = 2.0;
+const SEGMENT_THICKNESS_PX: f32 = 2.0;
+
+// Display parameters
+const STATS_FONT_SIZE: f32 = 30.0;
+// Fraction of the screen width, measured from the right edge, at which stats are drawn
+const STATS_X_ANCHOR_FRACTION: f32 = 0.4;
+const STATS_COLOR: Color = GREEN;
+
+/// Draw the world's particles, interpolated `alpha` of the way between the previous and current
+/// physics step (see `FixedTimestep::advance`)
+pub fn draw_particles(world: &World, alpha: f32) {
+    for idx in 0..world.objects.len() {
+        if let Some(transform) = world.interpolated_transform(idx, alpha) {
+            match world.objects[idx].world_collider_at(&transform) {
+                // Objects without a collider are still drawn, e.g. as decorations
+                ColliderType::NONE => draw_circle(
+                    transform.position.x.floor(),
+                    transform.position.y.floor(),
+                    world.config().particle_radius_px,
+                    PARTICLE_COLOR,
+                ),
+                collider => draw_collider(&collider),
+            }
+        }
+    }
+}
+
+/// Draw a world space collider, projected onto the screen's XY plane
+pub fn draw_collider(collider: &ColliderType) {
+    match collider {
+        ColliderType::SPHERE { center, radius } => {
+            draw_circle(center.x, center.y, *radius, PARTICLE_COLOR);
+        }
+        ColliderType::PLANE { normal, distance } => {
+            // The plane's closest point to the origin, and the direction along it on screen
+            let origin = *normal * *distance;
+            let (dx, dy) = (-normal.y, normal.x);
+            let half_length = 0.5 * PLANE_DRAW_LENGTH_PX;
+            draw_line(
+                origin.x - dx * half_length,
+                origin.y - dy * half_length,
+                origin.x + dx * half_length,
+                origin.y + dy * half_length,
+                PLANE_THICKNESS_PX,
+                SHAPE_COLOR,
+            );
+        }
+        ColliderType::AABB {
+            center,
+            half_extents,
+        } => {
+            draw_rectangle(
+                center.x - half_extents.x,
+                center.y - half_extents.y,
+                2.0 * half_extents.x,
+                2.0 * half_extents.y,
+                SHAPE_COLOR,
+            );
+        }
+        ColliderType::OBB {
+            center,
+            half_extents,
+            rotation,
+        } => {
+            // Draw the box's cross section as two triangles between its rotated corners
+            let corner = |sx: f32, sy: f32| {
+                let local = Vec3::new(sx * half_extents.x, sy * half_extents.y, 0.0);
+                let world = *rotation * local;
+                return vec2(center.x + world.x, center.y + world.y);
+            };
+            let corners = [
+                corner(-1.0, -1.0),
+                corner(1.0, -1.0),
+                corner(1.0, 1.0),
+                corner(-1.0, 1.0),
+            ];
+            draw_triangle(corners[0], corners[1], corners[2], SHAPE_COLOR);
+            draw_triangle(corners[0], corners[2], corners[3], SHAPE_COLOR);
+        }
+        ColliderType::CAPSULE { start, end, radius } => {
+            // A line as thick as the capsule, capped with a circle at each end
+            draw_line(start.x, start.y, end.x, end.y, 2.0 * radius, SHAPE_COLOR);
+            draw_circle(start.x, start.y, *radius, SHAPE_COLOR);
+            draw_circle(end.x, end.y, *radius, SHAPE_COLOR);
+        }
+        ColliderType::POLYGON { vertices } | ColliderType::HULL { vertices } => {
+            // Draw the outline of the shape's shadow on screen as a fan of triangles
+            let outline = projected_convex_hull(vertices);
+            for idx in 1..outline.len().saturating_sub(1) {
+                draw_triangle(outline[0], outline[idx], outline[idx + 1], SHAPE_COLOR);
+            }
+        }
+        ColliderType::COMPOUND { children } => {
+            for child in children {
+                draw_collider(&child.positioned());
+            }
+        }
+        ColliderType::NONE => {}
+        ColliderType::SEGMENT { start, end } => {
+            draw_line(
+                start.x,
+                start.y,
+                end.x,
+                end.y,
+                SEGMENT_THICKNESS_PX,
+                SHAPE_COLOR,
+            );
+        }
+    }
+}
+
+/// Returns the convex hull of the points projected onto the screen's XY plane, in order around it,
+/// using Andrew's monotone chain algorithm
+fn projected_convex_hull(points: &[XYZ]) -> Vec<Vec2> {
+    let mut sorted: Vec<Vec2> = points.iter().map(|p| vec2(p.x, p.y)).collect();
+    sorted.sort_by(|p, q| p.x.total_cmp(&q.x).then(p.y.total_cmp(&q.y)));
+    if sorted.len() < 3 {
+        return sorted;
+    }
+    let turns_left = |o: Vec2, a: Vec2, b: Vec2| (a - o).perp_dot(b - o) > 0.0;
+
+    let mut hull: Vec<Vec2> = Vec::with_capacity(2 * sorted.len());
+    // Build the lower half of the hull, then the upper half
+    for pass in [sorted.clone(), sorted.iter().rev().cloned().collect()] {
+        let start_len = hull.len();
+        for p in pass {
+            while hull.len() >= start_len + 2
+                && !turns_left(hull[hull.len() - 2], hull[hull.len() - 1], p)
+            {
+                hull.pop();
+            }
+            hull.push(p);
+        }
+        // Each half's last point is the first point of the other half
+        hull.pop();
+    }
+    return hull;
+}
+
+/// Draw simulation stats to screen
+pub fn draw_stats(particles: &[Object], config: &SimConfig) {
+    // TODO: fix sum_y_positions so that it doesn't overflow or nan or whatever with 1000 particles
+    let mut sum_y_velocity = 0.0;
+    let mut sum_x_velocity = 0.0;
+    let mut sum_y_positions = 0.0;
+    for p in particles {
+        sum_y_velocity += p.velocity.y;
+        sum_x_velocity += p.velocity.x;
+        sum_y_positions += p.transform.position.y;
+    }
+    let avg_y_velocity = sum_y_velocity / particles.len() as f32;
+    let avg_x_velocity = sum_x_velocity / particles.len() as f32;
+    let y_velocity_str = "Mean Y velocity: ".to_owned() + &avg_y_velocity.to_string();
+    let x_velocity_str = "Mean X velocity: ".to_owned() + &avg_x_velocity.to_string();
+
+    let sim_height_meters = config.screen_height / config.pixels_per_meter;
+    let sim_height_meters_str = "Sim height meters: ".to_owned() + &sim_height_meters.to_string();
+
+    let particle_count_str = "Particle count: ".to_owned() + &particles.len().to_string();
+
+    // The arena is flat, so particles only move in two directions
+    let temperature_str =
+        "Kinetic temperature: ".to_owned() + &kinetic_temperature(particles, 2).to_string();
+
+    let particle_mean_altitude_px =
+        config.screen_height - (sum_y_positions / particles.len() as f32);
+    let particle_mean_altitude_meters =
+        convert_pixels_to_meters(particle_mean_altitude_px, config.pixels_per_meter);
+    let particle_mean_altitude_str =
+        "Mean altitude (m): ".to_owned() + &particle_mean_altitude_meters.to_string();
+
+    let strings: [&String; 7] = [
+        &get_fps().to_string(),
+        &y_velocity_str,
+        &x_velocity_str,
+        &sim_height_meters_str,
+        &particle_mean_altitude_str,
+        &particle_count_str,
+        &temperature_str,
+    ];
+
+    let stats_x_anchor = config.screen_width - (STATS_X_ANCHOR_FRACTION * config.screen_width);
+    let y_offset = 30.0;
+    for (idx, s) in strings.iter().enumerate() {
+        draw_text(
+            s,
+            stats_x_anchor,
+            idx as f32 * y_offset + y_offset,
+            STATS_FONT_SIZE,
+            STATS_COLOR,
+        );
+    }
+}
+
+// FPS limiter copied from https://github.com/not-fl3/macroquad/issues/380#issuecomment-1026728046
+// fn limit_fps() {
+//     let minimum_frame_time = 1. / 1.; // 60 FPS
+//     let frame_time = get_frame_time();
+//     println!("Frame time: {}ms", frame_time * 1000.);
+//     if frame_time < minimum_frame_time {
+//         let time_to_sleep = (minimum_frame_time - frame_time) * 1000.;
+//         println!("Sleep for {}ms", time_to_sleep);
+//         std::thread::sleep(std::time::Duration::from_millis(time_to_sleep as u64));
+//     }
+// }
+
+pub async fn p_main() {
+    // Setup
+    let mut world = match World::new(SimConfig::default()) {
+        Ok(world) => world,
+        Err(e) => panic!("Invalid simulation config. {}", e),
+    };
+    let config = world.config().clone();
+    request_new_screen_size(config.screen_width, config.screen_height);
+    let particle = Object {
+        transform: Transform {
+            position: XYZ {
+                x: 0.5 * config.screen_width,
+                y: convert_meters_to_pixels(72.0 - 50.0, config.pixels_per_meter),
+                z: 0.0,
+            },
+            rotation: Quat::IDENTITY,
+            scale: XYZ {
+                x: 1.0,
+                y: 1.0,
+                z: 1.0,
+            },
+        },
+        velocity: XYZ {
+            x: 55.0,
+            y: 1.0,
+            z: 0.0,
+        },
+        force: XYZ {
+            x: 0.0,
+            y: 0.0,
+            z: 0.0,
+        },
+        angular_velocity: XYZ {
+            x: 0.0,
+            y: 0.0,
+            z: 0.0,
+        },
+        torque: XYZ {
+            x: 0.0,
+            y: 0.0,
+            z: 0.0,
+        },
+        // Relative to the particle's transform
+        collider: colliders::ColliderType::SPHERE {
+            center: XYZ {
+                x: 0.0,
+                y: 0.0,
+                z: 0.0,
+            },
+            radius: 10.0,
+        },
+        mass: 1.0,
+        inertia: None,
+        material: Material::default(),
+        drag_coefficient: DEFAULT_DRAG_COEFFICIENT,
+        charge: 0.0,
+    };
+
+    // As of 2024-05-09, 2550 is my maximum number of particles for constant >= 140 FPS
+    // for x in 1..500 {
+    //     particles.push(Particle {
+    //         x_pos: PARTICLE_RADIUS_PX + (x * 1) as f32,
+    //         y_pos: PARTICLE_RADIUS_PX +  (x * 1) as f32,
+    //         x_velocity_m_s: 250.0,
+    //         y_velocity_m_s: 250.0,
+    //     });
+    // }
+
+    // For accurate results, the particle should spawn fully within simulation bounds
+    if let Err(e) = world.add_object(particle) {
+        panic!("Failed to spawn particle. {}", e);
+    }
+
+    let mut timestep = FixedTimestep::new(&config);
+    let mut arena_resets = 0;
+
+    // Main loop
+    loop {
+        let alpha = timestep.advance(&mut world, get_frame_time() as f64);
+        if world.arena_resets() > arena_resets {
+            println!(
+                "Warning! Failed to calculate bounces for {} particle(s). Resetting particle parameters",
+                world.arena_resets() - arena_resets
+            );
+            arena_resets = world.arena_resets();
+        }
+
+        clear_background(BLACK);
+        draw_particles(&world, alpha);
+        draw_stats(&world.objects, &config);
+
+        next_frame().await
+    }
+}
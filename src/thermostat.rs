@@ -0,0 +1,138 @@
+//! Thermostats, which hold the objects at a target temperature, e.g. for thermodynamic experiments.
+//!
+//! Temperatures are in energy units, i.e. they're Boltzmann's constant times the temperature, as in
+//! `molecular::ReducedUnits`.
+use crate::objects::Object;
+use crate::random::Rng;
+use crate::xyz::{DotProductXyz, XYZ};
+
+/// Returns the temperature of the movable objects from their kinetic energy, by equipartition:
+/// each of the `dimensions` directions an object can move in holds half of the temperature on
+/// average, so T = sum(m v^2) / (dimensions * count). Returns 0 without any movable objects.
+///
+/// The arena is flat, so pass 2 unless objects move in Z too. Velocities beyond the first
+/// `dimensions` directions are ignored here, and left alone by the thermostats and
+/// `BrownianForce`, which take the same `dimensions`.
+pub fn kinetic_temperature(objects: &[Object], dimensions: usize) -> f32 {
+    let mut twice_kinetic_energy = 0.0;
+    let mut count = 0;
+    for p in objects.iter().filter(|p| p.inverse_mass() != 0.0) {
+        let velocity = in_dimensions(p.velocity, dimensions);
+        twice_kinetic_energy += p.mass * velocity.dot(velocity);
+        count += 1;
+    }
+    if count == 0 || dimensions == 0 {
+        return 0.0;
+    }
+    return twice_kinetic_energy / (dimensions * count) as f32;
+}
+
+/// Returns `v` with the components beyond the first `dimensions` zeroed
+fn in_dimensions(v: XYZ, dimensions: usize) -> XYZ {
+    return XYZ {
+        x: if dimensions > 0 { v.x } else { 0.0 },
+        y: if dimensions > 1 { v.y } else { 0.0 },
+        z: if dimensions > 2 { v.z } else { 0.0 },
+    };
+}
+
+/// Adjusts the objects' velocities towards a target temperature. A world's thermostat runs every
+/// tick, straight after integration, and any randomness it needs is drawn from the world's `rng`.
+pub trait Thermostat {
+    fn apply(&mut self, objects: &mut [Object], time_elapsed_seconds: f32, rng: &mut Rng);
+}
+
+/// Scales every velocity so that the temperature is exactly the target after each tick. This is the
+/// quickest way to reach a temperature, but it suppresses the natural fluctuations in the kinetic
+/// energy, so the objects don't sample a true canonical ensemble.
+pub struct VelocityRescaling {
+    pub target_temperature: f32,
+    // See `kinetic_temperature`
+    pub dimensions: usize,
+}
+
+impl Thermostat for VelocityRescaling {
+    fn apply(&mut self, objects: &mut [Object], _time_elapsed_seconds: f32, _rng: &mut Rng) {
+        let temperature = kinetic_temperature(objects, self.dimensions);
+        if temperature == 0.0 {
+            // There's no motion to scale
+            return;
+        }
+        scale_velocities(objects, (self.target_temperature / temperature).sqrt());
+    }
+}
+
+/// Scales every velocity so that the temperature relaxes exponentially towards the target, with the
+/// given time constant, as if the objects were weakly coupled to a heat bath. This disturbs the
+/// dynamics less than `VelocityRescaling`, but like it, doesn't sample a true canonical ensemble.
+pub struct Berendsen {
+    pub target_temperature: f32,
+    // How long the temperature takes to relax most of the way to the target, in seconds. A time
+    // constant as short as the tick is the same as velocity rescaling.
+    pub time_constant: f32,
+    // See `kinetic_temperature`
+    pub dimensions: usize,
+}
+
+impl Thermostat for Berendsen {
+    fn apply(&mut self, objects: &mut [Object], time_elapsed_seconds: f32, _rng: &mut Rng) {
+        let temperature = kinetic_temperature(objects, self.dimensions);
+        if temperature == 0.0 {
+            return;
+        }
+        let coupling = (time_elapsed_seconds / self.time_constant).min(1.0);
+        let scale_squared = 1.0 + coupling * (self.target_temperature / temperature - 1.0);
+        scale_velocities(objects, scale_squared.max(0.0).sqrt());
+    }
+}
+
+/// Multiplies the velocity of every movable object by `scale`, in every direction
+fn scale_velocities(objects: &mut [Object], scale: f32) {
+    for p in objects.iter_mut().filter(|p| p.inverse_mass() != 0.0) {
+        p.velocity = p.velocity * scale;
+    }
+}
+
+/// Damps every object's velocity with friction against an implicit solvent, and kicks it randomly,
+/// as the solvent's molecules would, so that the two balance at the target temperature. Unlike the
+/// rescaling thermostats, this samples the canonical ensemble, with its natural fluctuations.
+///
+/// Each tick applies the exact solution for the velocity over that tick, so it's stable for any
+/// damping and tick length.
+pub struct Langevin {
+    pub target_temperature: f32,
+    // The friction coefficient gamma per unit mass, in 1 / s. Larger values hold the temperature more
+    // tightly, but slow the objects' motion more.
+    pub damping: f32,
+    // See `kinetic_temperature`
+    pub dimensions: usize,
+}
+
+impl Langevin {
+    pub fn new(target_temperature: f32, damping: f32, dimensions: usize) -> Langevin {
+        return Langevin {
+            target_temperature,
+            damping,
+            dimensions,
+        };
+    }
+}
+
+impl Thermostat for Langevin {
+    fn apply(&mut self, objects: &mut [Object], time_elapsed_seconds: f32, rng: &mut Rng) {
+        let decay = (-self.damping * time_elapsed_seconds).exp();
+        for p in objects.iter_mut().filter(|p| p.inverse_mass() != 0.0) {
+            // The velocity forgets its past at the rate gamma, while the kicks restore a spread of
+            // sqrt(T / m) in each direction
+            let spread = ((1.0 - decay * decay) * self.target_temperature / p.mass).sqrt();
+            let kick = XYZ {
+                x: spread * rng.normal(),
+                y: spread * rng.normal(),
+                z: spread * rng.normal(),
+            };
+            let damped = in_dimensions(p.velocity, self.dimensions) * decay;
+            let untouched = p.velocity - in_dimensions(p.velocity, self.dimensions);
+            p.velocity = untouched + damped + in_dimensions(kick, self.dimensions);
+        }
+    }
+}
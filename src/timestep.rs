@@ -0,0 +1,64 @@
+//! A fixed timestep driver, which decouples the physics step size from the display frame rate
+use crate::config::SimConfig;
+use crate::world::World;
+
+// Tolerance for floating point imprecision when deciding whether a whole step has accumulated.
+// Without it, e.g. ten frames of 0.1s may only run nine steps of 0.1s.
+const ACCUMULATOR_TOLERANCE_SECONDS: f64 = 1e-9;
+
+/// Accumulates frame time and runs the world in whole, equally sized physics steps. This makes
+/// simulation results independent of the frame rate: the same total frame time always runs the
+/// same steps, regardless of how it's split into frames.
+///
+/// See https://gafferongames.com/post/fix_your_timestep/
+pub struct FixedTimestep {
+    step_seconds: f64,
+    max_substeps: u32,
+    // Frame time which hasn't yet been simulated, because it's less than a whole step
+    accumulator: f64,
+}
+
+impl FixedTimestep {
+    /// Create a driver which steps at `config.fixed_timestep_hz`, running at most
+    /// `config.max_substeps_per_frame` steps per frame.
+    pub fn new(config: &SimConfig) -> FixedTimestep {
+        return FixedTimestep {
+            step_seconds: 1.0 / config.fixed_timestep_hz as f64,
+            max_substeps: config.max_substeps_per_frame,
+            accumulator: 0.0,
+        };
+    }
+
+    /// The duration of a single physics step in seconds
+    pub fn step_seconds(&self) -> f64 {
+        return self.step_seconds;
+    }
+
+    /// Run as many whole physics steps as the accumulated frame time allows, and return the
+    /// interpolation alpha in [0, 1) for rendering. The alpha is how far the leftover, not yet
+    /// simulated time is into the next step, so that rendering the world at
+    /// `World::interpolated_transform(idx, alpha)` keeps motion smooth at any frame rate.
+    ///
+    /// If the frame took longer than `max_substeps` steps, the excess time is dropped rather than
+    /// simulated, which slows the simulation down instead of letting slow frames snowball
+    /// (the "spiral of death").
+    pub fn advance(&mut self, world: &mut World, frame_time_seconds: f64) -> f32 {
+        // Negative frame times make no sense, and would wind back the accumulator
+        self.accumulator += f64::max(frame_time_seconds, 0.0);
+
+        let mut substeps = 0;
+        while self.accumulator + ACCUMULATOR_TOLERANCE_SECONDS >= self.step_seconds {
+            if substeps == self.max_substeps {
+                // Keep any partial step, so the alpha remains meaningful
+                self.accumulator %= self.step_seconds;
+                break;
+            }
+            world.step(self.step_seconds);
+            self.accumulator -= self.step_seconds;
+            substeps += 1;
+        }
+        self.accumulator = f64::max(self.accumulator, 0.0);
+
+        return (self.accumulator / self.step_seconds) as f32;
+    }
+}
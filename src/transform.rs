@@ -1,10 +1,28 @@
-use macroquad::math::Quat;
+pub use glam::{Mat3, Quat};
 
 use crate::xyz::XYZ;
 
+#[derive(Debug, Copy, Clone, PartialEq)]
 pub struct Transform {
     // Describes an object's location
     pub position: XYZ,
     pub scale: XYZ,
     pub rotation: Quat,
 }
+
+impl Transform {
+    /// The transform which leaves everything where it is
+    pub const IDENTITY: Transform = Transform {
+        position: XYZ {
+            x: 0.0,
+            y: 0.0,
+            z: 0.0,
+        },
+        scale: XYZ {
+            x: 1.0,
+            y: 1.0,
+            z: 1.0,
+        },
+        rotation: Quat::IDENTITY,
+    };
+}
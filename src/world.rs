@@ -0,0 +1,210 @@
+//! The simulated world, independent of any rendering or windowing
+use crate::broadphase::{BoundingBox, Broadphase};
+use crate::config::{ConfigError, SimConfig};
+use crate::contacts::resolve_contacts;
+use crate::electromagnetism::{magnetic_fields_at, MagneticField};
+use crate::forces::{default_force_generators, ForceGenerator};
+use crate::integrators::integrate_rotation;
+use crate::objects::Object;
+use crate::random::Rng;
+use crate::thermostat::Thermostat;
+use crate::transform::Transform;
+use crate::xyz::XYZ;
+use crate::{arena_extents, resolve_arena_contacts, OutOfBoundsError};
+
+/// Owns everything needed to run a simulation: the objects, the config they're simulated with, and
+/// the simulated time. This lets the physics be driven from anywhere, e.g. a batch job or a test,
+/// without a window or a render loop.
+pub struct World {
+    pub objects: Vec<Object>,
+    // Exert forces on the objects each step. A new world has the config's gravity.
+    pub force_generators: Vec<Box<dyn ForceGenerator>>,
+    // Deflect charged objects each step. A new world has none.
+    pub magnetic_fields: Vec<Box<dyn MagneticField>>,
+    // Holds the objects at a temperature, if set. A new world has none.
+    pub thermostat: Option<Box<dyn Thermostat>>,
+    // The source of randomness for random forces and thermostats, seeded by the config
+    rng: Rng,
+    // The config's broadphase, kept between steps so that it can reuse its memory
+    broadphase: Box<dyn Broadphase>,
+    config: SimConfig,
+    // Total simulated time in seconds
+    time: f64,
+    // How many times an object has been reset to the arena's center, since its bounce off the
+    // arena's edges couldn't be calculated
+    arena_resets: u64,
+    // Each object's transform as of the start of the latest step, used for render interpolation
+    previous_transforms: Vec<Transform>,
+}
+
+impl World {
+    /// Create an empty world. Returns an error if the config contains nonsensical values.
+    pub fn new(config: SimConfig) -> Result<World, ConfigError> {
+        config.validate()?;
+        return Ok(World {
+            objects: Vec::new(),
+            force_generators: default_force_generators(&config),
+            magnetic_fields: Vec::new(),
+            thermostat: None,
+            rng: Rng::new(config.seed),
+            broadphase: config.broadphase.broadphase(),
+            config,
+            time: 0.0,
+            arena_resets: 0,
+            previous_transforms: Vec::new(),
+        });
+    }
+
+    pub fn config(&self) -> &SimConfig {
+        return &self.config;
+    }
+
+    /// The world's random number generator, which its random forces and thermostat draw from, e.g.
+    /// to draw random initial conditions from the config's seed too
+    pub fn rng(&mut self) -> &mut Rng {
+        return &mut self.rng;
+    }
+
+    /// The total simulated time in seconds
+    pub fn time(&self) -> f64 {
+        return self.time;
+    }
+
+    /// How many times, in total, an object has been reset to the arena's center, with no velocity,
+    /// since its bounce off the arena's edges couldn't be calculated. The world doesn't report these
+    /// itself, so callers which care should check this after stepping.
+    pub fn arena_resets(&self) -> u64 {
+        return self.arena_resets;
+    }
+
+    /// Add an object to the world. For accurate results, the object should spawn fully within
+    /// simulation bounds, so we return an error if it doesn't. Objects are measured by their
+    /// collider's bounds in the world, or for infinite or absent colliders, as particles of the
+    /// configured radius, see `arena_extents`.
+    pub fn add_object(&mut self, object: Object) -> Result<(), OutOfBoundsError> {
+        let position = object.transform.position;
+        let extents = arena_extents(&object, &self.config);
+        let bounds = BoundingBox {
+            min: position + extents.min,
+            max: position + extents.max,
+        };
+        if bounds.min.x < 0.0
+            || bounds.min.y < 0.0
+            || bounds.max.x > self.config.screen_width
+            || bounds.max.y > self.config.screen_height
+        {
+            return Err(OutOfBoundsError {
+                object_location_x: position.x,
+                object_location_y: position.y,
+            });
+        }
+        self.objects.push(object);
+        return Ok(());
+    }
+
+    /// Register a force generator, which will push the objects around from the next step onwards
+    pub fn add_force_generator(&mut self, generator: Box<dyn ForceGenerator>) {
+        self.force_generators.push(generator);
+    }
+
+    /// Register a magnetic field, which will deflect charged objects from the next step onwards. Use
+    /// the `Boris` integrator to keep their gyration stable over long runs.
+    pub fn add_magnetic_field(&mut self, field: Box<dyn MagneticField>) {
+        self.magnetic_fields.push(field);
+    }
+
+    /// Set the thermostat, which will hold the objects at its temperature from the next step onwards,
+    /// replacing any previous thermostat
+    pub fn set_thermostat(&mut self, thermostat: Box<dyn Thermostat>) {
+        self.thermostat = Some(thermostat);
+    }
+
+    /// Advance the simulation by `time_elapsed_seconds`, under the force generators' forces as well
+    /// as the force accumulated on each object by the caller. Charged objects are also deflected by
+    /// the magnetic fields. If there's a thermostat, it adjusts the velocities straight after they're
+    /// integrated. This is the only way to advance the simulation, so that nothing registered on the
+    /// world, e.g. its gravity, can be left out.
+    pub fn step(&mut self, time_elapsed_seconds: f64) {
+        self.previous_transforms = self.objects.iter().map(|o| o.transform).collect();
+        let dt = time_elapsed_seconds as f32;
+
+        // Deriving the inertia tensor is slow for some colliders, so it's only done once per object
+        for object in self.objects.iter_mut().filter(|o| o.inertia.is_none()) {
+            object.inertia = Some(object.local_inertia_tensor());
+        }
+
+        for generator in self.force_generators.iter_mut() {
+            generator.start_tick(&self.objects, dt, &mut self.rng);
+        }
+        // The force accumulated on each object by the caller stays constant over the step, whereas
+        // the generators' forces are recalculated each time the integrator asks for accelerations.
+        let force_generators = &mut self.force_generators;
+        let mut generated = vec![XYZ::default(); self.objects.len()];
+        let mut accelerations = |objects: &[Object]| -> Vec<XYZ> {
+            generated.fill(XYZ::default());
+            for generator in force_generators.iter_mut() {
+                generator.accumulate(objects, &mut generated);
+            }
+            return objects
+                .iter()
+                .zip(generated.iter())
+                .map(|(p, force)| (p.force + *force) * p.inverse_mass())
+                .collect();
+        };
+        let integrator = self.config.integrator.integrator();
+        let magnetic_fields = &self.magnetic_fields;
+        if magnetic_fields.is_empty() {
+            integrator.integrate(&mut self.objects, dt, &mut accelerations);
+        } else {
+            integrator.integrate_in_magnetic_field(
+                &mut self.objects,
+                dt,
+                &mut accelerations,
+                &mut |objects: &[Object]| magnetic_fields_at(magnetic_fields, objects),
+            );
+        }
+        integrate_rotation(&mut self.objects, dt);
+        if let Some(thermostat) = self.thermostat.as_mut() {
+            thermostat.apply(&mut self.objects, dt, &mut self.rng);
+        }
+        // Keep the total force for the rest of the step, as of the integrator's latest evaluation
+        for (p, force) in self.objects.iter_mut().zip(generated) {
+            p.force += force;
+        }
+
+        resolve_contacts(
+            &mut self.objects,
+            &self.config.material_combine,
+            self.broadphase.as_mut(),
+        );
+        self.arena_resets +=
+            resolve_arena_contacts(&mut self.objects, &self.config, time_elapsed_seconds);
+        self.time += time_elapsed_seconds;
+    }
+
+    /// Blend an object's transform between the previous and the current step, where an alpha of 0
+    /// is the previous step and 1 is the current step. Returns None if there's no such object.
+    ///
+    /// Objects added since the latest step have no previous transform, so we use their current one.
+    pub fn interpolated_transform(&self, idx: usize, alpha: f32) -> Option<Transform> {
+        let current = self.objects.get(idx)?.transform;
+        let previous = match self.previous_transforms.get(idx) {
+            Some(previous) => *previous,
+            None => return Some(current),
+        };
+
+        let alpha = alpha.clamp(0.0, 1.0);
+        let lerp = |a: XYZ, b: XYZ| a + (b - a) * alpha;
+        // Slerp requires unit quaternions, so skip it for e.g. an all-zero rotation
+        let rotation = if previous.rotation.is_normalized() && current.rotation.is_normalized() {
+            previous.rotation.slerp(current.rotation, alpha)
+        } else {
+            current.rotation
+        };
+        return Some(Transform {
+            position: lerp(previous.position, current.position),
+            scale: lerp(previous.scale, current.scale),
+            rotation,
+        });
+    }
+}
@@ -1,13 +1,29 @@
 use std::fmt;
 use std::ops;
 
-#[derive(Debug, Copy, Clone, PartialEq)]
+#[derive(Debug, Default, Copy, Clone, PartialEq)]
 pub struct XYZ {
     pub x: f32,
     pub y: f32,
     pub z: f32,
 }
 
+impl From<glam::Vec3> for XYZ {
+    fn from(v: glam::Vec3) -> XYZ {
+        return XYZ {
+            x: v.x,
+            y: v.y,
+            z: v.z,
+        };
+    }
+}
+
+impl From<XYZ> for glam::Vec3 {
+    fn from(v: XYZ) -> glam::Vec3 {
+        return glam::Vec3::new(v.x, v.y, v.z);
+    }
+}
+
 impl fmt::Display for XYZ {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(f, "XY(X={},Y={},Z={})", self.x, self.y, self.z)
@@ -27,10 +43,23 @@ impl ops::Add<XYZ> for &XYZ {
     }
 }
 
+impl ops::Add<XYZ> for XYZ {
+    type Output = XYZ;
+
+    fn add(self, rhs: XYZ) -> XYZ {
+        return XYZ {
+            x: self.x + rhs.x,
+            y: self.y + rhs.y,
+            z: self.z + rhs.z,
+        };
+    }
+}
+
 impl ops::AddAssign<XYZ> for XYZ {
     fn add_assign(&mut self, rhs: XYZ) {
         self.x += rhs.x;
         self.y += rhs.y;
+        self.z += rhs.z;
     }
 }
 
@@ -50,6 +79,7 @@ impl ops::SubAssign<XYZ> for XYZ {
     fn sub_assign(&mut self, rhs: XYZ) {
         self.x -= rhs.x;
         self.y -= rhs.y;
+        self.z -= rhs.z;
     }
 }
 
@@ -129,6 +159,18 @@ impl ops::Mul<f32> for &XYZ {
     }
 }
 
+impl ops::Neg for XYZ {
+    type Output = XYZ;
+
+    fn neg(self) -> XYZ {
+        return XYZ {
+            x: -self.x,
+            y: -self.y,
+            z: -self.z,
+        };
+    }
+}
+
 pub trait NormalizeXyz {
     fn normalize(&self) -> XYZ;
 }
@@ -174,3 +216,29 @@ impl MagnitudeXyz for XYZ {
         return res;
     }
 }
+
+pub trait DotProductXyz {
+    fn dot(&self, rhs: XYZ) -> f32;
+}
+
+impl DotProductXyz for XYZ {
+    fn dot(&self, rhs: XYZ) -> f32 {
+        return self.x * rhs.x + self.y * rhs.y + self.z * rhs.z;
+    }
+}
+
+pub trait CrossProductXyz {
+    fn cross(&self, rhs: XYZ) -> XYZ;
+}
+
+/// Returns the cross product self x rhs, which is perpendicular to both, with a magnitude equal to
+/// the area of the parallelogram they span
+impl CrossProductXyz for XYZ {
+    fn cross(&self, rhs: XYZ) -> XYZ {
+        return XYZ {
+            x: self.y * rhs.z - self.z * rhs.y,
+            y: self.z * rhs.x - self.x * rhs.z,
+            z: self.x * rhs.y - self.y * rhs.x,
+        };
+    }
+}
@@ -1,6 +1,11 @@
-use macroquad::math::Quat;
+// We prefer explicit returns throughout this crate
+#![allow(clippy::needless_return)]
+
 use objects::*;
 use particle_sim::colliders::ColliderType;
+use particle_sim::config::SimConfig;
+use particle_sim::material::Material;
+use particle_sim::transform::Quat;
 use particle_sim::transform::Transform;
 use particle_sim::xyz::XYZ;
 use particle_sim::*;
@@ -8,11 +13,12 @@ use particle_sim::*;
 /// Return a sphere with relatively standard arguments. The idea is to use this to reduce
 /// boilerplate, then overwrite specified parameters for a given test
 fn return_centered_sphere() -> Object {
+    let config = SimConfig::default();
     let sphere = Object {
         transform: Transform {
             position: XYZ {
-                x: 0.5 * SCREEN_WIDTH,
-                y: 0.5 * SCREEN_HEIGHT,
+                x: 0.5 * config.screen_width,
+                y: 0.5 * config.screen_height,
                 z: 0.0, // TODO: 0.5 * SCREEN_DEPTH,
             },
             scale: XYZ {
@@ -37,7 +43,21 @@ fn return_centered_sphere() -> Object {
             y: 0.0,
             z: 0.0,
         },
+        angular_velocity: XYZ {
+            x: 0.0,
+            y: 0.0,
+            z: 0.0,
+        },
+        torque: XYZ {
+            x: 0.0,
+            y: 0.0,
+            z: 0.0,
+        },
         mass: 1.0,
+        inertia: None,
+        material: Material::default(),
+        drag_coefficient: DEFAULT_DRAG_COEFFICIENT,
+        charge: 0.0,
         collider: ColliderType::SPHERE {
             center: XYZ {
                 x: 0.0,
@@ -52,13 +72,39 @@ fn return_centered_sphere() -> Object {
 
 #[cfg(test)]
 mod tests {
-    use colliders;
-    use macroquad::math::Quat;
     use objects::*;
-    use particle_sim::colliders::ColliderType;
-    use particle_sim::transform::Transform;
+    use particle_sim::broadphase::{
+        BoundingBox, Broadphase, BroadphaseType, BruteForce, SweepAndPrune, UniformGrid,
+    };
+    use particle_sim::brownian::{
+        einstein_diffusion_coefficient, BrownianForce, MeanSquaredDisplacement,
+    };
+    use particle_sim::colliders::{
+        plane_plane_intersection, ChildCollider, ColliderType, PlaneIntersection,
+    };
+    use particle_sim::config::{ConfigError, SimConfig};
+    use particle_sim::contacts::{resolve_contact, resolve_contacts};
+    use particle_sim::electromagnetism::{
+        magnetic_fields_at, CoulombForce, MagneticField, UniformElectricField, UniformMagneticField,
+    };
+    use particle_sim::forces::{
+        AerodynamicDrag, ForceGenerator, Gravity, LinearDrag, PointAttractor, QuadraticDrag, Spring,
+    };
+    use particle_sim::gjk::gjk_epa_collision_points;
+    use particle_sim::integrators::{integrate_rotation, IntegratorType};
+    use particle_sim::mass::MassProperties;
+    use particle_sim::material::{CombineMode, CombineRules, Material};
+    use particle_sim::molecular::{LennardJones, NeighborList, ReducedUnits};
+    use particle_sim::nbody::{NBodyGravity, NBodyMethod};
+    use particle_sim::random::Rng;
+    use particle_sim::thermostat::{
+        kinetic_temperature, Berendsen, Langevin, Thermostat, VelocityRescaling,
+    };
+    use particle_sim::timestep::FixedTimestep;
+    use particle_sim::transform::{Mat3, Quat, Transform};
+    use particle_sim::world::World;
     use particle_sim::xyz::NormalizeXyz;
-    use particle_sim::xyz::{MagnitudeXyz, XYZ};
+    use particle_sim::xyz::{CrossProductXyz, DotProductXyz, MagnitudeXyz, XYZ};
     use particle_sim::{colliders::TestCollision, *};
 
     use crate::return_centered_sphere;
@@ -67,6 +113,15 @@ mod tests {
     // this value for all 3 dimensions.
     const BALANCED_NORMAL: f32 = 0.57735026;
 
+    /// Step `objects` once in a new world with `config`, i.e. under the config's gravity alone, even
+    /// if they're out of bounds
+    fn step_objects(objects: &mut Vec<Object>, config: &SimConfig, time_elapsed_seconds: f64) {
+        let mut world = World::new(config.clone()).unwrap();
+        world.objects = std::mem::take(objects);
+        world.step(time_elapsed_seconds);
+        *objects = world.objects;
+    }
+
     #[test]
     fn test_xyz_magnitude_on_nonzero_input() {
         let loc_1 = XYZ {
@@ -151,9 +206,50 @@ mod tests {
         assert_eq!(result.z, BALANCED_NORMAL);
     }
 
+    #[test]
+    fn test_xyz_cross_product() {
+        let x_axis = XYZ {
+            x: 1.0,
+            y: 0.0,
+            z: 0.0,
+        };
+        let y_axis = XYZ {
+            x: 0.0,
+            y: 1.0,
+            z: 0.0,
+        };
+        let z_axis = XYZ {
+            x: 0.0,
+            y: 0.0,
+            z: 1.0,
+        };
+        assert_eq!(x_axis.cross(y_axis), z_axis);
+        assert_eq!(y_axis.cross(z_axis), x_axis);
+        assert_eq!(y_axis.cross(x_axis), -z_axis);
+
+        // The result is perpendicular to both inputs, and as long as the area of their parallelogram
+        let u = XYZ {
+            x: 1.0,
+            y: 2.0,
+            z: 3.0,
+        };
+        let v = XYZ {
+            x: -2.0,
+            y: 0.5,
+            z: 4.0,
+        };
+        let result = u.cross(v);
+        assert_eq!(result.dot(u), 0.0);
+        assert_eq!(result.dot(v), 0.0);
+        let cos_angle = u.dot(v) / (u.magnitude() * v.magnitude());
+        let area = u.magnitude() * v.magnitude() * (1.0 - cos_angle * cos_angle).sqrt();
+        assert!((result.magnitude() - area).abs() < 0.0001);
+        assert_eq!(u.cross(u), XYZ::default());
+    }
+
     #[test]
     fn test_sphere_sphere_intersection_sphere_zero_radius() {
-        // Test with 0 radius. We expect this to count as a collision
+        // Test with 0 radius and coincident centers. We expect this to count as a collision
         let sphere_1 = colliders::ColliderType::SPHERE {
             center: XYZ {
                 x: 20.0,
@@ -171,20 +267,27 @@ mod tests {
             radius: 0.0,
         };
         let result = sphere_1.test_collision(&sphere_2);
-        // Both spheres should collide at sphere 2's spawn, with the furthest point in each sphere being that spawn
-        // (because sphere_2's radius is 0.0)
-        assert!(result.has_collision == true);
-        assert!(result.a.x == 20.0);
-        assert!(result.a.y == 20.0);
-        assert!(result.a.z == 20.0);
-        assert!(result.b.x == 20.0);
-        assert!(result.b.y == 20.0);
-        assert!(result.b.z == 20.0);
-        // if we have a radius of 0, we can't have a penetration depth > 0.0
-        assert_eq!(result.depth, 0.0);
+        assert!(result.has_collision);
+        // There's no line between coincident centers, so we expect the default normal that we use
+        // for zero vectors
         assert_eq!(result.normal.x, BALANCED_NORMAL);
         assert_eq!(result.normal.y, BALANCED_NORMAL);
         assert_eq!(result.normal.z, BALANCED_NORMAL);
+        // Sphere 2 is a point at sphere 1's center, so sphere 1 would have to move its full radius
+        // to separate from it
+        assert_eq!(result.depth, 5.0);
+        // Since sphere_2's radius is 0.0, its furthest point into sphere 1 is its center
+        assert_eq!(result.b.x, 20.0);
+        assert_eq!(result.b.y, 20.0);
+        assert_eq!(result.b.z, 20.0);
+        assert!((result.a.x - (20.0 - 5.0 * BALANCED_NORMAL)).abs() < 0.0001);
+        assert!((result.a.y - (20.0 - 5.0 * BALANCED_NORMAL)).abs() < 0.0001);
+        assert!((result.a.z - (20.0 - 5.0 * BALANCED_NORMAL)).abs() < 0.0001);
+
+        // The result should be deterministic
+        let result_2 = sphere_1.test_collision(&sphere_2);
+        assert_eq!(result.normal, result_2.normal);
+        assert_eq!(result.a, result_2.a);
     }
 
     #[test]
@@ -207,15 +310,15 @@ mod tests {
             radius: 4.0,
         };
         let result = sphere_3.test_collision(&sphere_4);
-        assert!(result.has_collision == false);
+        assert!(!result.has_collision);
         // There may be more values in our result object, but for non-colliding spheres,
         // we don't care about them
     }
 
     #[test]
     fn test_sphere_sphere_intersection_single_axis() {
-        // Assert that a collision in only one axis is still registered as a collision,
-        // and the A and B values are as expected
+        // Assert that spheres offset along a single axis collide along that axis, and the A and B
+        // values are as expected
         let sphere_5 = colliders::ColliderType::SPHERE {
             center: XYZ {
                 x: 20.0,
@@ -225,6 +328,47 @@ mod tests {
             radius: 1.0,
         };
         let sphere_6 = colliders::ColliderType::SPHERE {
+            center: XYZ {
+                x: 20.0,
+                y: 20.0,
+                z: 18.0,
+            },
+            radius: 2.0,
+        };
+        let result = sphere_5.test_collision(&sphere_6);
+        assert!(result.has_collision);
+        // Sphere 5's lowest point in Z reaches into sphere 6
+        assert_eq!(
+            result.a,
+            XYZ {
+                x: 20.0,
+                y: 20.0,
+                z: 19.0
+            }
+        );
+        // Sphere 6's highest point in Z reaches into sphere 5, past its center
+        assert_eq!(
+            result.b,
+            XYZ {
+                x: 20.0,
+                y: 20.0,
+                z: 20.0
+            }
+        );
+        // Sphere 5 would need to move up in Z to separate
+        assert_eq!(
+            result.normal,
+            XYZ {
+                x: 0.0,
+                y: 0.0,
+                z: 1.0
+            }
+        );
+        assert_eq!(result.depth, 1.0);
+
+        // Overlapping in every individual axis isn't enough to collide, if the spheres are too far
+        // apart diagonally
+        let sphere_7 = colliders::ColliderType::SPHERE {
             center: XYZ {
                 x: 17.0,
                 y: 17.0,
@@ -232,21 +376,15 @@ mod tests {
             },
             radius: 2.0,
         };
-        let result = sphere_5.test_collision(&sphere_6);
-        assert!(result.has_collision == true);
-        assert!(result.a.z == 19.0);
-        // The center is the innermost point that a sphere (A) can intersect into another sphere (B).
-        // Therefore, if A intersects past that point, we still expect the point furthest into B to be
-        // B's center
-        assert!(result.b.z == 20.0);
+        let result = sphere_5.test_collision(&sphere_7);
+        assert!(!result.has_collision);
     }
 
     #[test]
     fn test_sphere_sphere_intersection_mini_sphere_fully_contained() {
         // Assert that, when a sphere fully contains another sphere:
         // 1) it's registered as a collision
-        // 2) the furthest point of the larger sphere into the smaller sphere is the smaller
-        // sphere's center
+        // 2) the collision points and depth are still measured along the line between the centers
         let sphere_7 = colliders::ColliderType::SPHERE {
             center: XYZ {
                 x: 20.0,
@@ -264,13 +402,152 @@ mod tests {
             radius: 2.0,
         };
         let result = sphere_7.test_collision(&sphere_8);
-        assert!(result.has_collision == true);
-        // the sphere A, containing the other sphere B, should have B's center as A's furthermost
-        // incursion into B's space
-        assert!(result.a.z == 21.0);
-        // the smaller sphere should choose the point closest to the center of the other sphere, that
-        // it can still reach
-        assert!(result.b.z == 20.0);
+        assert!(result.has_collision);
+        // Sphere 8 lies up and to the right of sphere 7's center, so sphere 7 must move down and left
+        assert_eq!(result.normal.x, -BALANCED_NORMAL);
+        assert_eq!(result.normal.y, -BALANCED_NORMAL);
+        assert_eq!(result.normal.z, -BALANCED_NORMAL);
+        // The radius sum, minus the distance between the centers
+        assert!((result.depth - (12.0 - f32::sqrt(3.0))).abs() < 0.0001);
+        // the larger sphere's surface, on the side of the smaller sphere
+        assert!((result.a.z - (20.0 + 10.0 * BALANCED_NORMAL)).abs() < 0.0001);
+        // the smaller sphere's surface, on the side of the larger sphere's center
+        assert!((result.b.z - (21.0 - 2.0 * BALANCED_NORMAL)).abs() < 0.0001);
+    }
+
+    /// A small deterministic pseudo-random number generator, so that the property tests below can
+    /// cover many cases while remaining reproducible
+    fn next_pseudo_random(state: &mut u64) -> f32 {
+        *state = state
+            .wrapping_mul(6364136223846793005)
+            .wrapping_add(1442695040888963407);
+        // Scale the top bits to [-1, 1)
+        return ((*state >> 40) as f32 / (1u64 << 24) as f32) * 2.0 - 1.0;
+    }
+
+    fn return_random_sphere_collider(state: &mut u64) -> ColliderType {
+        return ColliderType::SPHERE {
+            center: XYZ {
+                x: 10.0 * next_pseudo_random(state),
+                y: 10.0 * next_pseudo_random(state),
+                z: 10.0 * next_pseudo_random(state),
+            },
+            radius: 5.0 * (next_pseudo_random(state) + 1.0),
+        };
+    }
+
+    #[test]
+    fn test_sphere_sphere_intersection_property_symmetry() {
+        // Testing B against A should describe the same collision as A against B, from B's perspective
+        let mut state = 42;
+        for _i in 0..1000 {
+            let sphere_1 = return_random_sphere_collider(&mut state);
+            let sphere_2 = return_random_sphere_collider(&mut state);
+            let result_1 = sphere_1.test_collision(&sphere_2);
+            let result_2 = sphere_2.test_collision(&sphere_1);
+            assert_eq!(result_1.has_collision, result_2.has_collision);
+            assert!((result_1.depth - result_2.depth).abs() < 0.0001);
+            assert!((result_1.normal + result_2.normal).magnitude() < 0.0001);
+            assert!((result_1.a - result_2.b).magnitude() < 0.0001);
+            assert!((result_1.b - result_2.a).magnitude() < 0.0001);
+        }
+
+        // Coincident centers have no line between them, but the results must still mirror each other
+        for _i in 0..100 {
+            let center = XYZ {
+                x: 10.0 * next_pseudo_random(&mut state),
+                y: 10.0 * next_pseudo_random(&mut state),
+                z: 10.0 * next_pseudo_random(&mut state),
+            };
+            let radius = 5.0 * (next_pseudo_random(&mut state) + 1.0);
+            let sphere_1 = ColliderType::SPHERE { center, radius };
+            let sphere_2 = ColliderType::SPHERE {
+                center,
+                radius: radius + 1.0 + next_pseudo_random(&mut state).abs(),
+            };
+            let result_1 = sphere_1.test_collision(&sphere_2);
+            let result_2 = sphere_2.test_collision(&sphere_1);
+            assert!(result_1.has_collision && result_2.has_collision);
+            assert!((result_1.depth - result_2.depth).abs() < 0.0001);
+            assert!((result_1.normal + result_2.normal).magnitude() < 0.0001);
+            assert!((result_1.a - result_2.b).magnitude() < 0.0001);
+            assert!((result_1.b - result_2.a).magnitude() < 0.0001);
+        }
+    }
+
+    #[test]
+    fn test_sphere_sphere_intersection_property_translation_invariance() {
+        // Moving both spheres by the same offset should move the collision points by that offset,
+        // and leave everything else unchanged
+        let mut state = 7;
+        for _i in 0..1000 {
+            let sphere_1 = return_random_sphere_collider(&mut state);
+            let sphere_2 = return_random_sphere_collider(&mut state);
+            let offset = XYZ {
+                x: 100.0 * next_pseudo_random(&mut state),
+                y: 100.0 * next_pseudo_random(&mut state),
+                z: 100.0 * next_pseudo_random(&mut state),
+            };
+            let translate = |sphere: &ColliderType| match sphere {
+                ColliderType::SPHERE { center, radius } => ColliderType::SPHERE {
+                    center: *center + offset,
+                    radius: *radius,
+                },
+                _ => panic!("Expected a sphere"),
+            };
+            let result_1 = sphere_1.test_collision(&sphere_2);
+            let result_2 = translate(&sphere_1).test_collision(&translate(&sphere_2));
+            assert_eq!(result_1.has_collision, result_2.has_collision);
+            assert!((result_1.depth - result_2.depth).abs() < 0.001);
+            assert!((result_1.normal - result_2.normal).magnitude() < 0.001);
+            assert!((result_1.a + offset - result_2.a).magnitude() < 0.001);
+            assert!((result_1.b + offset - result_2.b).magnitude() < 0.001);
+        }
+    }
+
+    #[test]
+    fn test_sphere_sphere_intersection_matches_center_distance() {
+        // Spheres collide if and only if their centers are at most the sum of their radii apart,
+        // including on negative axes
+        let mut state = 1234;
+        for _i in 0..1000 {
+            let sphere_1 = return_random_sphere_collider(&mut state);
+            let sphere_2 = return_random_sphere_collider(&mut state);
+            let (c1, r1, c2, r2) = match (&sphere_1, &sphere_2) {
+                (
+                    ColliderType::SPHERE {
+                        center: c1,
+                        radius: r1,
+                    },
+                    ColliderType::SPHERE {
+                        center: c2,
+                        radius: r2,
+                    },
+                ) => (*c1, *r1, *c2, *r2),
+                _ => panic!("Expected spheres"),
+            };
+            let result = sphere_1.test_collision(&sphere_2);
+            assert_eq!(result.has_collision, (c1 - c2).magnitude() <= r1 + r2);
+        }
+
+        // Far apart in the negative direction on every axis
+        let sphere_1 = colliders::ColliderType::SPHERE {
+            center: XYZ {
+                x: -50.0,
+                y: -50.0,
+                z: -50.0,
+            },
+            radius: 1.0,
+        };
+        let sphere_2 = colliders::ColliderType::SPHERE {
+            center: XYZ {
+                x: 0.0,
+                y: 0.0,
+                z: 0.0,
+            },
+            radius: 1.0,
+        };
+        assert!(!sphere_1.test_collision(&sphere_2).has_collision);
     }
 
     #[test]
@@ -295,7 +572,7 @@ mod tests {
             distance: 0.0,
         };
         let result = sphere_1.test_collision(&plane_1);
-        assert!(result.has_collision == true);
+        assert!(result.has_collision);
         assert_eq!(result.a.x, 0.0);
         assert_eq!(result.b.x, 0.0);
 
@@ -334,7 +611,7 @@ mod tests {
             distance: 0.0,
         };
         let result = sphere_2.test_collision(&plane_2);
-        assert!(result.has_collision == true);
+        assert!(result.has_collision);
         // todo: determine what this (the deepest intrusion into a plane) is supposed to represent, given that a
         // plane is of infinite size.
         // For now, I think we ignore it? Given that it maybe doesn't make sense anyway
@@ -421,363 +698,4172 @@ mod tests {
     }
 
     #[test]
-    fn test_convert_meters_to_pixels() {
-        let result = particle_sim::convert_meters_to_pixels(10.0, 100.0);
-        assert_eq!(result, 1000.0);
-
-        let result = particle_sim::convert_meters_to_pixels(0.001, 0.01);
-        assert!(result - 0.000000000001 <= 0.00001);
+    fn test_default_config_is_valid() {
+        assert_eq!(SimConfig::default().validate(), Ok(()));
     }
 
     #[test]
-    fn test_convert_pixels_to_meters() {
-        let result = particle_sim::convert_pixels_to_meters(10.0, 100.0);
-        assert_eq!(result, 10.0 / 100.0);
+    fn test_config_rejects_nonsensical_values() {
+        // A negative bounce coefficient makes no sense
+        let config = SimConfig {
+            arena_material: Material {
+                restitution: -0.1,
+                ..Material::default()
+            },
+            ..SimConfig::default()
+        };
+        assert_eq!(
+            config.validate(),
+            Err(ConfigError::BounceCoefficientOutOfRange(-0.1))
+        );
 
-        let result = particle_sim::convert_pixels_to_meters(100.0, 0.1);
-        assert_eq!(result, 1000.0);
+        // A bounce coefficient of 1 or more would bounce forever
+        let config = SimConfig {
+            arena_material: Material {
+                restitution: 1.0,
+                ..Material::default()
+            },
+            ..SimConfig::default()
+        };
+        assert!(config.validate().is_err());
+
+        // We can't convert between pixels and meters with zero pixels per meter
+        let config = SimConfig {
+            pixels_per_meter: 0.0,
+            ..SimConfig::default()
+        };
+        assert_eq!(
+            config.validate(),
+            Err(ConfigError::NonPositivePixelsPerMeter(0.0))
+        );
+
+        let config = SimConfig {
+            screen_width: -1.0,
+            ..SimConfig::default()
+        };
+        assert!(config.validate().is_err());
+
+        // A particle which can't fit on screen is always out of bounds
+        let config = SimConfig {
+            particle_radius_px: 500.0,
+            ..SimConfig::default()
+        };
+        assert_eq!(
+            config.validate(),
+            Err(ConfigError::ParticleLargerThanScreen(500.0))
+        );
+
+        let config = SimConfig {
+            gravity_ms: f32::NAN,
+            ..SimConfig::default()
+        };
+        assert_eq!(
+            config.validate(),
+            Err(ConfigError::NonFiniteValue("gravity_ms"))
+        );
+
+        let config = SimConfig {
+            fixed_timestep_hz: 0.0,
+            ..SimConfig::default()
+        };
+        assert_eq!(
+            config.validate(),
+            Err(ConfigError::NonPositiveTimestepHz(0.0))
+        );
+
+        // The simulation could never advance
+        let config = SimConfig {
+            max_substeps_per_frame: 0,
+            ..SimConfig::default()
+        };
+        assert_eq!(config.validate(), Err(ConfigError::ZeroMaxSubsteps));
     }
 
     #[test]
-    fn test_calculate_particle_acceleration() {
-        let result = particle_sim::calculate_particle_acceleration(2.0, 1.0, 1.0);
-        assert_eq!(result, 1.0);
+    fn test_config_rejects_negative_friction() {
+        // Negative coefficients would speed objects up against the arena's edges
+        let with_arena_material = |arena_material: Material| SimConfig {
+            arena_material,
+            ..SimConfig::default()
+        };
+        let config = with_arena_material(Material {
+            static_friction: -0.1,
+            ..Material::default()
+        });
+        assert_eq!(
+            config.validate(),
+            Err(ConfigError::NegativeFrictionCoefficient(
+                "arena_material.static_friction",
+                -0.1
+            ))
+        );
+        let config = with_arena_material(Material {
+            dynamic_friction: -0.1,
+            ..Material::default()
+        });
+        assert_eq!(
+            config.validate(),
+            Err(ConfigError::NegativeFrictionCoefficient(
+                "arena_material.dynamic_friction",
+                -0.1
+            ))
+        );
+        let config = with_arena_material(Material {
+            rolling_resistance: -0.1,
+            ..Material::default()
+        });
+        assert_eq!(
+            config.validate(),
+            Err(ConfigError::NegativeFrictionCoefficient(
+                "arena_material.rolling_resistance",
+                -0.1
+            ))
+        );
 
-        let result = particle_sim::calculate_particle_acceleration(1.0, 3.0, 2.0);
-        assert_eq!(result, -1.0);
+        // Frictionless edges are fine
+        let config = with_arena_material(frictionless(0.5));
+        assert_eq!(config.validate(), Ok(()));
     }
 
-    // Maybe worth testing once I have other shapes. Then, I could for example pass a shape, orientation
-    // and function in, which would determine the distance to the ground
-    // #[test]
-    // fn test_particle_touching_ground() {
-    //     let result = particle_sim::particle_touching_ground(1.0,2.0,1.0);
-    //     assert_eq!(result, 1.0);
-    // }
-
     #[test]
-    fn test_calulate_friction() {
-        // This formula may break. The point is that we want any y location at which the particle touches the ground.
-        let touching_ground_y_pos = SCREEN_HEIGHT - 1.0;
+    fn test_config_changes_simulation_results() {
+        // Stronger gravity should pull a particle further over the same timespan
+        let weak_gravity = SimConfig {
+            gravity_ms: 1.0,
+            ..SimConfig::default()
+        };
+        let strong_gravity = SimConfig {
+            gravity_ms: 20.0,
+            ..SimConfig::default()
+        };
+        let mut particles_1: Vec<Object> = vec![return_centered_sphere()];
+        let mut particles_2: Vec<Object> = vec![return_centered_sphere()];
+        step_objects(&mut particles_1, &weak_gravity, 0.1);
+        step_objects(&mut particles_2, &strong_gravity, 0.1);
+        assert!(particles_2[0].velocity.y > particles_1[0].velocity.y);
+        assert!(particles_2[0].transform.position.y > particles_1[0].transform.position.y);
+    }
 
-        // Objects not in contact should have 0 friction
-        let mut sphere_airborne = return_centered_sphere();
-        sphere_airborne.transform.position.x = 5.0;
-        sphere_airborne.transform.position.y = 5.0;
-        sphere_airborne.transform.position.z = 5.0;
-        sphere_airborne.velocity.x = 5.0;
-        let result = particle_sim::calculate_friction_deceleration(&sphere_airborne, 0.2);
-        assert_eq!(result, 0.0);
+    #[test]
+    fn test_plane_sphere_intersection_is_symmetric() {
+        // Testing B against A should describe the same collision as A against B, but from B's perspective
+        let sphere = colliders::ColliderType::SPHERE {
+            center: XYZ {
+                x: 0.0,
+                y: 4.0,
+                z: 0.0,
+            },
+            radius: 10.0,
+        };
+        let plane = colliders::ColliderType::PLANE {
+            normal: XYZ {
+                x: 0.0,
+                y: 1.0,
+                z: 0.0,
+            },
+            distance: 0.0,
+        };
+        let sphere_first = sphere.test_collision(&plane);
+        let plane_first = plane.test_collision(&sphere);
+        assert!(plane_first.has_collision);
+        assert_eq!(plane_first.depth, sphere_first.depth);
+        assert_eq!(plane_first.a, sphere_first.b);
+        assert_eq!(plane_first.b, sphere_first.a);
+        // The plane would have to move down to separate from the sphere
+        assert_eq!(plane_first.normal.y, -1.0);
+    }
 
-        // Objects in contact should have friction
-        let mut sphere_grounded_1 = return_centered_sphere();
-        sphere_grounded_1.transform.position.y = touching_ground_y_pos;
-        sphere_grounded_1.velocity.x = 5.0;
-        let result = particle_sim::calculate_friction_deceleration(&sphere_grounded_1, 0.2);
-        assert_eq!(result, -1.96);
+    fn xyz(x: f32, y: f32, z: f32) -> XYZ {
+        return XYZ { x, y, z };
+    }
 
-        // Fast moving objects should have more friction than slow moving objects
-        let mut sphere_slow = return_centered_sphere();
-        sphere_slow.transform.position.y = touching_ground_y_pos;
-        sphere_slow.velocity.x = 1.0;
-        let result_slow = particle_sim::calculate_friction_deceleration(&sphere_slow, 0.2);
+    fn assert_xyz_close(actual: XYZ, expected: XYZ) {
+        assert!(
+            (actual - expected).magnitude() < 0.0001,
+            "expected {}, got {}",
+            expected,
+            actual
+        );
+    }
 
-        let mut sphere_fast = return_centered_sphere();
-        sphere_fast.transform.position.y = touching_ground_y_pos;
-        sphere_fast.velocity.x = 10.0;
-        let result_fast = particle_sim::calculate_friction_deceleration(&sphere_fast, 0.2);
-        assert!(result_fast.abs() > result_slow.abs());
+    #[test]
+    fn test_box_box_intersection_face_contact() {
+        let box_1 = ColliderType::AABB {
+            center: xyz(0.0, 0.0, 0.0),
+            half_extents: xyz(1.0, 1.0, 1.0),
+        };
+        let box_2 = ColliderType::AABB {
+            center: xyz(1.5, 0.0, 0.0),
+            half_extents: xyz(1.0, 1.0, 1.0),
+        };
+        let result = box_1.test_collision(&box_2);
+        assert!(result.has_collision);
+        assert!((result.depth - 0.5).abs() < 0.0001);
+        // Box 1 must move in -X to separate
+        assert_xyz_close(result.normal, xyz(-1.0, 0.0, 0.0));
+        // The deepest points are the centers of the overlapping faces
+        assert_xyz_close(result.a, xyz(1.0, 0.0, 0.0));
+        assert_xyz_close(result.b, xyz(0.5, 0.0, 0.0));
 
-        // Objects in contact should have friction regardless of direction
-        let mut sphere_grounded_2 = return_centered_sphere();
-        sphere_grounded_2.transform.position.y = touching_ground_y_pos;
-        sphere_grounded_2.velocity.x = -1.0;
-        let result = particle_sim::calculate_friction_deceleration(&sphere_grounded_2, 0.2);
-        assert_eq!(result, 1.0);
+        let far_box = ColliderType::AABB {
+            center: xyz(2.5, 0.0, 0.0),
+            half_extents: xyz(1.0, 1.0, 1.0),
+        };
+        let result = box_1.test_collision(&far_box);
+        assert!(!result.has_collision);
+        assert!(result.depth < 0.0);
+    }
 
-        // TODO: test the relationship between friction coefficient magnitude and velocity magnitude.
-        // The idea is to codify a logical relationship between the two units, instead of the current
-        // slightly magical numbers (e.g. why does 0.01 friction coefficient almost instantly stop +5 velocity)?
-        // what exactly is the relationship between the two numbers?
+    #[test]
+    fn test_oriented_box_intersection_uses_rotation() {
+        // Rotated by 45 degrees, a unit box reaches sqrt(2) along X, so it now touches a box it
+        // would otherwise miss
+        let rotated = ColliderType::OBB {
+            center: xyz(0.0, 0.0, 0.0),
+            half_extents: xyz(1.0, 1.0, 1.0),
+            rotation: Quat::from_rotation_z(std::f32::consts::FRAC_PI_4),
+        };
+        let unrotated = ColliderType::OBB {
+            center: xyz(0.0, 0.0, 0.0),
+            half_extents: xyz(1.0, 1.0, 1.0),
+            rotation: Quat::IDENTITY,
+        };
+        let other = ColliderType::AABB {
+            center: xyz(2.3, 0.0, 0.0),
+            half_extents: xyz(1.0, 1.0, 1.0),
+        };
+        assert!(!unrotated.test_collision(&other).has_collision);
+        let result = rotated.test_collision(&other);
+        assert!(result.has_collision);
+        assert!((result.depth - (2.0_f32.sqrt() + 1.0 - 2.3)).abs() < 0.0001);
+        assert_xyz_close(result.normal, xyz(-1.0, 0.0, 0.0));
+        // The rotated box's deepest point is its corner on the X axis
+        assert_xyz_close(result.a, xyz(2.0_f32.sqrt(), 0.0, 0.0));
 
-        // Test negative friction. It's not required, or how physics works, but it's fun.
-        let mut sphere_magical = return_centered_sphere();
-        sphere_magical.transform.position.y = touching_ground_y_pos;
-        sphere_magical.velocity.x = -5.0;
-        let result = particle_sim::calculate_friction_deceleration(&sphere_magical, -0.2);
-        assert_eq!(result, -1.96);
+        // The bounding box must contain the rotated box
+        let bounds = rotated.bounding_box();
+        assert!((bounds.max.x - 2.0_f32.sqrt()).abs() < 0.0001);
+        assert!((bounds.max.z - 1.0).abs() < 0.0001);
     }
 
     #[test]
-    fn test_bounce_y_basic() {
-        // Test bounces in both Y directions
+    fn test_box_sphere_intersection() {
+        let cube = ColliderType::AABB {
+            center: xyz(0.0, 0.0, 0.0),
+            half_extents: xyz(1.0, 2.0, 1.0),
+        };
+        // A sphere touching the top face
+        let sphere = ColliderType::SPHERE {
+            center: xyz(0.5, 2.5, 0.0),
+            radius: 1.0,
+        };
+        let result = sphere.test_collision(&cube);
+        assert!(result.has_collision);
+        assert!((result.depth - 0.5).abs() < 0.0001);
+        assert_xyz_close(result.normal, xyz(0.0, 1.0, 0.0));
+        assert_xyz_close(result.a, xyz(0.5, 1.5, 0.0));
+        assert_xyz_close(result.b, xyz(0.5, 2.0, 0.0));
 
-        // Choose a large velocity, to stress the calculation logic, and have the object
-        // bouncing within 1 second
-        let initial_y_velocity =
-            1.0 + convert_pixels_to_meters(-0.5 * SCREEN_HEIGHT - 1.0, PIXELS_PER_METER);
+        // Testing the box against the sphere describes the same collision from the box's perspective
+        let swapped = cube.test_collision(&sphere);
+        assert_eq!(swapped.depth, result.depth);
+        assert_eq!(swapped.a, result.b);
+        assert_eq!(swapped.b, result.a);
+        assert_xyz_close(swapped.normal, xyz(0.0, -1.0, 0.0));
 
-        // We want the particle not already colliding on spawn
-        let mut sphere = return_centered_sphere();
-        sphere.transform.position.x = 0.5 * SCREEN_WIDTH;
-        sphere.transform.position.y = 0.5 * SCREEN_HEIGHT;
-        sphere.velocity.y = initial_y_velocity;
-        let result = particle_sim::calculate_bounce(&sphere, 0.9, 1.0).unwrap();
+        // A sphere near a corner, but not touching it
+        let sphere = ColliderType::SPHERE {
+            center: xyz(1.8, 2.8, 0.0),
+            radius: 1.0,
+        };
+        assert!(!sphere.test_collision(&cube).has_collision);
 
-        // There should be no errors or nans with the parameters we're using here
-        assert!(!result.position.y.is_nan());
-        assert!(!result.velocity.y.is_nan());
+        // A sphere whose center is inside the box is pushed out through the nearest face
+        let sphere = ColliderType::SPHERE {
+            center: xyz(0.8, 0.0, 0.0),
+            radius: 0.5,
+        };
+        let result = sphere.test_collision(&cube);
+        assert!(result.has_collision);
+        assert!((result.depth - 0.7).abs() < 0.0001);
+        assert_xyz_close(result.normal, xyz(1.0, 0.0, 0.0));
+    }
 
-        // The particle should remain within Y bounds
-        assert!(result.position.y >= 0.0);
-        assert!(result.position.y <= SCREEN_HEIGHT + 0.0001);
+    #[test]
+    fn test_box_plane_intersection() {
+        let floor = ColliderType::PLANE {
+            normal: xyz(0.0, 1.0, 0.0),
+            distance: 0.0,
+        };
+        let resting = ColliderType::AABB {
+            center: xyz(3.0, 0.75, 0.0),
+            half_extents: xyz(1.0, 1.0, 1.0),
+        };
+        let result = resting.test_collision(&floor);
+        assert!(result.has_collision);
+        assert!((result.depth - 0.25).abs() < 0.0001);
+        assert_xyz_close(result.normal, xyz(0.0, 1.0, 0.0));
+        assert_xyz_close(result.a, xyz(3.0, -0.25, 0.0));
+        assert_xyz_close(result.b, xyz(3.0, 0.0, 0.0));
 
-        // The particle's X location should be unchanged
-        assert_eq!(result.position.x, 0.5 * SCREEN_WIDTH);
+        let swapped = floor.test_collision(&resting);
+        assert_eq!(swapped.a, result.b);
+        assert_xyz_close(swapped.normal, xyz(0.0, -1.0, 0.0));
+
+        // A box balanced on its edge only reaches the plane with that edge
+        let tilted = ColliderType::OBB {
+            center: xyz(0.0, 2.0_f32.sqrt() - 0.1, 0.0),
+            half_extents: xyz(1.0, 1.0, 1.0),
+            rotation: Quat::from_rotation_z(std::f32::consts::FRAC_PI_4),
+        };
+        let result = tilted.test_collision(&floor);
+        assert!(result.has_collision);
+        assert!((result.depth - 0.1).abs() < 0.0001);
+        assert_xyz_close(result.a, xyz(0.0, -0.1, 0.0));
+
+        let floating = ColliderType::AABB {
+            center: xyz(0.0, 1.5, 0.0),
+            half_extents: xyz(1.0, 1.0, 1.0),
+        };
+        assert!(!floating.test_collision(&floor).has_collision);
+    }
+
+    #[test]
+    fn test_capsule_sphere_intersection() {
+        let capsule = ColliderType::CAPSULE {
+            start: xyz(-2.0, 0.0, 0.0),
+            end: xyz(2.0, 0.0, 0.0),
+            radius: 1.0,
+        };
+        // Alongside the capsule's middle
+        let sphere = ColliderType::SPHERE {
+            center: xyz(0.5, 1.5, 0.0),
+            radius: 1.0,
+        };
+        let result = capsule.test_collision(&sphere);
+        assert!(result.has_collision);
+        assert!((result.depth - 0.5).abs() < 0.0001);
+        assert_xyz_close(result.normal, xyz(0.0, -1.0, 0.0));
+        assert_xyz_close(result.a, xyz(0.5, 1.0, 0.0));
+        assert_xyz_close(result.b, xyz(0.5, 0.5, 0.0));
+
+        // Beyond the capsule's end, it behaves like a sphere at the end
+        let sphere = ColliderType::SPHERE {
+            center: xyz(3.5, 0.0, 0.0),
+            radius: 1.0,
+        };
+        let result = sphere.test_collision(&capsule);
+        assert!(result.has_collision);
+        assert!((result.depth - 0.5).abs() < 0.0001);
+        assert_xyz_close(result.normal, xyz(1.0, 0.0, 0.0));
+
+        let sphere = ColliderType::SPHERE {
+            center: xyz(0.0, 2.5, 0.0),
+            radius: 1.0,
+        };
+        assert!(!capsule.test_collision(&sphere).has_collision);
+    }
+
+    #[test]
+    fn test_capsule_capsule_intersection() {
+        // Crossing capsules touch where their segments are closest
+        let capsule_1 = ColliderType::CAPSULE {
+            start: xyz(-2.0, 0.0, 0.0),
+            end: xyz(2.0, 0.0, 0.0),
+            radius: 0.5,
+        };
+        let capsule_2 = ColliderType::CAPSULE {
+            start: xyz(1.0, -2.0, 0.8),
+            end: xyz(1.0, 2.0, 0.8),
+            radius: 0.5,
+        };
+        let result = capsule_1.test_collision(&capsule_2);
+        assert!(result.has_collision);
+        assert!((result.depth - 0.2).abs() < 0.0001);
+        assert_xyz_close(result.normal, xyz(0.0, 0.0, -1.0));
+        assert_xyz_close(result.a, xyz(1.0, 0.0, 0.5));
+        assert_xyz_close(result.b, xyz(1.0, 0.0, 0.3));
+
+        // Parallel capsules
+        let capsule_3 = ColliderType::CAPSULE {
+            start: xyz(-1.0, 0.9, 0.0),
+            end: xyz(5.0, 0.9, 0.0),
+            radius: 0.5,
+        };
+        let result = capsule_1.test_collision(&capsule_3);
+        assert!(result.has_collision);
+        assert!((result.depth - 0.1).abs() < 0.0001);
+        assert_xyz_close(result.normal, xyz(0.0, -1.0, 0.0));
+
+        // A segment is a capsule with no radius
+        let segment = ColliderType::SEGMENT {
+            start: xyz(0.0, 0.4, -3.0),
+            end: xyz(0.0, 0.4, 3.0),
+        };
+        let result = segment.test_collision(&capsule_1);
+        assert!(result.has_collision);
+        assert!((result.depth - 0.1).abs() < 0.0001);
+        assert_xyz_close(result.normal, xyz(0.0, 1.0, 0.0));
+        assert_xyz_close(result.a, xyz(0.0, 0.4, 0.0));
+        let segment = ColliderType::SEGMENT {
+            start: xyz(0.0, 0.6, -3.0),
+            end: xyz(0.0, 0.6, 3.0),
+        };
+        assert!(!segment.test_collision(&capsule_1).has_collision);
+    }
+
+    #[test]
+    fn test_capsule_plane_intersection() {
+        let floor = ColliderType::PLANE {
+            normal: xyz(0.0, 1.0, 0.0),
+            distance: 0.0,
+        };
+        // A sloped capsule, whose lower end dips into the floor
+        let capsule = ColliderType::CAPSULE {
+            start: xyz(0.0, 3.0, 0.0),
+            end: xyz(2.0, 0.5, 0.0),
+            radius: 1.0,
+        };
+        let result = capsule.test_collision(&floor);
+        assert!(result.has_collision);
+        assert!((result.depth - 0.5).abs() < 0.0001);
+        assert_xyz_close(result.normal, xyz(0.0, 1.0, 0.0));
+        assert_xyz_close(result.a, xyz(2.0, -0.5, 0.0));
+        assert_xyz_close(result.b, xyz(2.0, 0.0, 0.0));
+
+        let swapped = floor.test_collision(&capsule);
+        assert_xyz_close(swapped.normal, xyz(0.0, -1.0, 0.0));
+        assert_eq!(swapped.a, result.b);
+
+        let segment = ColliderType::SEGMENT {
+            start: xyz(0.0, 3.0, 0.0),
+            end: xyz(2.0, 0.5, 0.0),
+        };
+        assert!(!segment.test_collision(&floor).has_collision);
+    }
+
+    #[test]
+    fn test_capsule_box_intersection() {
+        let cube = ColliderType::AABB {
+            center: xyz(0.0, 0.0, 0.0),
+            half_extents: xyz(1.0, 1.0, 1.0),
+        };
+        // A capsule lying across the top of the box
+        let capsule = ColliderType::CAPSULE {
+            start: xyz(-3.0, 1.5, 0.0),
+            end: xyz(3.0, 1.5, 0.0),
+            radius: 0.75,
+        };
+        let result = capsule.test_collision(&cube);
+        assert!(result.has_collision);
+        assert!((result.depth - 0.25).abs() < 0.0001);
+        assert_xyz_close(result.normal, xyz(0.0, 1.0, 0.0));
+
+        let swapped = cube.test_collision(&capsule);
+        assert!(swapped.has_collision);
+        assert_xyz_close(swapped.normal, xyz(0.0, -1.0, 0.0));
+
+        let capsule = ColliderType::CAPSULE {
+            start: xyz(-3.0, 2.0, 0.0),
+            end: xyz(3.0, 2.0, 0.0),
+            radius: 0.75,
+        };
+        assert!(!capsule.test_collision(&cube).has_collision);
+    }
+
+    #[test]
+    fn test_capsule_bounding_box_contains_capsule() {
+        let mut object = return_centered_sphere();
+        object.collider = ColliderType::CAPSULE {
+            start: xyz(-2.0, 0.0, 0.0),
+            end: xyz(2.0, 0.0, 0.0),
+            radius: 0.5,
+        };
+        // A quarter turn stands the capsule upright, about the object's position
+        object.transform.rotation = Quat::from_rotation_z(std::f32::consts::FRAC_PI_2);
+        let position = object.transform.position;
+        let bounds = object.world_collider().bounding_box();
+        assert_xyz_close(bounds.min, position + xyz(-0.5, -2.5, -0.5));
+        assert_xyz_close(bounds.max, position + xyz(0.5, 2.5, 0.5));
+    }
+
+    fn return_cube_hull(center: XYZ, half_extent: f32) -> ColliderType {
+        let mut vertices = Vec::new();
+        for signs in 0..8 {
+            let sign = |bit: u32| if signs & (1 << bit) == 0 { -1.0 } else { 1.0 };
+            vertices.push(center + xyz(sign(0), sign(1), sign(2)) * half_extent);
+        }
+        return ColliderType::HULL { vertices };
+    }
+
+    #[test]
+    fn test_hull_box_intersection() {
+        let hull = return_cube_hull(xyz(0.0, 0.0, 0.0), 1.0);
+        let cube = ColliderType::AABB {
+            center: xyz(1.5, 0.2, 0.0),
+            half_extents: xyz(1.0, 1.0, 1.0),
+        };
+        let result = hull.test_collision(&cube);
+        assert!(result.has_collision);
+        assert!((result.depth - 0.5).abs() < 0.0001);
+        assert_xyz_close(result.normal, xyz(-1.0, 0.0, 0.0));
+        // The contact points lie on the overlapping faces
+        assert!((result.a.x - 1.0).abs() < 0.0001);
+        assert!((result.b.x - 0.5).abs() < 0.0001);
+
+        let swapped = cube.test_collision(&hull);
+        assert!((swapped.depth - 0.5).abs() < 0.0001);
+        assert_xyz_close(swapped.normal, xyz(1.0, 0.0, 0.0));
+
+        // Apart, the depth is minus the distance between the shapes
+        let cube = ColliderType::AABB {
+            center: xyz(3.0, 0.2, 0.0),
+            half_extents: xyz(1.0, 1.0, 1.0),
+        };
+        let result = hull.test_collision(&cube);
+        assert!(!result.has_collision);
+        assert!((result.depth + 1.0).abs() < 0.0001);
+        assert_xyz_close(result.normal, xyz(-1.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn test_hull_sphere_intersection() {
+        let hull = return_cube_hull(xyz(10.0, 0.0, 0.0), 1.0);
+        let sphere = ColliderType::SPHERE {
+            center: xyz(10.0, 1.5, 0.0),
+            radius: 1.0,
+        };
+        let result = sphere.test_collision(&hull);
+        assert!(result.has_collision);
+        assert!((result.depth - 0.5).abs() < 0.001);
+        assert_xyz_close(result.normal, xyz(0.0, 1.0, 0.0));
+
+        let sphere = ColliderType::SPHERE {
+            center: xyz(12.0, 2.0, 0.0),
+            radius: 1.0,
+        };
+        let result = sphere.test_collision(&hull);
+        assert!(!result.has_collision);
+        // The closest point of the hull is its edge at (11, 1)
+        assert!((result.depth + (2.0_f32.sqrt() - 1.0)).abs() < 0.001);
+    }
+
+    #[test]
+    fn test_polygon_polygon_intersection_in_plane() {
+        // Two squares in the XY plane, overlapping by 0.5 along X and 1.5 along Y
+        let square = |x: f32, y: f32| ColliderType::POLYGON {
+            vertices: vec![
+                xyz(x, y, 0.0),
+                xyz(x + 2.0, y, 0.0),
+                xyz(x + 2.0, y + 2.0, 0.0),
+                xyz(x, y + 2.0, 0.0),
+            ],
+        };
+        let result = square(0.0, 0.0).test_collision(&square(1.5, 0.5));
+        assert!(result.has_collision);
+        assert!((result.depth - 0.5).abs() < 0.0001);
+        assert_xyz_close(result.normal, xyz(-1.0, 0.0, 0.0));
+        assert!((result.a.x - 2.0).abs() < 0.0001);
+        assert!((result.b.x - 1.5).abs() < 0.0001);
+
+        let result = square(0.0, 0.0).test_collision(&square(2.5, 0.5));
+        assert!(!result.has_collision);
+        assert!((result.depth + 0.5).abs() < 0.0001);
+    }
+
+    #[test]
+    fn test_polygon_plane_intersection() {
+        let floor = ColliderType::PLANE {
+            normal: xyz(0.0, 1.0, 0.0),
+            distance: 0.0,
+        };
+        let triangle = ColliderType::POLYGON {
+            vertices: vec![xyz(0.0, 2.0, 0.0), xyz(1.0, -0.5, 0.0), xyz(-1.0, 1.0, 0.0)],
+        };
+        let result = triangle.test_collision(&floor);
+        assert!(result.has_collision);
+        assert!((result.depth - 0.5).abs() < 0.0001);
+        assert_xyz_close(result.normal, xyz(0.0, 1.0, 0.0));
+        assert_xyz_close(result.a, xyz(1.0, -0.5, 0.0));
+        assert_xyz_close(result.b, xyz(1.0, 0.0, 0.0));
+        assert_xyz_close(floor.test_collision(&triangle).normal, xyz(0.0, -1.0, 0.0));
+    }
+
+    #[test]
+    fn test_empty_polygon_and_hull_never_collide() {
+        let others = [
+            ColliderType::SPHERE {
+                center: xyz(0.0, 0.0, 0.0),
+                radius: 1.0,
+            },
+            ColliderType::AABB {
+                center: xyz(0.0, 0.0, 0.0),
+                half_extents: xyz(1.0, 1.0, 1.0),
+            },
+            ColliderType::PLANE {
+                normal: xyz(0.0, 1.0, 0.0),
+                distance: 0.0,
+            },
+            return_cube_hull(xyz(0.0, 0.0, 0.0), 1.0),
+            ColliderType::HULL { vertices: vec![] },
+        ];
+        for empty in [
+            ColliderType::POLYGON { vertices: vec![] },
+            ColliderType::HULL { vertices: vec![] },
+        ] {
+            for other in &others {
+                assert!(!empty.test_collision(other).has_collision);
+                assert!(!other.test_collision(&empty).has_collision);
+            }
+        }
+    }
+
+    #[test]
+    fn test_gjk_matches_box_box_intersection() {
+        // The generic path should agree with the bespoke box-box function for any orientation
+        let mut state = 7;
+        for _ in 0..200 {
+            let mut random_box = || {
+                let axis = xyz(
+                    next_pseudo_random(&mut state),
+                    next_pseudo_random(&mut state),
+                    next_pseudo_random(&mut state),
+                )
+                .normalize();
+                ColliderType::OBB {
+                    center: xyz(
+                        next_pseudo_random(&mut state),
+                        next_pseudo_random(&mut state),
+                        next_pseudo_random(&mut state),
+                    ) * 2.0,
+                    half_extents: xyz(
+                        1.0 + next_pseudo_random(&mut state) * 0.5,
+                        1.0 + next_pseudo_random(&mut state) * 0.5,
+                        1.0 + next_pseudo_random(&mut state) * 0.5,
+                    ),
+                    rotation: Quat::from_axis_angle(
+                        glam::Vec3::new(axis.x, axis.y, axis.z),
+                        next_pseudo_random(&mut state) * std::f32::consts::PI,
+                    ),
+                }
+            };
+            let (box_1, box_2) = (random_box(), random_box());
+            let expected = box_1.test_collision(&box_2);
+            let result = gjk_epa_collision_points(&box_1, &box_2);
+            assert_eq!(result.has_collision, expected.has_collision);
+            if expected.has_collision {
+                assert!(
+                    (result.depth - expected.depth).abs() < 0.001,
+                    "expected {}, got {}",
+                    expected,
+                    result
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_world_collider_follows_transform() {
+        let mut object = return_centered_sphere();
+        object.transform.position = xyz(100.0, 50.0, 0.0);
+        object.collider = ColliderType::SPHERE {
+            center: xyz(2.0, 0.0, 0.0),
+            radius: 1.0,
+        };
+        let ColliderType::SPHERE { center, radius } = object.world_collider() else {
+            panic!("a sphere should stay a sphere");
+        };
+        assert_xyz_close(center, xyz(102.0, 50.0, 0.0));
+        assert_eq!(radius, 1.0);
+
+        // The offset is scaled, then rotated, and the radius grows with the largest scale factor
+        object.transform.scale = xyz(3.0, 1.0, 1.0);
+        object.transform.rotation = Quat::from_rotation_z(std::f32::consts::FRAC_PI_2);
+        let ColliderType::SPHERE { center, radius } = object.world_collider() else {
+            panic!("a sphere should stay a sphere");
+        };
+        assert_xyz_close(center, xyz(100.0, 56.0, 0.0));
+        assert_eq!(radius, 3.0);
+
+        // Moving the object moves its collider, so it collides where it's drawn
+        let mut other = return_centered_sphere();
+        other.transform.position = xyz(100.0, 58.5, 0.0);
+        other.collider = ColliderType::SPHERE {
+            center: xyz(0.0, 0.0, 0.0),
+            radius: 1.0,
+        };
+        let result = object
+            .world_collider()
+            .test_collision(&other.world_collider());
+        assert!(result.has_collision);
+        assert!((result.depth - 1.5).abs() < 0.0001);
+    }
+
+    #[test]
+    fn test_world_collider_rotates_axis_aligned_box() {
+        let mut object = return_centered_sphere();
+        object.transform.position = xyz(10.0, 10.0, 0.0);
+        object.collider = ColliderType::AABB {
+            center: xyz(0.0, 0.0, 0.0),
+            half_extents: xyz(2.0, 1.0, 1.0),
+        };
+        object.transform.scale = xyz(1.0, -2.0, 1.0);
+        let ColliderType::AABB { half_extents, .. } = object.world_collider() else {
+            panic!("an unrotated axis-aligned box should stay axis-aligned");
+        };
+        assert_xyz_close(half_extents, xyz(2.0, 2.0, 1.0));
+
+        object.transform.rotation = Quat::from_rotation_z(0.3);
+        let ColliderType::OBB { rotation, .. } = object.world_collider() else {
+            panic!("a rotated axis-aligned box should become an oriented box");
+        };
+        assert_eq!(rotation, object.transform.rotation);
+    }
+
+    #[test]
+    fn test_world_collider_moves_plane() {
+        let mut object = return_centered_sphere();
+        object.transform.position = xyz(0.0, 5.0, 0.0);
+        object.collider = ColliderType::PLANE {
+            normal: xyz(0.0, 1.0, 0.0),
+            distance: 1.0,
+        };
+        let ColliderType::PLANE { normal, distance } = object.world_collider() else {
+            panic!("a plane should stay a plane");
+        };
+        assert_xyz_close(normal, xyz(0.0, 1.0, 0.0));
+        assert!((distance - 6.0).abs() < 0.0001);
+
+        // A quarter turn makes the plane face -X, through the point (-1, 5)
+        object.transform.rotation = Quat::from_rotation_z(std::f32::consts::FRAC_PI_2);
+        let ColliderType::PLANE { normal, distance } = object.world_collider() else {
+            panic!("a plane should stay a plane");
+        };
+        assert_xyz_close(normal, xyz(-1.0, 0.0, 0.0));
+        assert!((distance - 1.0).abs() < 0.0001);
+
+        // A 45 degree slope through the origin, stretched along X, becomes shallower
+        object.transform.position = XYZ::default();
+        object.transform.rotation = Quat::IDENTITY;
+        object.transform.scale = xyz(2.0, 1.0, 1.0);
+        object.collider = ColliderType::PLANE {
+            normal: xyz(1.0, 1.0, 0.0).normalize(),
+            distance: 0.0,
+        };
+        let ColliderType::PLANE { normal, .. } = object.world_collider() else {
+            panic!("a plane should stay a plane");
+        };
+        // The point (1, -1) is on the slope, so (2, -1) must be on the stretched slope
+        assert!(normal.dot(xyz(2.0, -1.0, 0.0)).abs() < 0.0001);
+    }
+
+    #[test]
+    fn test_world_rejects_object_whose_collider_is_out_of_bounds() {
+        let mut world = World::new(SimConfig::default()).unwrap();
+        let mut object = return_centered_sphere();
+        object.transform.position = xyz(30.0, 100.0, 0.0);
+        object.collider = ColliderType::SPHERE {
+            center: xyz(0.0, 0.0, 0.0),
+            radius: 20.0,
+        };
+        assert!(world.add_object(object.clone()).is_ok());
+        // The position is in bounds, but the collider isn't
+        object.collider = ColliderType::SPHERE {
+            center: xyz(-20.0, 0.0, 0.0),
+            radius: 20.0,
+        };
+        assert!(world.add_object(object).is_err());
+    }
+
+    /// Return a dumbbell lying along the X axis: a sphere of radius 1 at each end of a bar from
+    /// x = -3 to x = 3, which is a capsule of radius 0.25
+    fn return_dumbbell() -> ColliderType {
+        let child = |x: f32, collider: ColliderType| ChildCollider {
+            transform: Transform {
+                position: xyz(x, 0.0, 0.0),
+                ..Transform::IDENTITY
+            },
+            collider,
+        };
+        let weight = ColliderType::SPHERE {
+            center: xyz(0.0, 0.0, 0.0),
+            radius: 1.0,
+        };
+        return ColliderType::COMPOUND {
+            children: vec![
+                child(-3.0, weight.clone()),
+                child(3.0, weight),
+                child(
+                    0.0,
+                    ColliderType::CAPSULE {
+                        start: xyz(-3.0, 0.0, 0.0),
+                        end: xyz(3.0, 0.0, 0.0),
+                        radius: 0.25,
+                    },
+                ),
+            ],
+        };
+    }
+
+    #[test]
+    fn test_compound_intersection_reports_deepest_child() {
+        let dumbbell = return_dumbbell();
+        // Touches both the right weight and the bar, but the weight more deeply
+        let sphere = ColliderType::SPHERE {
+            center: xyz(2.0, 1.2, 0.0),
+            radius: 1.0,
+        };
+        let expected_depth = 2.0 - (1.0_f32 + 1.2 * 1.2).sqrt();
+        let result = dumbbell.test_collision(&sphere);
+        assert!(result.has_collision);
+        assert!((result.depth - expected_depth).abs() < 0.0001);
+        assert_eq!(dumbbell.test_collision_all(&sphere).len(), 2);
+
+        let swapped = sphere.test_collision(&dumbbell);
+        assert!((swapped.depth - expected_depth).abs() < 0.0001);
+        assert_xyz_close(swapped.normal, -result.normal);
+
+        // Above the bar's middle, only the bar is close enough
+        let sphere = ColliderType::SPHERE {
+            center: xyz(0.0, 1.0, 0.0),
+            radius: 1.0,
+        };
+        let contacts = dumbbell.test_collision_all(&sphere);
+        assert_eq!(contacts.len(), 1);
+        assert!((contacts[0].depth - 0.25).abs() < 0.0001);
+
+        // Between the weights, beneath the bar, there's a gap
+        let sphere = ColliderType::SPHERE {
+            center: xyz(0.0, -1.5, 0.0),
+            radius: 1.0,
+        };
+        let result = dumbbell.test_collision(&sphere);
+        assert!(!result.has_collision);
+        assert!((result.depth + 0.25).abs() < 0.0001);
+        assert!(dumbbell.test_collision_all(&sphere).is_empty());
+    }
+
+    #[test]
+    fn test_compound_intersection_ignores_non_colliding_children() {
+        // One sphere dips into the floor, the other is far beneath it, which the plane reports as
+        // a large depth without a collision
+        let sphere = |y: f32| ChildCollider {
+            transform: Transform::IDENTITY,
+            collider: ColliderType::SPHERE {
+                center: xyz(0.0, y, 0.0),
+                radius: 1.0,
+            },
+        };
+        let pair = ColliderType::COMPOUND {
+            children: vec![sphere(0.5), sphere(-100.0)],
+        };
+        let floor = ColliderType::PLANE {
+            normal: xyz(0.0, 1.0, 0.0),
+            distance: 0.0,
+        };
+        assert_eq!(pair.test_collision_all(&floor).len(), 1);
+        for result in [pair.test_collision(&floor), floor.test_collision(&pair)] {
+            assert!(result.has_collision);
+            assert!((result.depth - 0.5).abs() < 0.0001);
+        }
+
+        // With only the far sphere, there's no collision at all
+        let far = ColliderType::COMPOUND {
+            children: vec![sphere(-100.0)],
+        };
+        assert!(!far.test_collision(&floor).has_collision);
+        assert!(far.test_collision_all(&floor).is_empty());
+    }
+
+    #[test]
+    fn test_compound_intersection_reports_all_contacts() {
+        // A floor which both weights of a dumbbell rest on, but the bar doesn't reach
+        let dumbbell = return_dumbbell();
+        let floor = ColliderType::AABB {
+            center: xyz(0.0, -1.9, 0.0),
+            half_extents: xyz(10.0, 1.0, 10.0),
+        };
+        let contacts = dumbbell.test_collision_all(&floor);
+        assert_eq!(contacts.len(), 2);
+        for contact in contacts {
+            assert!((contact.depth - 0.1).abs() < 0.0001);
+            assert_xyz_close(contact.normal, xyz(0.0, 1.0, 0.0));
+        }
+
+        // Two compounds test each child against each child. Here, each weight touches the weight
+        // above or below it, and the other dumbbell's bar, but the bars don't touch each other.
+        let other = return_dumbbell().transformed(&Transform {
+            position: xyz(0.0, 1.2, 0.0),
+            ..Transform::IDENTITY
+        });
+        assert_eq!(dumbbell.test_collision_all(&other).len(), 6);
+    }
+
+    #[test]
+    fn test_compound_follows_object_transform() {
+        let mut object = return_centered_sphere();
+        object.collider = return_dumbbell();
+        object.transform.rotation = Quat::from_rotation_z(std::f32::consts::FRAC_PI_2);
+        let position = object.transform.position;
+        // Standing upright, the dumbbell reaches 4 above and below its position
+        let bounds = object.world_collider().bounding_box();
+        assert_xyz_close(bounds.min, position + xyz(-1.0, -4.0, -1.0));
+        assert_xyz_close(bounds.max, position + xyz(1.0, 4.0, 1.0));
+
+        let sphere = ColliderType::SPHERE {
+            center: position + xyz(0.0, 4.5, 0.0),
+            radius: 1.0,
+        };
+        let result = object.world_collider().test_collision(&sphere);
+        assert!(result.has_collision);
+        assert!((result.depth - 0.5).abs() < 0.0001);
+    }
+
+    #[test]
+    fn test_collider_volume_and_centroid() {
+        let cube = return_cube_hull(xyz(1.0, 2.0, 3.0), 1.0);
+        assert!((cube.volume() - 8.0).abs() < 0.0001);
+        assert_xyz_close(cube.centroid(), xyz(1.0, 2.0, 3.0));
+
+        let triangle = ColliderType::POLYGON {
+            vertices: vec![xyz(0.0, 0.0, 0.0), xyz(3.0, 0.0, 0.0), xyz(0.0, 3.0, 0.0)],
+        };
+        assert!((triangle.volume() - 4.5).abs() < 0.0001);
+        assert_xyz_close(triangle.centroid(), xyz(1.0, 1.0, 0.0));
+
+        // An L-shaped bracket, made of a long and a short box
+        let bracket = ColliderType::COMPOUND {
+            children: vec![
+                ChildCollider {
+                    transform: Transform::IDENTITY,
+                    collider: ColliderType::AABB {
+                        center: xyz(2.0, 0.0, 0.0),
+                        half_extents: xyz(2.0, 0.5, 0.5),
+                    },
+                },
+                ChildCollider {
+                    transform: Transform::IDENTITY,
+                    collider: ColliderType::AABB {
+                        center: xyz(0.0, 1.5, 0.0),
+                        half_extents: xyz(0.5, 1.0, 0.5),
+                    },
+                },
+            ],
+        };
+        assert!((bracket.volume() - 6.0).abs() < 0.0001);
+        // The long box has twice the volume, so pulls the centroid twice as hard
+        assert_xyz_close(bracket.centroid(), xyz(4.0 / 3.0, 0.5, 0.0));
+
+        let dumbbell = return_dumbbell();
+        let sphere_volume = 4.0 / 3.0 * std::f32::consts::PI;
+        let bar_volume = std::f32::consts::PI * 0.0625 * (6.0 + 1.0 / 3.0);
+        assert!((dumbbell.volume() - (2.0 * sphere_volume + bar_volume)).abs() < 0.0001);
+        assert_xyz_close(dumbbell.centroid(), xyz(0.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn test_object_rotation_rotates_oriented_box() {
+        let mut object = return_centered_sphere();
+        object.collider = ColliderType::OBB {
+            center: xyz(0.0, 0.0, 0.0),
+            half_extents: xyz(2.0, 1.0, 1.0),
+            rotation: Quat::IDENTITY,
+        };
+        object.transform.rotation = Quat::from_rotation_z(std::f32::consts::FRAC_PI_2);
+        // Rotated a quarter turn, the box's long axis is along Y
+        let bounds = object.world_collider().bounding_box();
+        let position = object.transform.position;
+        assert!((bounds.max.x - position.x - 1.0).abs() < 0.0001);
+        assert!((bounds.max.y - position.y - 2.0).abs() < 0.0001);
+    }
+
+    #[test]
+    fn test_plane_plane_intersection_parallel_and_coincident() {
+        let floor = ColliderType::PLANE {
+            normal: xyz(0.0, 1.0, 0.0),
+            distance: 0.0,
+        };
+        let shelf = ColliderType::PLANE {
+            normal: xyz(0.0, 1.0, 0.0),
+            distance: 3.0,
+        };
+        let result = shelf.test_collision(&floor);
+        assert!(!result.has_collision);
+        assert_eq!(result.depth, -3.0);
+        assert_xyz_close(result.normal, xyz(0.0, 1.0, 0.0));
+        assert_xyz_close(result.a, xyz(0.0, 3.0, 0.0));
+        assert_xyz_close(result.b, xyz(0.0, 0.0, 0.0));
+        assert_eq!(
+            plane_plane_intersection(xyz(0.0, 1.0, 0.0), 0.0, xyz(0.0, 1.0, 0.0), 3.0),
+            PlaneIntersection::Parallel {
+                separation: xyz(0.0, -3.0, 0.0)
+            }
+        );
+
+        // A plane facing the other way, but describing the same surface as the shelf
+        let ceiling = ColliderType::PLANE {
+            normal: xyz(0.0, -1.0, 0.0),
+            distance: -3.0,
+        };
+        let result = shelf.test_collision(&ceiling);
+        assert!(result.has_collision);
+        assert_eq!(result.depth, 0.0);
+        assert_eq!(
+            plane_plane_intersection(xyz(0.0, 1.0, 0.0), 3.0, xyz(0.0, -1.0, 0.0), -3.0),
+            PlaneIntersection::Coincident
+        );
+    }
+
+    #[test]
+    fn test_plane_plane_intersection_line() {
+        // The planes x = 1 and y = 2 cross along a line parallel to the Z axis
+        let intersection =
+            plane_plane_intersection(xyz(1.0, 0.0, 0.0), 1.0, xyz(0.0, 1.0, 0.0), 2.0);
+        match intersection {
+            PlaneIntersection::Line { point, direction } => {
+                assert_xyz_close(point, xyz(1.0, 2.0, 0.0));
+                assert_xyz_close(direction, xyz(0.0, 0.0, 1.0));
+            }
+            other => panic!("expected a line, got {:?}", other),
+        }
+
+        // A slope crossing the floor. Its line must lie on both planes.
+        let slope_normal = xyz(1.0, 1.0, 0.0).normalize();
+        let intersection = plane_plane_intersection(slope_normal, 5.0, xyz(0.0, 1.0, 0.0), 0.0);
+        let PlaneIntersection::Line { point, direction } = intersection else {
+            panic!("expected a line, got {:?}", intersection);
+        };
+        for t in [-10.0, 0.0, 10.0] {
+            let on_line = point + direction * t;
+            assert!((on_line.dot(slope_normal) - 5.0).abs() < 0.0001);
+            assert!(on_line.y.abs() < 0.0001);
+        }
+
+        let slope = ColliderType::PLANE {
+            normal: slope_normal,
+            distance: 5.0,
+        };
+        let floor = ColliderType::PLANE {
+            normal: xyz(0.0, 1.0, 0.0),
+            distance: 0.0,
+        };
+        let result = slope.test_collision(&floor);
+        assert!(result.has_collision);
+        assert_xyz_close(result.a, point);
+    }
+
+    #[test]
+    fn test_none_collider_never_collides() {
+        let ghost = ColliderType::NONE;
+        let others = [
+            ColliderType::SPHERE {
+                center: xyz(0.0, 0.0, 0.0),
+                radius: 1.0,
+            },
+            ColliderType::PLANE {
+                normal: xyz(0.0, 1.0, 0.0),
+                distance: 0.0,
+            },
+            return_cube_hull(xyz(0.0, 0.0, 0.0), 1.0),
+            ColliderType::NONE,
+        ];
+        for other in &others {
+            assert!(!ghost.test_collision(other).has_collision);
+            assert!(!other.test_collision(&ghost).has_collision);
+        }
+
+        // A ghost overlapping a sphere shouldn't affect it, or be affected by it
+        let mut ghost = return_moving_sphere(0.5, -2.0, 1.0, 1.0);
+        ghost.collider = ColliderType::NONE;
+        let mut objects = vec![return_moving_sphere(0.0, 2.0, 1.0, 1.0), ghost];
+        for broadphase in [
+            BroadphaseType::BruteForce,
+            BroadphaseType::UniformGrid { cell_size: 4.0 },
+            BroadphaseType::SweepAndPrune,
+        ] {
+            resolve_contacts_with_restitution(&mut objects, 1.0, broadphase.broadphase().as_mut());
+            assert_eq!(objects[0].velocity.x, 2.0);
+            assert_eq!(objects[1].velocity.x, -2.0);
+            assert_eq!(objects[1].transform.position.x, 0.5);
+        }
+    }
+
+    /// Resolve contacts between objects which all have the given restitution
+    fn resolve_contacts_with_restitution(
+        objects: &mut [Object],
+        restitution: f32,
+        broadphase: &mut dyn Broadphase,
+    ) {
+        for p in objects.iter_mut() {
+            p.material.restitution = restitution;
+        }
+        resolve_contacts(objects, &CombineRules::default(), broadphase);
+    }
+
+    /// Return a sphere of the given radius and mass, at the origin plus `x`, moving along the X axis
+    fn return_moving_sphere(x: f32, x_velocity: f32, radius: f32, mass: f32) -> Object {
+        let mut sphere = return_centered_sphere();
+        sphere.transform.position = XYZ { x, y: 0.0, z: 0.0 };
+        sphere.velocity.x = x_velocity;
+        sphere.mass = mass;
+        sphere.collider = ColliderType::SPHERE {
+            center: XYZ {
+                x: 0.0,
+                y: 0.0,
+                z: 0.0,
+            },
+            radius,
+        };
+        return sphere;
+    }
+
+    #[test]
+    fn test_elastic_collision_exchanges_velocities() {
+        // In a perfectly elastic head-on collision between equal masses, the objects swap velocities
+        let mut objects = vec![
+            return_moving_sphere(0.0, 5.0, 1.0, 1.0),
+            return_moving_sphere(1.5, 0.0, 1.0, 1.0),
+        ];
+        resolve_contacts_with_restitution(&mut objects, 1.0, &mut BruteForce);
+        assert!(objects[0].velocity.x.abs() < 0.0001);
+        assert!((objects[1].velocity.x - 5.0).abs() < 0.0001);
+        assert_eq!(objects[0].velocity.y, 0.0);
+        assert_eq!(objects[1].velocity.y, 0.0);
+
+        // The result shouldn't depend on the order of the objects
+        let mut objects = vec![
+            return_moving_sphere(1.5, 0.0, 1.0, 1.0),
+            return_moving_sphere(0.0, 5.0, 1.0, 1.0),
+        ];
+        resolve_contacts_with_restitution(&mut objects, 1.0, &mut BruteForce);
+        assert!((objects[0].velocity.x - 5.0).abs() < 0.0001);
+        assert!(objects[1].velocity.x.abs() < 0.0001);
+    }
+
+    #[test]
+    fn test_collision_conserves_momentum() {
+        let restitution = 0.5;
+        let mut objects = vec![
+            return_moving_sphere(0.0, 3.0, 1.0, 2.0),
+            return_moving_sphere(1.8, -4.0, 1.0, 5.0),
+        ];
+        let momentum =
+            |objects: &Vec<Object>| objects.iter().map(|p| p.mass * p.velocity.x).sum::<f32>();
+        let initial_momentum = momentum(&objects);
+        let initial_closing_speed = objects[0].velocity.x - objects[1].velocity.x;
+
+        resolve_contacts_with_restitution(&mut objects, restitution, &mut BruteForce);
+        assert!((momentum(&objects) - initial_momentum).abs() < 0.0001);
+
+        // The objects should now be separating, at the closing speed scaled by the restitution
+        let separating_speed = objects[1].velocity.x - objects[0].velocity.x;
+        assert!((separating_speed - restitution * initial_closing_speed).abs() < 0.0001);
+
+        // The heavier object should have had its velocity changed less
+        assert!((objects[0].velocity.x - 3.0).abs() > (objects[1].velocity.x + 4.0).abs());
+    }
+
+    #[test]
+    fn test_collision_corrects_penetration() {
+        let mut objects = vec![
+            return_moving_sphere(0.0, 0.0, 1.0, 1.0),
+            return_moving_sphere(1.0, 0.0, 1.0, 1.0),
+        ];
+        resolve_contacts_with_restitution(&mut objects, 0.5, &mut BruteForce);
+        let separation = objects[1].transform.position.x - objects[0].transform.position.x;
+        // The spheres overlapped by 1.0, and should have been pushed apart by most of that, equally
+        assert!(separation > 1.5);
+        assert!(
+            (objects[0].transform.position.x + objects[1].transform.position.x - 1.0).abs()
+                < 0.0001
+        );
+
+        // Separating objects shouldn't be pulled back together, only pushed apart
+        let mut objects = vec![
+            return_moving_sphere(0.0, -1.0, 1.0, 1.0),
+            return_moving_sphere(1.0, 1.0, 1.0, 1.0),
+        ];
+        resolve_contacts_with_restitution(&mut objects, 0.5, &mut BruteForce);
+        assert_eq!(objects[0].velocity.x, -1.0);
+        assert_eq!(objects[1].velocity.x, 1.0);
+    }
+
+    #[test]
+    fn test_combine_modes() {
+        assert_eq!(CombineMode::Min.combine(0.2, 0.8), 0.2);
+        assert_eq!(CombineMode::Max.combine(0.2, 0.8), 0.8);
+        assert_eq!(CombineMode::Average.combine(0.2, 0.8), 0.5);
+        assert!((CombineMode::Multiply.combine(0.2, 0.8) - 0.16).abs() < 0.0001);
+
+        let rubber = Material {
+            restitution: 0.8,
+            static_friction: 1.0,
+            dynamic_friction: 0.8,
+            rolling_resistance: 0.02,
+        };
+        let ice = Material {
+            restitution: 0.1,
+            static_friction: 0.1,
+            dynamic_friction: 0.02,
+            rolling_resistance: 0.0,
+        };
+        let rules = CombineRules {
+            restitution: CombineMode::Max,
+            friction: CombineMode::Min,
+        };
+        // Restitution and friction follow their own rules, and the order of materials doesn't matter
+        let contact = rubber.combine(&ice, &rules);
+        assert_eq!(contact, ice.combine(&rubber, &rules));
+        assert_eq!(
+            contact,
+            Material {
+                restitution: 0.8,
+                ..ice
+            }
+        );
+    }
+
+    #[test]
+    fn test_materials_change_bounce() {
+        let bounce_off_floor = |ball_material: Material, floor_material: Material, rules| {
+            let mut floor = return_centered_sphere();
+            floor.mass = f32::INFINITY;
+            floor.transform.position = XYZ::default();
+            floor.collider = ColliderType::PLANE {
+                normal: xyz(0.0, 1.0, 0.0),
+                distance: 0.0,
+            };
+            floor.material = floor_material;
+            let mut ball = return_moving_sphere(0.0, 0.0, 1.0, 1.0);
+            ball.transform.position.y = 0.5;
+            ball.velocity.y = -4.0;
+            ball.material = ball_material;
+            let mut objects = vec![floor, ball];
+            resolve_contacts(&mut objects, &rules, &mut BruteForce);
+            return objects[1].velocity.y;
+        };
+        let rubber = Material {
+            restitution: 0.9,
+            ..Material::default()
+        };
+        let steel = Material {
+            restitution: 0.5,
+            ..Material::default()
+        };
+        let floor = Material {
+            restitution: 0.5,
+            ..Material::default()
+        };
+
+        // A rubber ball bounces higher than a steel one off the same floor
+        let rules = CombineRules::default();
+        assert!((bounce_off_floor(rubber, floor, rules) - 2.8).abs() < 0.0001);
+        assert!((bounce_off_floor(steel, floor, rules) - 2.0).abs() < 0.0001);
+
+        for (mode, expected) in [
+            (CombineMode::Min, 2.0),
+            (CombineMode::Max, 3.6),
+            (CombineMode::Multiply, 1.8),
+        ] {
+            let rules = CombineRules {
+                restitution: mode,
+                ..CombineRules::default()
+            };
+            let bounce = bounce_off_floor(rubber, floor, rules);
+            assert!((bounce - expected).abs() < 0.0001, "{:?}: {}", mode, bounce);
+        }
+    }
+
+    #[test]
+    fn test_arena_contact_material() {
+        let config = SimConfig {
+            arena_material: Material {
+                restitution: 0.5,
+                static_friction: 0.4,
+                dynamic_friction: 0.2,
+                rolling_resistance: 0.0,
+            },
+            ..SimConfig::default()
+        };
+        let mut particle = return_centered_sphere();
+        particle.material = Material {
+            restitution: 0.7,
+            static_friction: 0.6,
+            dynamic_friction: 0.4,
+            rolling_resistance: 0.1,
+        };
+        let contact = arena_contact_material(&particle, &config);
+        assert!((contact.restitution - 0.6).abs() < 0.0001);
+        assert!((contact.dynamic_friction - 0.3).abs() < 0.0001);
+        assert!((contact.rolling_resistance - 0.05).abs() < 0.0001);
+
+        // The arena bounce can't terminate on a perfectly elastic bounce, so the restitution is capped
+        particle.material.restitution = 1.0;
+        let config = SimConfig {
+            material_combine: CombineRules {
+                restitution: CombineMode::Max,
+                ..CombineRules::default()
+            },
+            ..config
+        };
+        assert!(arena_contact_material(&particle, &config).restitution < 1.0);
+    }
+
+    #[test]
+    fn test_collision_with_immovable_object() {
+        // A sphere landing on an infinitely heavy floor should bounce, without moving the floor
+        let mut floor = return_centered_sphere();
+        floor.mass = f32::INFINITY;
+        // Colliders are relative to the object's position, so put the floor's object at the origin
+        floor.transform.position = XYZ::default();
+        floor.collider = ColliderType::PLANE {
+            normal: XYZ {
+                x: 0.0,
+                y: 1.0,
+                z: 0.0,
+            },
+            distance: 0.0,
+        };
+        let mut ball = return_moving_sphere(0.0, 0.0, 1.0, 1.0);
+        ball.transform.position.y = 0.5;
+        ball.velocity.y = -3.0;
+
+        let mut objects = vec![floor, ball];
+        let floor_position = objects[0].transform.position;
+        resolve_contacts_with_restitution(&mut objects, 0.5, &mut BruteForce);
+        assert_eq!(objects[0].transform.position, floor_position);
+        assert_eq!(objects[0].velocity.y, 0.0);
+        assert!((objects[1].velocity.y - 1.5).abs() < 0.0001);
+        assert!(objects[1].transform.position.y > 0.5);
+    }
+
+    fn assert_mat3_close(actual: Mat3, expected: Mat3) {
+        assert!(
+            actual.abs_diff_eq(expected, 0.001),
+            "expected {:?}, got {:?}",
+            expected,
+            actual
+        );
+    }
+
+    #[test]
+    fn test_inertia_tensors_of_simple_shapes() {
+        let diagonal = |x: f32, y: f32, z: f32| Mat3::from_diagonal(glam::Vec3::new(x, y, z));
+        let origin = xyz(0.0, 0.0, 0.0);
+
+        let sphere = ColliderType::SPHERE {
+            center: origin,
+            radius: 2.0,
+        };
+        assert_mat3_close(sphere.inertia_tensor(5.0, origin), diagonal(8.0, 8.0, 8.0));
+
+        // A 2x4x6 box has I_x = m (4^2 + 6^2) / 12
+        let cube = ColliderType::AABB {
+            center: origin,
+            half_extents: xyz(1.0, 2.0, 3.0),
+        };
+        let expected = diagonal(52.0, 40.0, 20.0) * (3.0 / 12.0);
+        assert_mat3_close(cube.inertia_tensor(3.0, origin), expected);
+
+        // A hull with the same corners has the same inertia
+        let mut vertices = Vec::new();
+        for signs in 0..8 {
+            let sign = |bit: u32| if signs & (1 << bit) == 0 { -1.0 } else { 1.0 };
+            vertices.push(xyz(sign(0), 2.0 * sign(1), 3.0 * sign(2)));
+        }
+        let hull = ColliderType::HULL { vertices };
+        assert_mat3_close(hull.inertia_tensor(3.0, origin), expected);
+
+        // A thin rod along X, spun about one end, has I = m L^2 / 3 across its axis
+        let rod = ColliderType::SEGMENT {
+            start: origin,
+            end: xyz(3.0, 0.0, 0.0),
+        };
+        assert_mat3_close(rod.inertia_tensor(2.0, origin), diagonal(0.0, 6.0, 6.0));
+
+        // A square plate in the XY plane has I_z = m (a^2 + b^2) / 12
+        let plate = ColliderType::POLYGON {
+            vertices: vec![
+                xyz(-1.0, -1.0, 0.0),
+                xyz(1.0, -1.0, 0.0),
+                xyz(1.0, 1.0, 0.0),
+                xyz(-1.0, 1.0, 0.0),
+            ],
+        };
+        assert_mat3_close(plate.inertia_tensor(6.0, origin), diagonal(2.0, 2.0, 4.0));
+
+        // Rotating a box rotates its tensor
+        let rotated = ColliderType::OBB {
+            center: origin,
+            half_extents: xyz(1.0, 2.0, 3.0),
+            rotation: Quat::from_rotation_z(std::f32::consts::FRAC_PI_2),
+        };
+        let expected = diagonal(40.0, 52.0, 20.0) * (3.0 / 12.0);
+        assert_mat3_close(rotated.inertia_tensor(3.0, origin), expected);
+    }
+
+    #[test]
+    fn test_compound_inertia_uses_parallel_axis_theorem() {
+        // Two point-like spheres far apart, spun about their midpoint, have I = 2 (m/2) d^2
+        let child = |x: f32| ChildCollider {
+            transform: Transform::IDENTITY,
+            collider: ColliderType::SPHERE {
+                center: xyz(x, 0.0, 0.0),
+                radius: 0.001,
+            },
+        };
+        let dumbbell = ColliderType::COMPOUND {
+            children: vec![child(-2.0), child(2.0)],
+        };
+        let inertia = dumbbell.inertia_tensor(2.0, xyz(0.0, 0.0, 0.0));
+        assert!(inertia.x_axis.x.abs() < 0.0001);
+        assert!((inertia.y_axis.y - 8.0).abs() < 0.0001);
+        assert!((inertia.z_axis.z - 8.0).abs() < 0.0001);
+    }
+
+    #[test]
+    fn test_mass_derived_from_density() {
+        let sphere = |radius: f32| ColliderType::SPHERE {
+            center: xyz(0.0, 0.0, 0.0),
+            radius,
+        };
+        let small = Object::from_density(Transform::IDENTITY, sphere(2.0), 3.0);
+        let expected_mass = 3.0 * 4.0 / 3.0 * std::f32::consts::PI * 8.0;
+        assert!((small.mass - expected_mass).abs() < 0.001);
+        assert_eq!(small.velocity, xyz(0.0, 0.0, 0.0));
+        let inertia = 0.4 * expected_mass * 4.0;
+        assert_mat3_close(
+            small.inertia_tensor(),
+            Mat3::from_diagonal(glam::Vec3::splat(inertia)),
+        );
+
+        // Doubling the radius makes it 8 times heavier, whether by its size or its scale
+        let big = Object::from_density(Transform::IDENTITY, sphere(4.0), 3.0);
+        assert!((big.mass - 8.0 * expected_mass).abs() < 0.01);
+        let scaled = Object::from_density(
+            Transform {
+                scale: xyz(2.0, 2.0, 2.0),
+                ..Transform::IDENTITY
+            },
+            sphere(2.0),
+            3.0,
+        );
+        assert!((scaled.mass - big.mass).abs() < 0.01);
+        assert_mat3_close(scaled.inertia_tensor(), big.inertia_tensor());
+
+        // Planes are immovable, and segments have no volume to weigh
+        let plane = ColliderType::PLANE {
+            normal: xyz(0.0, 1.0, 0.0),
+            distance: 0.0,
+        };
+        let wall = MassProperties::from_density(&plane, 3.0);
+        assert_eq!(wall.mass, f32::INFINITY);
+        assert_eq!(wall.inertia, Mat3::ZERO);
+        let segment = ColliderType::SEGMENT {
+            start: xyz(0.0, 0.0, 0.0),
+            end: xyz(3.0, 0.0, 0.0),
+        };
+        assert_eq!(MassProperties::from_density(&segment, 3.0).mass, 0.0);
+
+        // A rod about its center has I = m L^2 / 12, and about its end, m L^2 / 3
+        let rod = MassProperties::from_mass(&segment, 2.0);
+        assert_xyz_close(rod.center_of_mass, xyz(1.5, 0.0, 0.0));
+        assert_mat3_close(
+            rod.inertia,
+            Mat3::from_diagonal(glam::Vec3::new(0.0, 1.5, 1.5)),
+        );
+        assert_mat3_close(
+            rod.inertia_about(xyz(0.0, 0.0, 0.0)),
+            Mat3::from_diagonal(glam::Vec3::new(0.0, 6.0, 6.0)),
+        );
+    }
+
+    #[test]
+    fn test_mass_properties_can_be_overridden() {
+        let collider = ColliderType::AABB {
+            center: xyz(0.0, 0.0, 0.0),
+            half_extents: xyz(1.0, 1.0, 1.0),
+        };
+        let mut object = Object::from_density(Transform::IDENTITY, collider.clone(), 1.0);
+        let properties = MassProperties::from_density(&collider, 1.0)
+            .with_inertia(Mat3::from_diagonal(glam::Vec3::new(1.0, 2.0, 3.0)));
+        object.set_mass_properties(&properties);
+        assert_eq!(object.mass, 8.0);
+        assert_mat3_close(
+            object.inertia_tensor(),
+            Mat3::from_diagonal(glam::Vec3::new(1.0, 2.0, 3.0)),
+        );
+
+        // The override turns with the object
+        object.transform.rotation = Quat::from_rotation_z(std::f32::consts::FRAC_PI_2);
+        assert_mat3_close(
+            object.inertia_tensor(),
+            Mat3::from_diagonal(glam::Vec3::new(2.0, 1.0, 3.0)),
+        );
+
+        // and is what torques work against
+        object.torque = xyz(0.0, 0.0, 6.0);
+        let mut objects = vec![object];
+        integrate_rotation(&mut objects, 0.5);
+        assert!((objects[0].angular_velocity.z - 1.0).abs() < 0.0001);
+
+        // Moving the center of mass away from the position makes it harder to spin about the position
+        let mut object = Object::from_density(Transform::IDENTITY, collider.clone(), 1.0);
+        let centered = object.inertia_tensor();
+        object.set_mass_properties(
+            &MassProperties::from_density(&collider, 1.0).with_center_of_mass(xyz(1.0, 0.0, 0.0)),
+        );
+        let offset = object.inertia_tensor();
+        assert_eq!(offset.x_axis.x, centered.x_axis.x);
+        assert!((offset.y_axis.y - (centered.y_axis.y + 8.0)).abs() < 0.001);
+    }
+
+    #[test]
+    fn test_derived_inertia_is_cached_and_turns_with_object() {
+        let mut object = return_centered_sphere();
+        object.collider = return_cube_hull(xyz(0.0, 0.0, 0.0), 2.0);
+        object.transform.scale = xyz(2.0, 1.0, 1.0);
+        object.transform.rotation = Quat::from_rotation_z(0.3);
+        let derived = object
+            .world_collider()
+            .inertia_tensor(object.mass, object.transform.position);
+        assert_eq!(object.inertia, None);
+        assert_mat3_close(object.inertia_tensor(), derived);
+
+        // The world derives it once, in the object's local space
+        let mut world = World::new(SimConfig::default()).unwrap();
+        world.add_object(object).unwrap();
+        world.step(1.0 / 60.0);
+        let object = &world.objects[0];
+        assert!(object.inertia.is_some());
+        assert_mat3_close(object.inertia_tensor(), derived);
+
+        // Objects made from a density start with it cached
+        let hull = return_cube_hull(xyz(0.0, 0.0, 0.0), 1.0);
+        let object = Object::from_density(Transform::IDENTITY, hull.clone(), 1.0);
+        assert_mat3_close(
+            object.inertia.unwrap(),
+            hull.inertia_tensor(object.mass, xyz(0.0, 0.0, 0.0)),
+        );
+    }
+
+    #[test]
+    fn test_torque_spins_object() {
+        let mut sphere = return_moving_sphere(0.0, 0.0, 2.0, 5.0);
+        // I = 2/5 m r^2 = 8, so this accelerates at 0.5 rad/s^2
+        sphere.torque = xyz(0.0, 0.0, 4.0);
+        let mut objects = vec![sphere];
+        for _ in 0..10 {
+            integrate_rotation(&mut objects, 0.1);
+        }
+        assert!((objects[0].angular_velocity.z - 0.5).abs() < 0.0001);
+        assert_eq!(objects[0].angular_velocity.x, 0.0);
+
+        // Each step turns by the angular velocity after that step, i.e. semi-implicit Euler
+        let expected_angle: f32 = (1..=10).map(|n| 0.05 * n as f32 * 0.1).sum();
+        let (axis, angle) = objects[0].transform.rotation.to_axis_angle();
+        assert!((angle - expected_angle).abs() < 0.0001);
+        assert!((axis.z - 1.0).abs() < 0.0001);
+
+        // Torque is cleared each tick, while the spin continues
+        let config = SimConfig::default();
+        let mut objects = vec![return_centered_sphere()];
+        objects[0].torque = xyz(0.0, 0.0, 1.0);
+        step_objects(&mut objects, &config, 0.1);
+        let spin = objects[0].angular_velocity.z;
+        assert!(spin > 0.0);
+        assert_eq!(objects[0].torque, xyz(0.0, 0.0, 0.0));
+        step_objects(&mut objects, &config, 0.1);
+        assert_eq!(objects[0].angular_velocity.z, spin);
+    }
+
+    #[test]
+    fn test_off_center_contact_spins_objects() {
+        // A ball falling onto one end of a resting plank, in the XY plane
+        let mut plank = return_moving_sphere(0.0, 0.0, 1.0, 4.0);
+        plank.collider = ColliderType::AABB {
+            center: xyz(0.0, 0.0, 0.0),
+            half_extents: xyz(4.0, 0.5, 0.5),
+        };
+        let mut ball = return_moving_sphere(3.0, 0.0, 1.0, 1.0);
+        ball.transform.position.y = 1.4;
+        ball.velocity.y = -2.0;
+
+        let total_linear = |a: &Object, b: &Object| a.velocity * a.mass + b.velocity * b.mass;
+        let energy = |o: &Object| {
+            let omega = glam::Vec3::from(o.angular_velocity);
+            0.5 * o.mass * o.velocity.magnitude().powi(2)
+                + 0.5 * omega.dot(o.inertia_tensor() * omega)
+        };
+        let linear_before = total_linear(&ball, &plank);
+        let energy_before = energy(&ball) + energy(&plank);
+
+        let points = ball
+            .world_collider()
+            .test_collision(&plank.world_collider());
+        assert!(points.has_collision);
+        resolve_contact(&mut ball, &mut plank, &points, &frictionless(1.0));
+
+        // Pushed down at its right end, the plank spins clockwise, i.e. about -Z
+        assert!(plank.angular_velocity.z < 0.0);
+        assert_eq!(ball.angular_velocity, xyz(0.0, 0.0, 0.0));
+        assert!(ball.velocity.y > 0.0);
+        assert_xyz_close(total_linear(&ball, &plank), linear_before);
+        let energy_after = energy(&ball) + energy(&plank);
+        assert!((energy_after - energy_before).abs() < 0.001 * energy_before);
+    }
+
+    fn return_random_bounds(state: &mut u64, count: usize, spread: f32) -> Vec<BoundingBox> {
+        let mut bounds = Vec::new();
+        for _i in 0..count {
+            let center = XYZ {
+                x: spread * next_pseudo_random(state),
+                y: spread * next_pseudo_random(state),
+                z: 0.1 * spread * next_pseudo_random(state),
+            };
+            // Mostly similar sizes, with the occasional much larger object spanning many grid cells
+            let mut half_size = 1.0 + next_pseudo_random(state).abs();
+            if next_pseudo_random(state) > 0.95 {
+                half_size *= 10.0;
+            }
+            let extent = XYZ {
+                x: half_size,
+                y: half_size,
+                z: half_size,
+            };
+            bounds.push(BoundingBox {
+                min: center - extent,
+                max: center + extent,
+            });
+        }
+        return bounds;
+    }
+
+    fn sorted_candidate_pairs(
+        broadphase: &mut dyn Broadphase,
+        bounds: &[BoundingBox],
+    ) -> Vec<(usize, usize)> {
+        let mut pairs = broadphase.candidate_pairs(bounds);
+        pairs.sort_unstable();
+        return pairs;
+    }
+
+    #[test]
+    fn test_broadphases_match_brute_force() {
+        let mut state = 99;
+        let mut bounds = return_random_bounds(&mut state, 500, 50.0);
+        // Include infinite bounds, like those of a plane
+        let infinity = XYZ {
+            x: f32::INFINITY,
+            y: f32::INFINITY,
+            z: f32::INFINITY,
+        };
+        bounds.push(BoundingBox {
+            min: -infinity,
+            max: infinity,
+        });
+        bounds.push(BoundingBox {
+            min: -infinity,
+            max: infinity,
+        });
+
+        let expected = sorted_candidate_pairs(&mut BruteForce, &bounds);
+        // Sanity check that the test is meaningful: some, but not all, pairs should overlap
+        assert!(expected.len() > bounds.len());
+        assert!(expected.len() < bounds.len() * (bounds.len() - 1) / 2);
+
+        for cell_size in [2.0, 4.0, 100.0] {
+            let result = sorted_candidate_pairs(&mut UniformGrid::new(cell_size), &bounds);
+            assert_eq!(result, expected, "cell size {}", cell_size);
+        }
+        let result = sorted_candidate_pairs(&mut SweepAndPrune, &bounds);
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn test_uniform_grid_handles_huge_finite_bounds() {
+        // Boxes spanning far too many cells to bucket should still pair up correctly, and quickly
+        let mut state = 5;
+        let mut bounds = return_random_bounds(&mut state, 200, 50.0);
+        let huge = xyz(1.0e7, 1.0e7, 1.0e7);
+        bounds.push(BoundingBox {
+            min: -huge,
+            max: huge,
+        });
+        bounds.push(BoundingBox {
+            min: xyz(-1.0e7, 20.0, -1.0e7),
+            max: xyz(1.0e7, 30.0, 1.0e7),
+        });
+
+        let expected = sorted_candidate_pairs(&mut BruteForce, &bounds);
+        // The same grid is reused between calls, so it must give the same pairs each time
+        let mut grid = UniformGrid::new(0.5);
+        for _i in 0..3 {
+            assert_eq!(sorted_candidate_pairs(&mut grid, &bounds), expected);
+        }
+    }
+
+    #[test]
+    fn test_broadphases_report_each_pair_once() {
+        // Two large boxes sharing many grid cells, and a third box touching the first
+        let bounds = vec![
+            BoundingBox {
+                min: XYZ {
+                    x: 0.0,
+                    y: 0.0,
+                    z: 0.0,
+                },
+                max: XYZ {
+                    x: 10.0,
+                    y: 10.0,
+                    z: 10.0,
+                },
+            },
+            BoundingBox {
+                min: XYZ {
+                    x: 1.0,
+                    y: 1.0,
+                    z: 1.0,
+                },
+                max: XYZ {
+                    x: 9.0,
+                    y: 9.0,
+                    z: 9.0,
+                },
+            },
+            BoundingBox {
+                min: XYZ {
+                    x: -5.0,
+                    y: 10.0,
+                    z: 0.0,
+                },
+                max: XYZ {
+                    x: 0.0,
+                    y: 15.0,
+                    z: 1.0,
+                },
+            },
+        ];
+        let expected = vec![(0, 1), (0, 2)];
+        for broadphase_type in [
+            BroadphaseType::BruteForce,
+            BroadphaseType::UniformGrid { cell_size: 1.0 },
+            BroadphaseType::SweepAndPrune,
+        ] {
+            let result = sorted_candidate_pairs(broadphase_type.broadphase().as_mut(), &bounds);
+            assert_eq!(result, expected, "{:?}", broadphase_type);
+        }
+    }
+
+    #[test]
+    fn test_config_selects_broadphase() {
+        // All broadphases should give identical simulation results, since they find the same pairs
+        let mut results = Vec::new();
+        for broadphase_type in [
+            BroadphaseType::BruteForce,
+            BroadphaseType::UniformGrid { cell_size: 20.0 },
+            BroadphaseType::SweepAndPrune,
+        ] {
+            let config = SimConfig {
+                broadphase: broadphase_type,
+                ..SimConfig::default()
+            };
+            let radius = config.particle_radius_px;
+            let mut world = World::new(config.clone()).unwrap();
+            for x in 0..20 {
+                let mut sphere = return_moving_sphere(
+                    radius + 15.0 * x as f32,
+                    (x % 3) as f32 - 1.0,
+                    radius,
+                    1.0,
+                );
+                sphere.transform.position.y = 0.5 * config.screen_height;
+                world.add_object(sphere).unwrap();
+            }
+            for _i in 0..20 {
+                world.step(0.05);
+            }
+            results.push(
+                world
+                    .objects
+                    .iter()
+                    .map(|o| o.transform.position)
+                    .collect::<Vec<XYZ>>(),
+            );
+        }
+        assert_eq!(results[0], results[1]);
+        assert_eq!(results[0], results[2]);
+
+        let config = SimConfig {
+            broadphase: BroadphaseType::UniformGrid { cell_size: 0.0 },
+            ..SimConfig::default()
+        };
+        assert_eq!(
+            config.validate(),
+            Err(ConfigError::NonPositiveGridCellSize(0.0))
+        );
+    }
+
+    #[test]
+    fn test_convert_meters_to_pixels() {
+        let result = particle_sim::convert_meters_to_pixels(10.0, 100.0);
+        assert_eq!(result, 1000.0);
+
+        let result = particle_sim::convert_meters_to_pixels(0.001, 0.01);
+        assert!(result - 0.000000000001 <= 0.00001);
+    }
+
+    #[test]
+    fn test_convert_pixels_to_meters() {
+        let result = particle_sim::convert_pixels_to_meters(10.0, 100.0);
+        assert_eq!(result, 10.0 / 100.0);
+
+        let result = particle_sim::convert_pixels_to_meters(100.0, 0.1);
+        assert_eq!(result, 1000.0);
+    }
+
+    #[test]
+    fn test_calculate_particle_acceleration() {
+        let result = particle_sim::calculate_particle_acceleration(2.0, 1.0, 1.0);
+        assert_eq!(result, 1.0);
+
+        let result = particle_sim::calculate_particle_acceleration(1.0, 3.0, 2.0);
+        assert_eq!(result, -1.0);
+    }
+
+    // Maybe worth testing once I have other shapes. Then, I could for example pass a shape, orientation
+    // and function in, which would determine the distance to the ground
+    // #[test]
+    // fn test_particle_touching_ground() {
+    //     let result = particle_sim::particle_touching_ground(1.0,2.0,1.0);
+    //     assert_eq!(result, 1.0);
+    // }
+
+    /// Return a material which bounces with the given restitution, and has no friction
+    fn frictionless(restitution: f32) -> Material {
+        return Material {
+            restitution,
+            static_friction: 0.0,
+            dynamic_friction: 0.0,
+            rolling_resistance: 0.0,
+        };
+    }
+
+    #[test]
+    fn test_calulate_friction() {
+        // This formula may break. The point is that we want any y location at which the particle touches the ground.
+        let config = SimConfig::default();
+        let touching_ground_y_pos = config.screen_height - 1.0;
+        // Friction depends on the total force pressing the particle into the ground, i.e. its weight
+        let weight = config.gravity_ms;
+        let contact = Material {
+            static_friction: 0.2,
+            dynamic_friction: 0.2,
+            ..Material::default()
+        };
+
+        // Objects not in contact should have 0 friction
+        let mut sphere_airborne = return_centered_sphere();
+        sphere_airborne.transform.position.x = 5.0;
+        sphere_airborne.transform.position.y = 5.0;
+        sphere_airborne.transform.position.z = 5.0;
+        sphere_airborne.velocity.x = 5.0;
+        let result = particle_sim::calculate_friction_deceleration(
+            &sphere_airborne,
+            &contact,
+            &arena_extents(&sphere_airborne, &config),
+            &config,
+            1.0,
+        );
+        assert_eq!(result, XYZ::default());
+
+        // Objects in contact should have friction
+        let mut sphere_grounded_1 = return_centered_sphere();
+        sphere_grounded_1.transform.position.y = touching_ground_y_pos;
+        sphere_grounded_1.force.y = weight;
+        sphere_grounded_1.velocity.x = 5.0;
+        let result = particle_sim::calculate_friction_deceleration(
+            &sphere_grounded_1,
+            &contact,
+            &arena_extents(&sphere_grounded_1, &config),
+            &config,
+            1.0,
+        );
+        assert_eq!(result.x, -1.96);
+        assert_eq!(result.y, 0.0);
+
+        // Fast moving objects should have more friction than slow moving objects
+        let mut sphere_slow = return_centered_sphere();
+        sphere_slow.transform.position.y = touching_ground_y_pos;
+        sphere_slow.force.y = weight;
+        sphere_slow.velocity.x = 1.0;
+        let result_slow = particle_sim::calculate_friction_deceleration(
+            &sphere_slow,
+            &contact,
+            &arena_extents(&sphere_slow, &config),
+            &config,
+            1.0,
+        );
+
+        let mut sphere_fast = return_centered_sphere();
+        sphere_fast.transform.position.y = touching_ground_y_pos;
+        sphere_fast.force.y = weight;
+        sphere_fast.velocity.x = 10.0;
+        let result_fast = particle_sim::calculate_friction_deceleration(
+            &sphere_fast,
+            &contact,
+            &arena_extents(&sphere_fast, &config),
+            &config,
+            1.0,
+        );
+        assert!(result_fast.x.abs() > result_slow.x.abs());
+
+        // Objects in contact should have friction regardless of direction
+        let mut sphere_grounded_2 = return_centered_sphere();
+        sphere_grounded_2.transform.position.y = touching_ground_y_pos;
+        sphere_grounded_2.force.y = weight;
+        sphere_grounded_2.velocity.x = -1.0;
+        let result = particle_sim::calculate_friction_deceleration(
+            &sphere_grounded_2,
+            &contact,
+            &arena_extents(&sphere_grounded_2, &config),
+            &config,
+            1.0,
+        );
+        assert_eq!(result.x, 1.0);
+
+        // TODO: test the relationship between friction coefficient magnitude and velocity magnitude.
+        // The idea is to codify a logical relationship between the two units, instead of the current
+        // slightly magical numbers (e.g. why does 0.01 friction coefficient almost instantly stop +5 velocity)?
+        // what exactly is the relationship between the two numbers?
+
+        // Test negative friction. It's not required, or how physics works, but it's fun.
+        let magical = Material {
+            static_friction: -0.2,
+            dynamic_friction: -0.2,
+            ..Material::default()
+        };
+        let mut sphere_magical = return_centered_sphere();
+        sphere_magical.transform.position.y = touching_ground_y_pos;
+        sphere_magical.force.y = weight;
+        sphere_magical.velocity.x = -5.0;
+        let result = particle_sim::calculate_friction_deceleration(
+            &sphere_magical,
+            &magical,
+            &arena_extents(&sphere_magical, &config),
+            &config,
+            1.0,
+        );
+        assert_eq!(result.x, -1.96);
+    }
+
+    #[test]
+    fn test_static_friction_holds_until_overcome() {
+        let config = SimConfig::default();
+        let contact = Material {
+            static_friction: 0.5,
+            dynamic_friction: 0.1,
+            ..Material::default()
+        };
+        let mut sphere = return_centered_sphere();
+        sphere.transform.position.y = config.screen_height - 1.0;
+        sphere.force.y = config.gravity_ms;
+
+        // Slower than static friction can hold, so it stops dead
+        sphere.velocity.x = 4.0;
+        let result = particle_sim::calculate_friction_deceleration(
+            &sphere,
+            &contact,
+            &arena_extents(&sphere, &config),
+            &config,
+            1.0,
+        );
+        assert_eq!(result.x, -4.0);
+
+        // Once it's sliding faster than that, only dynamic friction slows it
+        sphere.velocity.x = 6.0;
+        let result = particle_sim::calculate_friction_deceleration(
+            &sphere,
+            &contact,
+            &arena_extents(&sphere, &config),
+            &config,
+            1.0,
+        );
+        assert!((result.x + 0.98).abs() < 0.0001);
+
+        // Pushing down on the particle presses it harder into the floor, so that it sticks again
+        sphere.force.y += 9.8;
+        let result = particle_sim::calculate_friction_deceleration(
+            &sphere,
+            &contact,
+            &arena_extents(&sphere, &config),
+            &config,
+            1.0,
+        );
+        assert_eq!(result.x, -6.0);
+    }
+
+    #[test]
+    fn test_friction_stopping_distance_independent_of_tick_rate() {
+        let config = SimConfig::default();
+        let contact = Material {
+            static_friction: 0.2,
+            dynamic_friction: 0.2,
+            ..Material::default()
+        };
+        let stopping_distance = |ticks_per_second: f32| {
+            let dt = 1.0 / ticks_per_second;
+            let mut sphere = return_centered_sphere();
+            sphere.transform.position.y = config.screen_height - 1.0;
+            sphere.force.y = config.gravity_ms;
+            sphere.velocity.x = 5.0;
+            let mut distance = 0.0;
+            while sphere.velocity.x > 0.0 {
+                sphere.velocity += particle_sim::calculate_friction_deceleration(
+                    &sphere,
+                    &contact,
+                    &arena_extents(&sphere, &config),
+                    &config,
+                    dt,
+                );
+                distance += sphere.velocity.x * dt;
+            }
+            return distance;
+        };
+        // Sliding friction decelerates it at mu g, so it stops after v^2 / (2 mu g)
+        let expected = 5.0 * 5.0 / (2.0 * 0.2 * config.gravity_ms);
+        let at_60_hz = stopping_distance(60.0);
+        let at_240_hz = stopping_distance(240.0);
+        assert!((at_60_hz - expected).abs() < 0.05 * expected);
+        assert!((at_240_hz - expected).abs() < 0.05 * expected);
+        assert!((at_60_hz - at_240_hz).abs() < 0.05 * expected);
+    }
+
+    #[test]
+    fn test_friction_on_every_arena_edge() {
+        let config = SimConfig::default();
+        let contact = Material {
+            static_friction: 0.1,
+            dynamic_friction: 0.1,
+            ..Material::default()
+        };
+
+        // A particle pushed into the right wall slides down it against friction
+        let mut sphere = return_centered_sphere();
+        sphere.transform.position.x = config.screen_width - 1.0;
+        sphere.velocity.y = 5.0;
+        sphere.force = xyz(20.0, config.gravity_ms, 0.0);
+        let result = particle_sim::calculate_friction_deceleration(
+            &sphere,
+            &contact,
+            &arena_extents(&sphere, &config),
+            &config,
+            1.0,
+        );
+        assert_eq!(result.x, 0.0);
+        assert!((result.y + 2.0).abs() < 0.0001);
+
+        // But sliding along the wall without being pushed into it has no friction
+        sphere.force.x = 0.0;
+        let result = particle_sim::calculate_friction_deceleration(
+            &sphere,
+            &contact,
+            &arena_extents(&sphere, &config),
+            &config,
+            1.0,
+        );
+        assert_eq!(result, XYZ::default());
+
+        // Gravity pulls particles away from the ceiling, so it takes a strong push for it to have friction
+        let mut sphere = return_centered_sphere();
+        sphere.transform.position.y = 1.0;
+        sphere.velocity.x = 5.0;
+        sphere.force.y = config.gravity_ms;
+        let result = particle_sim::calculate_friction_deceleration(
+            &sphere,
+            &contact,
+            &arena_extents(&sphere, &config),
+            &config,
+            1.0,
+        );
+        assert_eq!(result, XYZ::default());
+        sphere.force.y = -20.0;
+        let result = particle_sim::calculate_friction_deceleration(
+            &sphere,
+            &contact,
+            &arena_extents(&sphere, &config),
+            &config,
+            1.0,
+        );
+        assert!((result.x + 2.0).abs() < 0.0001);
+
+        // Friction also opposes sliding along the Z axis
+        let mut sphere = return_centered_sphere();
+        sphere.transform.position.y = config.screen_height - 1.0;
+        sphere.force.y = config.gravity_ms;
+        sphere.velocity = xyz(30.0, 0.0, 40.0);
+        let result = particle_sim::calculate_friction_deceleration(
+            &sphere,
+            &contact,
+            &arena_extents(&sphere, &config),
+            &config,
+            1.0,
+        );
+        assert_xyz_close(result, xyz(-0.588, 0.0, -0.784));
+    }
+
+    #[test]
+    fn test_bounce_applies_friction() {
+        let config = SimConfig::default();
+        let bounce = |velocity: XYZ, contact: &Material| {
+            let mut sphere = return_centered_sphere();
+            sphere.velocity = velocity;
+            return particle_sim::calculate_bounce(
+                &sphere,
+                contact,
+                &arena_extents(&sphere, &config),
+                1.0,
+                &config,
+            )
+            .unwrap();
+        };
+        // Fast enough to hit the floor once within a second
+        let falling = xyz(3.0, 40.0, 0.0);
+        let frictionless_bounce = bounce(falling, &frictionless(0.5));
+        assert_eq!(frictionless_bounce.velocity.x, 3.0);
+        assert_eq!(frictionless_bounce.velocity.y, -20.0);
+
+        // Hitting the floor at 40 and bouncing off at 20 gives a push of 60, which slows the
+        // particle along the floor
+        let sliding = Material {
+            static_friction: 0.01,
+            dynamic_friction: 0.01,
+            ..frictionless(0.5)
+        };
+        let result = bounce(falling, &sliding);
+        assert!((result.velocity.x - 2.4).abs() < 0.0001);
+        assert_eq!(result.velocity.y, -20.0);
+        assert_eq!(result.position, frictionless_bounce.position);
+
+        // With enough static friction, the particle stops sliding altogether
+        let sticky = Material {
+            static_friction: 0.1,
+            ..sliding
+        };
+        assert_eq!(bounce(falling, &sticky).velocity.x, 0.0);
+
+        // The same happens against the walls
+        let result = bounce(xyz(60.0, 3.0, 0.0), &sliding);
+        assert!(result.velocity.x < 0.0);
+        assert!(result.velocity.y < 3.0);
+    }
+
+    #[test]
+    fn test_contact_friction_makes_sphere_roll() {
+        let hit_floor = |contact: &Material| {
+            let mut floor = return_centered_sphere();
+            floor.mass = f32::INFINITY;
+            floor.transform.position = XYZ::default();
+            floor.collider = ColliderType::PLANE {
+                normal: xyz(0.0, 1.0, 0.0),
+                distance: 0.0,
+            };
+            // A ball which barely touches the floor, sliding along it as it lands
+            let mut ball = return_moving_sphere(0.0, 2.0, 1.0, 1.0);
+            ball.transform.position.y = 0.999;
+            ball.velocity.y = -4.0;
+            let points = ball
+                .world_collider()
+                .test_collision(&floor.world_collider());
+            resolve_contact(&mut ball, &mut floor, &points, contact);
+            return ball;
+        };
+
+        // With enough static friction, the ball's contact with the floor stops sliding, so it
+        // rolls. A sliding sphere which starts rolling keeps 5/7 of its speed.
+        let grippy = Material {
+            restitution: 0.0,
+            static_friction: 0.5,
+            dynamic_friction: 0.5,
+            rolling_resistance: 0.0,
+        };
+        let ball = hit_floor(&grippy);
+        assert!((ball.velocity.x - 10.0 / 7.0).abs() < 0.001);
+        assert!((ball.angular_velocity.z + ball.velocity.x).abs() < 0.001);
+        assert!(ball.velocity.y.abs() < 0.0001);
+
+        // Otherwise it slides, losing the dynamic coefficient times the push from the floor
+        let slippery = Material {
+            static_friction: 0.05,
+            dynamic_friction: 0.05,
+            ..grippy
+        };
+        let ball = hit_floor(&slippery);
+        assert!((ball.velocity.x - 1.8).abs() < 0.0001);
+        assert!(ball.angular_velocity.z < 0.0);
+
+        // And with no friction, it neither slows nor spins
+        let ball = hit_floor(&frictionless(0.0));
+        assert_eq!(ball.velocity.x, 2.0);
+        assert_eq!(ball.angular_velocity, XYZ::default());
+    }
+
+    #[test]
+    fn test_rolling_resistance_slows_rolling_sphere() {
+        let roll = |contact: &Material| {
+            let mut floor = return_centered_sphere();
+            floor.mass = f32::INFINITY;
+            floor.transform.position = XYZ::default();
+            floor.collider = ColliderType::PLANE {
+                normal: xyz(0.0, 1.0, 0.0),
+                distance: 0.0,
+            };
+            // A ball rolling along the floor without sliding
+            let mut ball = return_moving_sphere(0.0, 2.0, 1.0, 1.0);
+            ball.transform.position.y = 0.999;
+            ball.angular_velocity.z = -2.0;
+            let dt = 1.0 / 60.0;
+            for _ in 0..60 {
+                // Gravity presses it into the floor each tick
+                ball.velocity.y -= 9.8 * dt;
+                let points = ball
+                    .world_collider()
+                    .test_collision(&floor.world_collider());
+                resolve_contact(&mut ball, &mut floor, &points, contact);
+            }
+            return ball;
+        };
+        let grippy = Material {
+            restitution: 0.0,
+            static_friction: 0.5,
+            dynamic_friction: 0.5,
+            rolling_resistance: 0.0,
+        };
+
+        // Without rolling resistance, it rolls on forever
+        let ball = roll(&grippy);
+        assert!((ball.velocity.x - 2.0).abs() < 0.001);
+
+        // A resisting torque of c m g r slows a rolling sphere by c g / (1 + 2/5)
+        let resistant = Material {
+            rolling_resistance: 0.1,
+            ..grippy
+        };
+        let ball = roll(&resistant);
+        let expected = 2.0 - 0.1 * 9.8 / 1.4;
+        assert!((ball.velocity.x - expected).abs() < 0.05);
+        // It still rolls, rather than sliding
+        assert!((ball.angular_velocity.z + ball.velocity.x).abs() < 0.05);
+    }
+
+    #[test]
+    fn test_arena_rolling_resistance() {
+        let config = SimConfig::default();
+        let contact = Material {
+            rolling_resistance: 0.1,
+            ..Material::default()
+        };
+        let mut sphere = return_centered_sphere();
+        sphere.angular_velocity = xyz(0.0, 3.0, -5.0);
+        sphere.force.y = config.gravity_ms;
+
+        // In the air, nothing resists its spin
+        let result = particle_sim::calculate_rolling_resistance(
+            &sphere,
+            &contact,
+            &arena_extents(&sphere, &config),
+            &config,
+            0.1,
+        );
+        assert_eq!(result, XYZ::default());
+
+        // On the floor, its rolling slows, but not its spin in place
+        sphere.transform.position.y = config.screen_height - 1.0;
+        let result = particle_sim::calculate_rolling_resistance(
+            &sphere,
+            &contact,
+            &arena_extents(&sphere, &config),
+            &config,
+            0.1,
+        );
+        assert!(result.z > 0.0);
+        assert_eq!(result.x, 0.0);
+        assert_eq!(result.y, 0.0);
+
+        // At most, it stops rolling altogether
+        let result = particle_sim::calculate_rolling_resistance(
+            &sphere,
+            &contact,
+            &arena_extents(&sphere, &config),
+            &config,
+            1e6,
+        );
+        assert_xyz_close(result, xyz(0.0, 0.0, 5.0));
+    }
+
+    #[test]
+    fn test_bounce_y_basic() {
+        // Test bounces in both Y directions
+        let config = SimConfig::default();
+
+        // Choose a large velocity, to stress the calculation logic, and have the object
+        // bouncing within 1 second
+        let initial_y_velocity = 1.0
+            + convert_pixels_to_meters(-0.5 * config.screen_height - 1.0, config.pixels_per_meter);
+
+        // We want the particle not already colliding on spawn, and as big as the config's particles,
+        // so that it reaches the arena's edges within that second
+        let mut sphere = return_centered_sphere();
+        if let ColliderType::SPHERE { ref mut radius, .. } = sphere.collider {
+            *radius = config.particle_radius_px;
+        }
+        sphere.transform.position.x = 0.5 * config.screen_width;
+        sphere.transform.position.y = 0.5 * config.screen_height;
+        sphere.velocity.y = initial_y_velocity;
+        let result = particle_sim::calculate_bounce(
+            &sphere,
+            &frictionless(0.9),
+            &arena_extents(&sphere, &config),
+            1.0,
+            &config,
+        )
+        .unwrap();
+
+        // There should be no errors or nans with the parameters we're using here
+        assert!(!result.position.y.is_nan());
+        assert!(!result.velocity.y.is_nan());
+
+        // The particle should remain within Y bounds
+        assert!(result.position.y >= 0.0);
+        assert!(result.position.y <= config.screen_height + 0.0001);
+
+        // The particle's X location should be unchanged
+        assert_eq!(result.position.x, 0.5 * config.screen_width);
 
         // The particle's Y velocity should be reversed, and reduced in magnitude
         assert!(result.velocity.y >= 0.0);
         assert!(result.velocity.y.abs() < initial_y_velocity.abs());
 
-        // Test the other direction
-        let mut sphere_2 = return_centered_sphere();
-        sphere_2.transform.position.x = 0.5 * SCREEN_WIDTH;
-        sphere_2.transform.position.y = 0.5 * SCREEN_HEIGHT;
-        sphere_2.velocity.y = -1.0 * initial_y_velocity;
-        let result = particle_sim::calculate_bounce(&sphere, 0.9, 1.0).unwrap();
+        // Test the other direction
+        let mut sphere_2 = return_centered_sphere();
+        sphere_2.transform.position.x = 0.5 * config.screen_width;
+        sphere_2.transform.position.y = 0.5 * config.screen_height;
+        sphere_2.velocity.y = -initial_y_velocity;
+        let result = particle_sim::calculate_bounce(
+            &sphere,
+            &frictionless(0.9),
+            &arena_extents(&sphere, &config),
+            1.0,
+            &config,
+        )
+        .unwrap();
+
+        assert!(!result.position.y.is_nan());
+        assert!(!result.velocity.y.is_nan());
+        assert!(result.position.y >= 0.0);
+        assert!(result.position.y <= config.screen_height + 0.0001);
+        assert_eq!(result.position.x, 0.5 * config.screen_width);
+        assert!(result.velocity.y >= 0.0);
+        assert!(result.velocity.y.abs() > -initial_y_velocity.abs());
+    }
+
+    #[test]
+    fn test_bounce_x_basic() {
+        // Test bounces in both x directions
+        let config = SimConfig::default();
+        let initial_x_velocity =
+            convert_pixels_to_meters(-0.5 * config.screen_width - 1.0, config.pixels_per_meter);
+        let initial_position_y = 0.5 * config.screen_width;
+        let mut sphere_1 = return_centered_sphere();
+
+        // We want the particle not already colliding on spawn
+        // We want the ball to hit the ground within 1 tick
+        sphere_1.transform.position.y = initial_position_y;
+        sphere_1.velocity.x = initial_x_velocity;
+        let result = particle_sim::calculate_bounce(
+            &sphere_1,
+            &frictionless(0.9),
+            &arena_extents(&sphere_1, &config),
+            1.0,
+            &config,
+        )
+        .unwrap();
+
+        // There should be no errors or nans with the parameters we're using here
+        assert!(!result.position.x.is_nan());
+        assert!(!result.velocity.x.is_nan());
+
+        // The particle should remain within x bounds
+        assert!(result.position.x >= 0.0);
+        assert!(result.position.x <= config.screen_width + 0.1);
+
+        // The function shouldn't affect the particle's y position
+        assert_eq!(result.position.y, initial_position_y);
+
+        // The particle's x velocity should be reversed, and reduced in magnitude
+        assert!(result.velocity.x >= 0.0);
+        assert!(result.velocity.x.abs() < initial_x_velocity.abs());
+    }
+
+    #[test]
+    fn test_bounce_coefficient_effect() {
+        // Test that the bounce coefficient has the expected influence on particle position and velocity post-bounce
+        let config = SimConfig::default();
+        let initial_x_velocity =
+            convert_pixels_to_meters(0.5 * config.screen_width + 1.0, config.pixels_per_meter);
+        let mut sphere_1 = return_centered_sphere();
+        sphere_1.velocity.x = initial_x_velocity;
+        let bounce_coefficient = 0.5;
+        let result = particle_sim::calculate_bounce(
+            &sphere_1,
+            &frictionless(bounce_coefficient),
+            &arena_extents(&sphere_1, &config),
+            1.0,
+            &config,
+        )
+        .unwrap();
+
+        // Check we haven't errored or returned nan
+        assert!(!result.position.x.is_nan());
+        assert!(!result.velocity.x.is_nan());
+
+        // Check that we haven't gone off-screen
+        assert!(result.position.x >= 0.0);
+        assert!(result.position.x <= config.screen_width + 0.1);
+
+        // Check that velocity has been reversed and reduced
+        assert!(result.velocity.x <= 0.0);
+        println!("{}, {}", result.velocity.x, initial_x_velocity);
+        assert!(result.velocity.x < initial_x_velocity);
+
+        // Check that velocity is a fraction of its initial value, as specified by the bounce coefficient
+        // This reflects the fact that our bounce coefficient is supposed to be a percentage bounciness, expressed as a value
+        // between [0, 1), where the 0 is inclusive, but the 1 is not.
+        assert_eq!(
+            result.velocity.x.abs(),
+            initial_x_velocity * bounce_coefficient
+        );
+    }
+
+    #[test]
+    fn test_bounce_given_invalid_particle_position() {
+        // If given a particle position that falls outside of the arena, then the function should return
+        // an error, to let the caller handle it
+        let config = SimConfig::default();
+        let mut sphere_1 = return_centered_sphere();
+        sphere_1.transform.position.x = config.screen_width + 1.0;
+        sphere_1.transform.position.y = config.screen_height + 1.0;
+        sphere_1.velocity.y = -5.0;
+        let result = particle_sim::calculate_bounce(
+            &sphere_1,
+            &frictionless(1.0),
+            &arena_extents(&sphere_1, &config),
+            0.99,
+            &config,
+        );
+        // TODO: change this to instead be a check for OutOfBoundsError
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_bounce_no_infinite_bouncing() {
+        // Check that bounces eventually stop
+        let config = SimConfig::default();
+        let mut sphere_1 = return_centered_sphere();
+        sphere_1.velocity.y = -1.0;
+        let result = particle_sim::calculate_bounce(
+            &sphere_1,
+            &frictionless(0.99),
+            &arena_extents(&sphere_1, &config),
+            200.0,
+            &config,
+        )
+        .unwrap();
+        assert_eq!(result.velocity.y.abs().floor(), 0.0);
+
+        // The bounced object should remain within bounds, on its bouncing axis
+        assert!(result.position.y >= 0.0);
+        assert!(result.position.y <= config.screen_height);
+    }
+
+    #[test]
+    fn test_bounce_terminates_with_object_remaining_in_bounds() {
+        // TODO: rename function
+        // Check that a bounced object remains above the arena floor
+        let config = SimConfig::default();
+        let mut sphere_1 = return_centered_sphere();
+        sphere_1.velocity.y = -1.0;
+        let result = particle_sim::calculate_bounce(
+            &sphere_1,
+            &frictionless(0.01),
+            &arena_extents(&sphere_1, &config),
+            200.0,
+            &config,
+        )
+        .unwrap();
+        assert_eq!(result.velocity.y.abs().floor(), 0.0);
+        assert!(result.position.y >= 0.0);
+    }
+
+    #[test]
+    fn test_bounce_at_arena_edge() {
+        // Check that bouncing at the very edge of the arena does not bounce it out of bounds
+        let config = SimConfig::default();
+        let mut sphere_1 = return_centered_sphere();
+
+        // We want the particle ever so slightly in bounds
+
+        // ColliderType is an enum, and Rust enums don't allow direct field access like structs do
+        // so we can't do sphere_1.collider.radius = 1.0;
+        if let ColliderType::SPHERE { ref mut radius, .. } = sphere_1.collider {
+            *radius = 1.0;
+        }
+        println!("{}", sphere_1.collider);
+        sphere_1.transform.position.x = 1.0001;
+        sphere_1.transform.position.y = 1.0001;
+        sphere_1.transform.position.z = 1.0001;
+
+        // We want a negligible velocity, which is significant enough to affect results (in
+        // this case greater than the distance to the arena edge), but tiny enough that a lazy
+        // programmer may be tempted not to calculate it
+        sphere_1.velocity.y = -0.0005;
+
+        // Check that the resulting bounce does not move the particle out of bounds
+        let result = particle_sim::calculate_bounce(
+            &sphere_1,
+            &frictionless(0.9),
+            &arena_extents(&sphere_1, &config),
+            1.0,
+            &config,
+        )
+        .unwrap();
+        println!("Resulting Y position {}", result.position.y);
+        assert!(result.position.y >= 1.0);
+
+        // Check the same for the X axis and in the opposite direction, with a particle as big as the
+        // config's particles
+        let initial_position_x = config.particle_radius_px + 0.0001;
+        let mut sphere_2 = return_centered_sphere();
+        sphere_2.transform.position.x = initial_position_x;
+        sphere_2.velocity.x = -0.0005;
+        if let ColliderType::SPHERE { ref mut radius, .. } = sphere_2.collider {
+            *radius = config.particle_radius_px;
+        }
+        let result = particle_sim::calculate_bounce(
+            &sphere_2,
+            &frictionless(0.9),
+            &arena_extents(&sphere_2, &config),
+            1.0,
+            &config,
+        )
+        .unwrap();
+        println!("Resulting X position {}", result.position.x);
+        assert!(result.position.x >= config.particle_radius_px);
+    }
+
+    #[test]
+    fn test_bounce_extreme_velocity_particle_remains_within_bounds() {
+        // TODO: add test to check the particle's final velocity after bouncing
+        // Check that high velocities update a particle's position and also don't push it out of bounds
+        let config = SimConfig::default();
+        let mut sphere_1 = return_centered_sphere();
+        sphere_1.velocity.x = 5000.0;
+        sphere_1.velocity.y = -5001.0;
+
+        let result = particle_sim::calculate_bounce(
+            &sphere_1,
+            &frictionless(0.20),
+            &arena_extents(&sphere_1, &config),
+            1.0,
+            &config,
+        )
+        .unwrap();
+        assert!(result.position.x >= 0.0);
+        assert!(result.position.x <= config.screen_width + 0.1);
+        assert!(result.position.y >= 0.0);
+        assert!(result.position.y <= config.screen_height + 0.1);
+
+        assert!(result.velocity.y.abs() <= 5001.0);
+        assert!(result.velocity.x.abs() <= 5000.0);
+    }
+
+    const ALL_INTEGRATORS: [IntegratorType; 4] = [
+        IntegratorType::SemiImplicitEuler,
+        IntegratorType::VelocityVerlet,
+        IntegratorType::RK4,
+        IntegratorType::Boris,
+    ];
+
+    #[test]
+    fn test_integrators_match_analytic_projectile() {
+        // Under constant acceleration, x(t) = x0 + v0*t + 0.5*a*t^2
+        let gravity = XYZ {
+            x: 0.0,
+            y: -9.8,
+            z: 0.0,
+        };
+        let dt = 0.01;
+        let ticks = 100;
+        let t = dt * ticks as f32;
+        for integrator_type in ALL_INTEGRATORS {
+            let mut projectile = return_centered_sphere();
+            projectile.transform.position = XYZ {
+                x: 0.0,
+                y: 0.0,
+                z: 0.0,
+            };
+            projectile.velocity = XYZ {
+                x: 10.0,
+                y: 20.0,
+                z: 5.0,
+            };
+            let mut objects = vec![projectile];
+            let mut accelerations = |objects: &[Object]| vec![gravity; objects.len()];
+            for _i in 0..ticks {
+                integrator_type
+                    .integrator()
+                    .integrate(&mut objects, dt, &mut accelerations);
+            }
+
+            let expected_y = 20.0 * t + 0.5 * gravity.y * t * t;
+            let result = &objects[0];
+            assert!((result.transform.position.x - 10.0 * t).abs() < 0.001);
+            assert!((result.transform.position.z - 5.0 * t).abs() < 0.001);
+            assert!((result.velocity.y - (20.0 + gravity.y * t)).abs() < 0.001);
+            let position_error = (result.transform.position.y - expected_y).abs();
+            match integrator_type {
+                // First order, so the error is proportional to the time step: 0.5*g*dt*t. Boris is
+                // semi-implicit Euler outside of magnetic fields.
+                IntegratorType::SemiImplicitEuler | IntegratorType::Boris => {
+                    assert!(position_error < 0.06, "{}", position_error)
+                }
+                // These are exact for constant accelerations
+                _ => assert!(position_error < 0.001, "{}", position_error),
+            }
+        }
+    }
+
+    /// Acceleration of a mass on a spring with stiffness / mass = omega^2, anchored at the origin
+    fn harmonic_oscillator_accelerations(objects: &[Object], omega: f32) -> Vec<XYZ> {
+        return objects
+            .iter()
+            .map(|p| p.transform.position * (-omega * omega))
+            .collect();
+    }
+
+    #[test]
+    fn test_integrators_match_analytic_harmonic_oscillator() {
+        // Starting at rest, x(t) = x0 * cos(omega * t)
+        let omega = 2.0;
+        let dt = 0.01;
+        let ticks = 1000;
+        let t = dt * ticks as f32;
+        for integrator_type in ALL_INTEGRATORS {
+            let mut spring = return_centered_sphere();
+            spring.transform.position = XYZ {
+                x: 1.0,
+                y: 0.0,
+                z: 0.0,
+            };
+            let mut objects = vec![spring];
+            let mut accelerations =
+                |objects: &[Object]| harmonic_oscillator_accelerations(objects, omega);
+            for _i in 0..ticks {
+                integrator_type
+                    .integrator()
+                    .integrate(&mut objects, dt, &mut accelerations);
+            }
+
+            let expected_x = f32::cos(omega * t);
+            let position_error = (objects[0].transform.position.x - expected_x).abs();
+            let tolerance = match integrator_type {
+                IntegratorType::SemiImplicitEuler | IntegratorType::Boris => 0.05,
+                IntegratorType::VelocityVerlet => 0.01,
+                IntegratorType::RK4 => 0.001,
+            };
+            assert!(
+                position_error < tolerance,
+                "{:?} error {}",
+                integrator_type,
+                position_error
+            );
+        }
+    }
+
+    #[test]
+    fn test_integrators_do_not_gain_energy_in_harmonic_oscillator() {
+        // Over many periods, explicit Euler would multiply the energy by (1 + omega^2 dt^2) each tick,
+        // i.e. roughly 50 times here
+        let omega = 2.0;
+        let dt = 0.01;
+        let ticks = 10000;
+        let energy = |p: &Object| {
+            let x = p.transform.position.x;
+            let v = p.velocity.x;
+            return 0.5 * v * v + 0.5 * omega * omega * x * x;
+        };
+        for integrator_type in ALL_INTEGRATORS {
+            let mut spring = return_centered_sphere();
+            spring.transform.position = XYZ {
+                x: 1.0,
+                y: 0.0,
+                z: 0.0,
+            };
+            let initial_energy = energy(&spring);
+            let mut objects = vec![spring];
+            let mut accelerations =
+                |objects: &[Object]| harmonic_oscillator_accelerations(objects, omega);
+            let mut max_energy = initial_energy;
+            for _i in 0..ticks {
+                integrator_type
+                    .integrator()
+                    .integrate(&mut objects, dt, &mut accelerations);
+                max_energy = f32::max(max_energy, energy(&objects[0]));
+            }
+            // Semi-implicit Euler's energy oscillates by roughly omega * dt around the true value,
+            // but doesn't drift
+            assert!(
+                max_energy < initial_energy * 1.02,
+                "{:?} max energy {}",
+                integrator_type,
+                max_energy
+            );
+            assert!(energy(&objects[0]) > initial_energy * 0.98);
+        }
+    }
+
+    #[test]
+    fn test_world_uses_configured_integrator() {
+        // Different integrators should give different results for the same world
+        let mut results = Vec::new();
+        for integrator_type in ALL_INTEGRATORS {
+            let config = SimConfig {
+                integrator: integrator_type,
+                ..SimConfig::default()
+            };
+            let mut world = World::new(config).unwrap();
+            world.add_object(return_centered_sphere()).unwrap();
+            world.step(0.1);
+            results.push(world.objects[0].transform.position.y);
+        }
+        assert_ne!(results[0], results[1]);
+        // Both of these are exact for constant gravity
+        assert!((results[1] - results[2]).abs() < 0.0001);
+    }
+
+    /*
+    Integration tests below
+    */
+
+    #[test]
+    fn test_world_step_basic() {
+        // Test that several simulation ticks do not move a particle out of bounds
+        let config = SimConfig::default();
+        let ticks = 10;
+        let seconds_elapsed = 1.0;
+
+        let mut sphere_1 = return_centered_sphere();
+        sphere_1.velocity.y = 50.0;
+
+        let mut sphere_2 = return_centered_sphere();
+        sphere_2.transform.position.x = 0.25 * config.screen_width;
+        sphere_2.transform.position.y = 0.25 * config.screen_height;
+        sphere_2.velocity.y = -50.0;
+
+        let mut particles: Vec<Object> = vec![sphere_1, sphere_2];
+
+        // Check that the resulting bounce does not move the particle out of bounds
+        for _i in 0..ticks {
+            step_objects(&mut particles, &config, seconds_elapsed);
+        }
+        let result_1 = particles.first().unwrap();
+        let result_2 = particles.get(1).unwrap();
+
+        // Check we haven't errored or returned nan
+        assert!(!result_1.transform.position.x.is_nan());
+        assert!(!result_1.velocity.x.is_nan());
+        assert!(!result_2.transform.position.x.is_nan());
+        assert!(!result_2.velocity.x.is_nan());
+
+        assert!(!result_1.transform.position.y.is_nan());
+        assert!(!result_1.velocity.y.is_nan());
+        assert!(!result_2.transform.position.y.is_nan());
+        assert!(!result_2.velocity.y.is_nan());
+
+        // Check that we haven't gone off-screen
+        assert!(result_1.transform.position.x >= 0.0);
+        assert!(result_1.transform.position.x <= config.screen_width + 0.1);
+        assert!(result_2.transform.position.x >= 0.0);
+        assert!(result_2.transform.position.x <= config.screen_width + 0.1);
+
+        assert!(result_1.transform.position.y >= 0.0);
+        assert!(result_1.transform.position.y <= config.screen_height + 0.1);
+        assert!(result_2.transform.position.y >= 0.0);
+        assert!(result_2.transform.position.y <= config.screen_height + 0.1);
+    }
+
+    #[test]
+    fn test_world_rejects_invalid_config() {
+        let config = SimConfig {
+            pixels_per_meter: 0.0,
+            ..SimConfig::default()
+        };
+        assert!(World::new(config).is_err());
+    }
+
+    #[test]
+    fn test_world_rejects_out_of_bounds_object() {
+        let config = SimConfig::default();
+        let mut world = World::new(config.clone()).unwrap();
+        let mut sphere = return_centered_sphere();
+        sphere.transform.position.x = config.screen_width + 1.0;
+        assert!(world.add_object(sphere).is_err());
+        assert!(world.objects.is_empty());
+    }
+
+    /// Return each object's force from a single force generator
+    fn generated_forces(generator: &mut dyn ForceGenerator, objects: &[Object]) -> Vec<XYZ> {
+        let mut forces = vec![XYZ::default(); objects.len()];
+        generator.accumulate(objects, &mut forces);
+        return forces;
+    }
+
+    #[test]
+    fn test_gravity_generator() {
+        let config = SimConfig::default();
+        let mut heavy = return_centered_sphere();
+        heavy.mass = 3.0;
+        let mut wall = return_centered_sphere();
+        wall.mass = f32::INFINITY;
+        let objects = vec![return_centered_sphere(), heavy, wall];
+
+        let forces = generated_forces(&mut Gravity::from_config(&config), &objects);
+        assert_eq!(forces[0], xyz(0.0, config.gravity_ms, 0.0));
+        assert_eq!(forces[1], xyz(0.0, 3.0 * config.gravity_ms, 0.0));
+        // Immovable objects aren't pushed at all
+        assert_eq!(forces[2], XYZ::default());
+
+        // Gravity can pull in any direction, e.g. sideways in a world without downward gravity
+        let mut world = World::new(config).unwrap();
+        world.force_generators.clear();
+        world.add_force_generator(Box::new(Gravity {
+            acceleration: xyz(2.0, 0.0, 0.0),
+        }));
+        world.add_object(return_centered_sphere()).unwrap();
+        world.add_object(objects[2].clone()).unwrap();
+        world.step(0.5);
+        assert_eq!(world.objects[0].velocity, xyz(1.0, 0.0, 0.0));
+        assert_eq!(world.objects[1].velocity, XYZ::default());
+    }
+
+    #[test]
+    fn test_drag_generators() {
+        let mut sphere = return_centered_sphere();
+        sphere.velocity = xyz(3.0, 4.0, 0.0);
+        let objects = vec![sphere];
+
+        let forces = generated_forces(&mut LinearDrag { coefficient: 0.5 }, &objects);
+        assert_xyz_close(forces[0], xyz(-1.5, -2.0, 0.0));
+        // Quadratic drag is 5 times stronger at 5 times the speed
+        let forces = generated_forces(&mut QuadraticDrag { coefficient: 0.5 }, &objects);
+        assert_xyz_close(forces[0], xyz(-7.5, -10.0, 0.0));
+    }
+
+    #[test]
+    fn test_cross_sectional_areas() {
+        let pi = std::f32::consts::PI;
+        let forward = xyz(1.0, 0.0, 0.0);
+        let diagonal = xyz(1.0, 1.0, 1.0);
+        let sphere = ColliderType::SPHERE {
+            center: xyz(0.0, 0.0, 0.0),
+            radius: 2.0,
+        };
+        assert!((sphere.cross_sectional_area(diagonal) - 4.0 * pi).abs() < 0.0001);
+
+        // A 2x4x6 box, face on and corner on
+        let cuboid = ColliderType::AABB {
+            center: xyz(0.0, 0.0, 0.0),
+            half_extents: xyz(1.0, 2.0, 3.0),
+        };
+        assert!((cuboid.cross_sectional_area(forward) - 24.0).abs() < 0.0001);
+        let corner_on = (24.0 + 12.0 + 8.0) / 3.0f32.sqrt();
+        assert!((cuboid.cross_sectional_area(diagonal) - corner_on).abs() < 0.0001);
+        // A hull with the same corners casts the same shadow
+        let mut vertices = Vec::new();
+        for signs in 0..8 {
+            let sign = |bit: u32| if signs & (1 << bit) == 0 { -1.0 } else { 1.0 };
+            vertices.push(xyz(sign(0), 2.0 * sign(1), 3.0 * sign(2)));
+        }
+        let hull = ColliderType::HULL { vertices };
+        assert!((hull.cross_sectional_area(forward) - 24.0).abs() < 0.001);
+        assert!((hull.cross_sectional_area(diagonal) - corner_on).abs() < 0.001);
+
+        // A capsule end on is a circle, and side on, a rectangle with round ends
+        let capsule = ColliderType::CAPSULE {
+            start: xyz(0.0, 0.0, 0.0),
+            end: xyz(4.0, 0.0, 0.0),
+            radius: 1.0,
+        };
+        assert!((capsule.cross_sectional_area(forward) - pi).abs() < 0.0001);
+        assert!((capsule.cross_sectional_area(xyz(0.0, 1.0, 0.0)) - (8.0 + pi)).abs() < 0.0001);
+
+        // A flat plate has no cross-section edge on
+        let plate = ColliderType::POLYGON {
+            vertices: vec![
+                xyz(0.0, 0.0, 0.0),
+                xyz(2.0, 0.0, 0.0),
+                xyz(2.0, 2.0, 0.0),
+                xyz(0.0, 2.0, 0.0),
+            ],
+        };
+        assert_eq!(plate.cross_sectional_area(forward), 0.0);
+        assert!((plate.cross_sectional_area(xyz(0.0, 0.0, -1.0)) - 4.0).abs() < 0.0001);
+
+        let pair = ColliderType::COMPOUND {
+            children: vec![
+                ChildCollider {
+                    transform: Transform::IDENTITY,
+                    collider: sphere.clone(),
+                },
+                ChildCollider {
+                    transform: Transform::IDENTITY,
+                    collider: cuboid,
+                },
+            ],
+        };
+        assert!((pair.cross_sectional_area(forward) - (4.0 * pi + 24.0)).abs() < 0.0001);
+    }
+
+    /// Return the velocity of a sphere after falling for a long time, under gravity and the given
+    /// drag, in an arena tall enough that it never lands
+    fn return_terminal_velocity(drag: AerodynamicDrag, radius: f32, mass: f32) -> XYZ {
+        let config = SimConfig {
+            screen_height: 1_000_000.0,
+            ..SimConfig::default()
+        };
+        let mut world = World::new(config).unwrap();
+        world.add_force_generator(Box::new(drag));
+        let mut sphere = return_centered_sphere();
+        sphere.mass = mass;
+        sphere.collider = ColliderType::SPHERE {
+            center: xyz(0.0, 0.0, 0.0),
+            radius,
+        };
+        world.add_object(sphere).unwrap();
+        for _ in 0..2000 {
+            world.step(0.01);
+        }
+        return world.objects[0].velocity;
+    }
+
+    #[test]
+    fn test_drag_gives_terminal_velocity() {
+        let pi = std::f32::consts::PI;
+        let g = SimConfig::default().gravity_ms;
+        let (radius, mass) = (2.0, 1.0);
+        let area = pi * radius * radius;
+
+        // With quadratic drag alone, mg = 0.5 rho Cd A v^2
+        let drag = AerodynamicDrag {
+            fluid_density: 0.01,
+            viscosity: 0.0,
+        };
+        let expected = (2.0 * mass * g / (0.01 * DEFAULT_DRAG_COEFFICIENT * area)).sqrt();
+        let velocity = return_terminal_velocity(drag, radius, mass);
+        assert!(
+            (velocity.y - expected).abs() < 0.001 * expected,
+            "{} vs {}",
+            velocity.y,
+            expected
+        );
+        assert_eq!(velocity.x, 0.0);
+
+        // With linear (Stokes) drag alone, mg = 6 pi mu r v
+        let drag = AerodynamicDrag {
+            fluid_density: 0.0,
+            viscosity: 0.05,
+        };
+        let expected = mass * g / (6.0 * pi * 0.05 * radius);
+        let velocity = return_terminal_velocity(drag, radius, mass);
+        assert!(
+            (velocity.y - expected).abs() < 0.001 * expected,
+            "{} vs {}",
+            velocity.y,
+            expected
+        );
+
+        // With both, the terminal velocity is the positive root of the quadratic
+        // 0.5 rho Cd A v^2 + 6 pi mu r v - mg = 0
+        let drag = AerodynamicDrag {
+            fluid_density: 0.01,
+            viscosity: 0.05,
+        };
+        let a = 0.5 * 0.01 * DEFAULT_DRAG_COEFFICIENT * area;
+        let b = 6.0 * pi * 0.05 * radius;
+        let expected = (-b + (b * b + 4.0 * a * mass * g).sqrt()) / (2.0 * a);
+        let velocity = return_terminal_velocity(drag, radius, mass);
+        assert!(
+            (velocity.y - expected).abs() < 0.001 * expected,
+            "{} vs {}",
+            velocity.y,
+            expected
+        );
+
+        // A heavier sphere of the same size falls faster
+        let drag = AerodynamicDrag {
+            fluid_density: 0.01,
+            viscosity: 0.0,
+        };
+        let heavy = return_terminal_velocity(drag, radius, 4.0 * mass);
+        let light_expected = (2.0 * mass * g / (0.01 * DEFAULT_DRAG_COEFFICIENT * area)).sqrt();
+        assert!((heavy.y - 2.0 * light_expected).abs() < 0.002 * light_expected);
+    }
+
+    #[test]
+    fn test_point_attractor_generator() {
+        let mut near = return_moving_sphere(2.0, 0.0, 1.0, 2.0);
+        near.transform.position.y = 0.0;
+        let far = return_moving_sphere(-4.0, 0.0, 1.0, 2.0);
+        let objects = vec![near, far];
+        let mut attractor = PointAttractor {
+            position: XYZ::default(),
+            strength: 8.0,
+            softening: 0.0,
+        };
+        let forces = generated_forces(&mut attractor, &objects);
+        assert_xyz_close(forces[0], xyz(-4.0, 0.0, 0.0));
+        // Twice as far away, a quarter of the pull
+        assert_xyz_close(forces[1], xyz(1.0, 0.0, 0.0));
+
+        // A negative strength repels
+        attractor.strength = -8.0;
+        let forces = generated_forces(&mut attractor, &objects);
+        assert_xyz_close(forces[0], xyz(4.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn test_spring_generator() {
+        let objects = vec![
+            return_moving_sphere(0.0, 0.0, 1.0, 1.0),
+            return_moving_sphere(3.0, 0.0, 1.0, 1.0),
+        ];
+        let mut spring = Spring {
+            a: 0,
+            b: 1,
+            rest_length: 2.0,
+            stiffness: 4.0,
+            damping: 0.0,
+        };
+        // Stretched by 1, so the ends are pulled together
+        let forces = generated_forces(&mut spring, &objects);
+        assert_xyz_close(forces[0], xyz(4.0, 0.0, 0.0));
+        assert_xyz_close(forces[1], xyz(-4.0, 0.0, 0.0));
+
+        // Springs to objects which don't exist do nothing
+        spring.b = 2;
+        assert_eq!(generated_forces(&mut spring, &objects)[0], XYZ::default());
+
+        // Damping resists the ends moving apart, but not them moving sideways
+        spring.b = 1;
+        spring.damping = 2.0;
+        let mut objects = objects;
+        objects[1].velocity = xyz(1.0, 5.0, 0.0);
+        let forces = generated_forces(&mut spring, &objects);
+        assert_xyz_close(forces[0], xyz(6.0, 0.0, 0.0));
+        assert_xyz_close(forces[1], xyz(-6.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn test_nbody_gravity_between_two_objects() {
+        let mut gravity = NBodyGravity {
+            gravitational_constant: 2.0,
+            softening: 0.0,
+            method: NBodyMethod::Direct,
+        };
+        let mut wall = return_centered_sphere();
+        wall.mass = f32::INFINITY;
+        let objects = vec![
+            return_moving_sphere(0.0, 0.0, 1.0, 3.0),
+            return_moving_sphere(2.0, 0.0, 1.0, 4.0),
+            wall,
+        ];
+        // G m1 m2 / r^2 = 2 * 3 * 4 / 4, equal and opposite. The immovable wall takes no part.
+        let forces = generated_forces(&mut gravity, &objects);
+        assert_xyz_close(forces[0], xyz(6.0, 0.0, 0.0));
+        assert_xyz_close(forces[1], xyz(-6.0, 0.0, 0.0));
+        assert_eq!(forces[2], XYZ::default());
+
+        // Softening weakens the attraction, as if the objects were further apart
+        gravity.softening = 2.0;
+        let forces = generated_forces(&mut gravity, &objects);
+        let expected = 2.0 * 3.0 * 4.0 * 2.0 / 8.0f32.powf(1.5);
+        assert_xyz_close(forces[0], xyz(expected, 0.0, 0.0));
+
+        // Barnes-Hut has nothing to approximate with two objects
+        gravity.method = NBodyMethod::BarnesHut { opening_angle: 0.5 };
+        let barnes_hut = generated_forces(&mut gravity, &objects);
+        assert_xyz_close(barnes_hut[0], forces[0]);
+        assert_xyz_close(barnes_hut[1], forces[1]);
+    }
+
+    #[test]
+    fn test_barnes_hut_matches_direct_sum() {
+        // A lopsided cluster, with a dense core and a sparse halo
+        let mut state = 7;
+        let mut objects = Vec::new();
+        for idx in 0..400 {
+            let spread = if idx % 4 == 0 { 200.0 } else { 20.0 };
+            let mut body = return_centered_sphere();
+            body.transform.position = xyz(
+                spread * next_pseudo_random(&mut state),
+                spread * next_pseudo_random(&mut state),
+                spread * next_pseudo_random(&mut state),
+            );
+            body.mass = 1.0 + next_pseudo_random(&mut state).abs();
+            objects.push(body);
+        }
+        let gravity = |method| NBodyGravity {
+            gravitational_constant: 1.0,
+            softening: 0.1,
+            method,
+        };
+        let direct = generated_forces(&mut gravity(NBodyMethod::Direct), &objects);
+
+        // The relative error of the total force across all objects
+        let error = |opening_angle| {
+            let method = NBodyMethod::BarnesHut { opening_angle };
+            let approximate = generated_forces(&mut gravity(method), &objects);
+            let mut error_squared = 0.0;
+            let mut total_squared = 0.0;
+            for (a, d) in approximate.iter().zip(direct.iter()) {
+                error_squared += (*a - *d).dot(*a - *d);
+                total_squared += d.dot(*d);
+            }
+            return (error_squared / total_squared).sqrt();
+        };
+        // An opening angle of 0 never approximates, so it's the direct sum, up to rounding
+        assert!(error(0.0) < 0.0001);
+        let typical = error(0.5);
+        assert!(typical < 0.01, "{}", typical);
+        // Wider angles trade accuracy for speed
+        let coarse = error(1.2);
+        assert!(coarse > typical);
+        assert!(coarse < 0.1, "{}", coarse);
+    }
+
+    #[test]
+    fn test_nbody_gravity_keeps_binary_in_orbit() {
+        // Two equal masses in a circular orbit about their shared center, each at radius r, need a
+        // speed of sqrt(G m / 4r), and take 2 pi r / v to go around
+        let (g, mass, r): (f32, f32, f32) = (100.0, 2.0, 5.0);
+        let speed = (g * mass / (4.0 * r)).sqrt();
+        let period = 2.0 * std::f32::consts::PI * r / speed;
+        for method in [
+            NBodyMethod::Direct,
+            NBodyMethod::BarnesHut { opening_angle: 0.5 },
+        ] {
+            let mut objects = vec![
+                return_moving_sphere(-r, 0.0, 1.0, mass),
+                return_moving_sphere(r, 0.0, 1.0, mass),
+            ];
+            objects[0].velocity.y = -speed;
+            objects[1].velocity.y = speed;
+            let mut gravity = NBodyGravity {
+                gravitational_constant: g,
+                softening: 0.0,
+                method,
+            };
+            let mut accelerations = |objects: &[Object]| -> Vec<XYZ> {
+                let forces = generated_forces(&mut gravity, objects);
+                return forces.iter().map(|f| f * (1.0 / mass)).collect();
+            };
+            let steps = 1000;
+            for step in 0..steps {
+                IntegratorType::VelocityVerlet.integrator().integrate(
+                    &mut objects,
+                    period / steps as f32,
+                    &mut accelerations,
+                );
+                // The orbit stays circular throughout
+                if step % 100 == 0 {
+                    let separation = objects[1].transform.position - objects[0].transform.position;
+                    assert!((separation.magnitude() - 2.0 * r).abs() < 0.01 * r);
+                }
+            }
+            // After one period, both are back where they started
+            assert!((objects[0].transform.position - xyz(-r, 0.0, 0.0)).magnitude() < 0.05);
+            assert!((objects[1].transform.position - xyz(r, 0.0, 0.0)).magnitude() < 0.05);
+        }
+    }
+
+    /// Return a sphere at the given position along the X axis, with the given charge
+    fn return_charged_sphere(x: f32, charge: f32) -> Object {
+        let mut sphere = return_moving_sphere(x, 0.0, 1.0, 1.0);
+        sphere.charge = charge;
+        return sphere;
+    }
+
+    #[test]
+    fn test_coulomb_force() {
+        let mut coulomb = CoulombForce {
+            coulomb_constant: 2.0,
+            softening: 0.0,
+            cutoff: None,
+        };
+        // k q1 q2 / r^2 = 2 * 3 * 4 / 4. Like charges repel, and uncharged objects feel nothing.
+        let objects = vec![
+            return_charged_sphere(0.0, 3.0),
+            return_charged_sphere(2.0, 4.0),
+            return_charged_sphere(1.0, 0.0),
+        ];
+        let forces = generated_forces(&mut coulomb, &objects);
+        assert_xyz_close(forces[0], xyz(-6.0, 0.0, 0.0));
+        assert_xyz_close(forces[1], xyz(6.0, 0.0, 0.0));
+        assert_eq!(forces[2], XYZ::default());
+
+        // Opposite charges attract
+        let objects = vec![
+            return_charged_sphere(0.0, 3.0),
+            return_charged_sphere(2.0, -4.0),
+        ];
+        let forces = generated_forces(&mut coulomb, &objects);
+        assert_xyz_close(forces[0], xyz(6.0, 0.0, 0.0));
+        assert_xyz_close(forces[1], xyz(-6.0, 0.0, 0.0));
+
+        // Softening weakens the force, and charges beyond the cutoff don't interact at all
+        coulomb.softening = 2.0;
+        let forces = generated_forces(&mut coulomb, &objects);
+        let expected = 2.0 * 3.0 * 4.0 * 2.0 / 8.0f32.powf(1.5);
+        assert_xyz_close(forces[0], xyz(expected, 0.0, 0.0));
+        coulomb.cutoff = Some(1.5);
+        assert_eq!(generated_forces(&mut coulomb, &objects)[0], XYZ::default());
+    }
+
+    #[test]
+    fn test_uniform_electric_field() {
+        let mut field = UniformElectricField {
+            field: xyz(0.0, 3.0, -1.0),
+        };
+        let objects = vec![
+            return_charged_sphere(0.0, 2.0),
+            return_charged_sphere(5.0, -0.5),
+            return_charged_sphere(10.0, 0.0),
+        ];
+        let forces = generated_forces(&mut field, &objects);
+        assert_xyz_close(forces[0], xyz(0.0, 6.0, -2.0));
+        assert_xyz_close(forces[1], xyz(0.0, -1.5, 0.5));
+        assert_eq!(forces[2], XYZ::default());
+    }
+
+    #[test]
+    fn test_like_charges_separate_as_predicted() {
+        // Two equal masses with equal charges, released at rest a distance r0 apart, fly apart. By
+        // conservation of energy, with reduced mass mu = m / 2, they're r = x r0 apart after
+        // t = sqrt(mu r0^3 / 2 k q^2) (sqrt(x (x - 1)) + ln(sqrt(x) + sqrt(x - 1)))
+        let (k, q, mass, r0): (f32, f32, f32, f32) = (1.0, 2.0, 1.0, 2.0);
+        let reduced_mass = 0.5 * mass;
+        let time_to_separate = |x: f32| {
+            let scale = (reduced_mass * r0.powi(3) / (2.0 * k * q * q)).sqrt();
+            return scale * ((x * (x - 1.0)).sqrt() + (x.sqrt() + (x - 1.0).sqrt()).ln());
+        };
+
+        let mut objects = vec![return_charged_sphere(0.0, q), return_charged_sphere(r0, q)];
+        let mut coulomb = CoulombForce {
+            coulomb_constant: k,
+            softening: 0.0,
+            cutoff: None,
+        };
+        let mut accelerations = |objects: &[Object]| -> Vec<XYZ> {
+            let forces = generated_forces(&mut coulomb, objects);
+            return forces.iter().map(|f| f * (1.0 / mass)).collect();
+        };
+        let steps = 1000;
+        let mut time = 0.0;
+        for x in [1.5, 3.0, 10.0] {
+            let end = time_to_separate(x);
+            let dt = end / steps as f32;
+            while time < end - 0.5 * dt {
+                IntegratorType::RK4
+                    .integrator()
+                    .integrate(&mut objects, dt, &mut accelerations);
+                time += dt;
+            }
+            let separation = objects[1].transform.position.x - objects[0].transform.position.x;
+            assert!(
+                (separation - x * r0).abs() < 0.001 * x * r0,
+                "expected {} after {}s, got {}",
+                x * r0,
+                time,
+                separation
+            );
+        }
+        // Momentum is conserved, so they separate symmetrically
+        let midpoint = 0.5 * (objects[0].transform.position.x + objects[1].transform.position.x);
+        assert!((midpoint - 0.5 * r0).abs() < 0.0001);
+    }
+
+    #[test]
+    fn test_magnetic_fields_sum_at_each_position() {
+        let fields: Vec<Box<dyn MagneticField>> = vec![
+            Box::new(UniformMagneticField {
+                field: xyz(0.0, 0.0, 2.0),
+            }),
+            // Strengthens with distance along X, as between two unequal magnets
+            Box::new(|position: XYZ| xyz(0.0, 0.0, 0.5 * position.x)),
+        ];
+        let objects = vec![
+            return_charged_sphere(0.0, 1.0),
+            return_charged_sphere(4.0, 0.0),
+        ];
+        let result = magnetic_fields_at(&fields, &objects);
+        assert_xyz_close(result[0], xyz(0.0, 0.0, 2.0));
+        assert_xyz_close(result[1], xyz(0.0, 0.0, 4.0));
+    }
+
+    /// Gyrates a charged object in a uniform magnetic field with the given integrator, and returns
+    /// the object after each step
+    fn gyrate(
+        integrator_type: IntegratorType,
+        object: Object,
+        field: XYZ,
+        dt: f32,
+        steps: usize,
+    ) -> Vec<Object> {
+        let fields: Vec<Box<dyn MagneticField>> = vec![Box::new(UniformMagneticField { field })];
+        let mut objects = vec![object];
+        let mut history = Vec::new();
+        for _i in 0..steps {
+            integrator_type.integrator().integrate_in_magnetic_field(
+                &mut objects,
+                dt,
+                &mut |objects: &[Object]| vec![XYZ::default(); objects.len()],
+                &mut |objects: &[Object]| magnetic_fields_at(&fields, objects),
+            );
+            history.push(objects[0].clone());
+        }
+        return history;
+    }
+
+    #[test]
+    fn test_boris_matches_cyclotron_radius_and_period() {
+        // A charge q of mass m moving at speed v across a field B circles with radius m v / |q| B,
+        // once every 2 pi m / |q| B
+        let (charge, mass, field, speed): (f32, f32, f32, f32) = (2.0, 1.5, 0.5, 12.0);
+        let radius = mass * speed / (charge * field);
+        let period = 2.0 * std::f32::consts::PI * mass / (charge * field);
+        let dt = 0.01;
+        let steps = 10000;
 
-        assert!(!result.position.y.is_nan());
-        assert!(!result.velocity.y.is_nan());
-        assert!(result.position.y >= 0.0);
-        assert!(result.position.y <= SCREEN_HEIGHT + 0.0001);
-        assert_eq!(result.position.x, 0.5 * SCREEN_WIDTH);
-        assert!(result.velocity.y >= 0.0);
-        assert!(result.velocity.y.abs() > -1.0 * initial_y_velocity.abs());
+        let mut object = return_moving_sphere(0.0, speed, 1.0, mass);
+        object.charge = charge;
+        let history = gyrate(
+            IntegratorType::Boris,
+            object,
+            xyz(0.0, 0.0, field),
+            dt,
+            steps,
+        );
+
+        // The speed never changes, and the orbit stays the same size for every revolution
+        let mut angle = 0.0;
+        let mut previous_velocity = xyz(speed, 0.0, 0.0);
+        for (step, p) in history.iter().enumerate() {
+            assert!((p.velocity.magnitude() - speed).abs() < 0.001 * speed);
+            // A positive charge moving along X in a field along Z is pushed towards -Y, so it
+            // circles a center one radius below where it started
+            let from_center = p.transform.position - xyz(0.0, -radius, 0.0);
+            assert!(
+                (from_center.magnitude() - radius).abs() < 0.01 * radius,
+                "radius {} after {} steps",
+                from_center.magnitude(),
+                step
+            );
+            assert_eq!(p.transform.position.z, 0.0);
+            // Track the total angle the velocity has turned through, which decreases for clockwise
+            // (viewed from +Z) gyration
+            angle += previous_velocity
+                .cross(p.velocity)
+                .z
+                .atan2(previous_velocity.dot(p.velocity));
+            previous_velocity = p.velocity;
+        }
+        let revolutions = -angle / (2.0 * std::f32::consts::PI);
+        let measured_period = dt * steps as f32 / revolutions;
+        assert!(
+            (measured_period - period).abs() < 0.001 * period,
+            "expected period {}, got {}",
+            period,
+            measured_period
+        );
+
+        // An opposite charge gyrates the other way
+        let mut object = return_moving_sphere(0.0, speed, 1.0, mass);
+        object.charge = -charge;
+        let history = gyrate(IntegratorType::Boris, object, xyz(0.0, 0.0, field), dt, 10);
+        assert!(history[9].velocity.y > 0.0);
     }
 
     #[test]
-    fn test_bounce_x_basic() {
-        // Test bounces in both x directions
-        let initial_x_velocity =
-            convert_pixels_to_meters(-0.5 * SCREEN_WIDTH - 1.0, PIXELS_PER_METER);
-        let initial_position_y = 0.5 * SCREEN_WIDTH;
-        let mut sphere_1 = return_centered_sphere();
+    fn test_semi_implicit_euler_spirals_out_of_cyclotron_orbit() {
+        // Without Boris's exact rotation, the Lorentz force is applied along the tangent, so each
+        // step speeds the object up slightly, which Boris avoids
+        let mut object = return_moving_sphere(0.0, 10.0, 1.0, 1.0);
+        object.charge = 1.0;
+        let field = xyz(0.0, 0.0, 1.0);
+        let euler = gyrate(
+            IntegratorType::SemiImplicitEuler,
+            object.clone(),
+            field,
+            0.05,
+            2000,
+        );
+        let boris = gyrate(IntegratorType::Boris, object, field, 0.05, 2000);
+        assert!(euler[1999].velocity.magnitude() > 20.0);
+        assert!((boris[1999].velocity.magnitude() - 10.0).abs() < 0.001);
+    }
 
-        // We want the particle not already colliding on spawn
-        // We want the ball to hit the ground within 1 tick
-        sphere_1.transform.position.y = initial_position_y;
-        sphere_1.velocity.x = initial_x_velocity;
-        let result = particle_sim::calculate_bounce(&sphere_1, 0.9, 1.0).unwrap();
+    #[test]
+    fn test_world_gyrates_charges_in_magnetic_field() {
+        let config = SimConfig {
+            integrator: IntegratorType::Boris,
+            ..SimConfig::default()
+        };
+        let mut world = World::new(config).unwrap();
+        world.force_generators.clear();
+        world.add_magnetic_field(Box::new(UniformMagneticField {
+            field: xyz(0.0, 0.0, 2.0),
+        }));
+        let (speed, omega) = (20.0, 2.0);
+        let mut charged = return_centered_sphere();
+        charged.velocity = xyz(speed, 0.0, 0.0);
+        charged.charge = 1.0;
+        world.add_object(charged).unwrap();
 
-        // There should be no errors or nans with the parameters we're using here
-        assert!(!result.position.x.is_nan());
-        assert!(!result.velocity.x.is_nan());
+        // Over thousands of ticks, the charge keeps its speed and turns at the cyclotron frequency
+        let dt = 1.0 / 60.0;
+        let ticks = 3000;
+        for _i in 0..ticks {
+            world.step(dt);
+        }
+        let t = (dt * ticks as f64) as f32;
+        let expected = xyz(speed * (omega * t).cos(), -speed * (omega * t).sin(), 0.0);
+        let velocity = world.objects[0].velocity;
+        assert!(
+            (velocity - expected).magnitude() < 0.05 * speed,
+            "expected {}, got {}",
+            expected,
+            velocity
+        );
+        assert!((velocity.magnitude() - speed).abs() < 0.001 * speed);
+    }
 
-        // The particle should remain within x bounds
-        assert!(result.position.x >= 0.0);
-        assert!(result.position.x <= SCREEN_WIDTH + 0.1);
+    const ARGON_LIKE: ReducedUnits = ReducedUnits {
+        sigma: 10.0,
+        epsilon: 100.0,
+        mass: 1.0,
+    };
 
-        // The function shouldn't affect the particle's y position
-        assert_eq!(result.position.y, initial_position_y);
+    /// Returns an atom of `ARGON_LIKE` mass at the given position
+    fn return_atom(position: XYZ) -> Object {
+        let mut atom = return_moving_sphere(0.0, 0.0, 0.5 * ARGON_LIKE.sigma, ARGON_LIKE.mass);
+        atom.transform.position = position;
+        return atom;
+    }
 
-        // The particle's x velocity should be reversed, and reduced in magnitude
-        assert!(result.velocity.x >= 0.0);
-        assert!(result.velocity.x.abs() < initial_x_velocity.abs());
+    #[test]
+    fn test_reduced_units() {
+        // The unit of time is sigma * sqrt(m / epsilon)
+        assert_eq!(ARGON_LIKE.length(2.5), 25.0);
+        assert_eq!(ARGON_LIKE.energy(0.5), 50.0);
+        assert_eq!(ARGON_LIKE.time(2.0), 2.0);
+        assert_eq!(ARGON_LIKE.velocity(1.0), 10.0);
+        assert_eq!(ARGON_LIKE.force(1.0), 10.0);
+        assert_eq!(ARGON_LIKE.temperature(0.45), 45.0);
     }
 
     #[test]
-    fn test_bounce_coefficient_effect() {
-        // Test that the bounce coefficient has the expected influence on particle position and velocity post-bounce
-        let initial_x_velocity =
-            convert_pixels_to_meters(0.5 * SCREEN_WIDTH + 1.0, PIXELS_PER_METER);
-        let mut sphere_1 = return_centered_sphere();
-        sphere_1.velocity.x = initial_x_velocity;
-        let bounce_coefficient = 0.5;
-        let result = particle_sim::calculate_bounce(&sphere_1, bounce_coefficient, 1.0).unwrap();
+    fn test_lennard_jones_potential_and_force() {
+        let mut lennard_jones = LennardJones::from_units(&ARGON_LIKE, 2.5, 0.3);
+        let (sigma, epsilon) = (ARGON_LIKE.sigma, ARGON_LIKE.epsilon);
+        let shift = 4.0 * epsilon * (2.5f32.powi(-12) - 2.5f32.powi(-6));
 
-        // Check we haven't errored or returned nan
-        assert!(!result.position.x.is_nan());
-        assert!(!result.velocity.x.is_nan());
+        // Zero at sigma before shifting, deepest at 2^(1/6) sigma, and zero at and beyond the cutoff
+        let minimum = 2.0f32.powf(1.0 / 6.0) * sigma;
+        assert!((lennard_jones.potential(sigma) + shift).abs() < 0.001);
+        assert!((lennard_jones.potential(minimum) - (-epsilon - shift)).abs() < 0.001);
+        assert!(lennard_jones.potential(2.5 * sigma).abs() < 0.001);
+        assert_eq!(lennard_jones.potential(3.0 * sigma), 0.0);
 
-        // Check that we haven't gone off-screen
-        assert!(result.position.x >= 0.0);
-        assert!(result.position.x <= SCREEN_WIDTH + 0.1);
+        // The force is the potential's slope, so it vanishes at the minimum, repels inside it and
+        // attracts outside it
+        let force_at = |lennard_jones: &mut LennardJones, distance: f32| {
+            let objects = vec![
+                return_atom(XYZ::default()),
+                return_atom(xyz(distance, 0.0, 0.0)),
+            ];
+            return generated_forces(lennard_jones, &objects)[1].x;
+        };
+        assert!(force_at(&mut lennard_jones, minimum).abs() < 0.001);
+        for distance in [0.95 * sigma, 1.5 * sigma, 2.2 * sigma] {
+            let h = 0.001 * sigma;
+            let slope = (lennard_jones.potential(distance + h)
+                - lennard_jones.potential(distance - h))
+                / (2.0 * h);
+            let force = force_at(&mut lennard_jones, distance);
+            assert!(
+                (force + slope).abs() < 0.01 * force.abs(),
+                "{} vs {}",
+                force,
+                -slope
+            );
+        }
+        assert!(force_at(&mut lennard_jones, 0.95 * sigma) > 0.0);
+        assert!(force_at(&mut lennard_jones, 1.5 * sigma) < 0.0);
+        assert_eq!(force_at(&mut lennard_jones, 2.6 * sigma), 0.0);
 
-        // Check that velocity has been reversed and reduced
-        assert!(result.velocity.x <= 0.0);
-        println!("{}, {}", result.velocity.x, initial_x_velocity);
-        assert!(result.velocity.x < initial_x_velocity);
+        // Immovable objects take no part
+        let mut wall = return_atom(xyz(1.5 * sigma, 0.0, 0.0));
+        wall.mass = f32::INFINITY;
+        let objects = vec![return_atom(XYZ::default()), wall];
+        assert_eq!(
+            generated_forces(&mut lennard_jones, &objects)[0],
+            XYZ::default()
+        );
+    }
 
-        // Check that velocity is a fraction of its initial value, as specified by the bounce coefficient
-        // This reflects the fact that our bounce coefficient is supposed to be a percentage bounciness, expressed as a value
-        // between [0, 1), where the 0 is inclusive, but the 1 is not.
+    #[test]
+    fn test_neighbor_list_rebuilds_only_when_needed() {
+        let (cutoff, skin) = (25.0, 5.0);
+        let mut objects: Vec<Object> = (0..50)
+            .map(|idx| return_atom(xyz(7.0 * (idx % 10) as f32, 9.0 * (idx / 10) as f32, 0.0)))
+            .collect();
+        let brute_force_pairs = |objects: &[Object], range: f32| {
+            let mut pairs = Vec::new();
+            for i in 0..objects.len() {
+                for j in (i + 1)..objects.len() {
+                    let offset = objects[j].transform.position - objects[i].transform.position;
+                    if offset.magnitude() <= range {
+                        pairs.push((i, j));
+                    }
+                }
+            }
+            return pairs;
+        };
+        let mut neighbor_list = NeighborList::new(skin);
         assert_eq!(
-            result.velocity.x.abs(),
-            initial_x_velocity * bounce_coefficient
+            neighbor_list.pairs(&objects, cutoff).to_vec(),
+            brute_force_pairs(&objects, cutoff + skin)
+        );
+        assert_eq!(neighbor_list.rebuilds(), 1);
+
+        // Moving less than half the skin keeps the list, which still has every pair within the cutoff
+        let mut state = 7;
+        for p in objects.iter_mut() {
+            p.transform.position.x += 0.5 * skin * 0.99 * next_pseudo_random(&mut state).abs();
+        }
+        let pairs = neighbor_list.pairs(&objects, cutoff).to_vec();
+        assert_eq!(neighbor_list.rebuilds(), 1);
+        for pair in brute_force_pairs(&objects, cutoff) {
+            assert!(pairs.contains(&pair));
+        }
+
+        // Moving further, changing the cutoff or adding objects rebuilds it
+        objects[0].transform.position.y += 0.6 * skin;
+        neighbor_list.pairs(&objects, cutoff);
+        assert_eq!(neighbor_list.rebuilds(), 2);
+        neighbor_list.pairs(&objects, 2.0 * cutoff);
+        assert_eq!(neighbor_list.rebuilds(), 3);
+        objects.push(return_atom(XYZ::default()));
+        assert_eq!(
+            neighbor_list.pairs(&objects, 2.0 * cutoff).to_vec(),
+            brute_force_pairs(&objects, 2.0 * cutoff + skin)
+        );
+        assert_eq!(neighbor_list.rebuilds(), 4);
+    }
+
+    /// Returns a 2D Lennard-Jones crystal: a triangular lattice of `side` x `side` atoms spaced at
+    /// the potential's minimum, with random velocities at the given reduced temperature
+    fn return_lennard_jones_crystal(side: usize, temperature: f32) -> Vec<Object> {
+        let spacing = 2.0f32.powf(1.0 / 6.0) * ARGON_LIKE.sigma;
+        let thermal_speed = ARGON_LIKE.velocity(temperature.sqrt());
+        let mut state = 2024;
+        let mut atoms: Vec<Object> = (0..side * side)
+            .map(|idx| {
+                let (column, row) = ((idx % side) as f32, (idx / side) as f32);
+                let mut atom = return_atom(xyz(
+                    spacing * (column + 0.5 * (row % 2.0)),
+                    spacing * row * 0.75f32.sqrt(),
+                    0.0,
+                ));
+                // Uniform in [-sqrt(3), sqrt(3)) has unit variance
+                atom.velocity = xyz(
+                    next_pseudo_random(&mut state) * 3.0f32.sqrt() * thermal_speed,
+                    next_pseudo_random(&mut state) * 3.0f32.sqrt() * thermal_speed,
+                    0.0,
+                );
+                return atom;
+            })
+            .collect();
+        // Stop the crystal drifting as a whole
+        let drift =
+            &atoms.iter().fold(XYZ::default(), |sum, p| sum + p.velocity) / atoms.len() as f32;
+        for atom in atoms.iter_mut() {
+            atom.velocity -= drift;
+        }
+        return atoms;
+    }
+
+    /// Runs a 2D Lennard-Jones crystal for 20 reduced time units, returning the fraction of
+    /// neighboring atoms which were still neighbors at the end, the worst relative drift in total
+    /// energy, and how many times the neighbor list was rebuilt. The crystal as a whole may drift and
+    /// spin, so we look at neighbors rather than the atoms' positions.
+    fn run_lennard_jones_crystal(temperature: f32) -> (f32, f32, usize) {
+        let mut atoms = return_lennard_jones_crystal(8, temperature);
+        let neighbor_distance = 1.5 * ARGON_LIKE.sigma;
+        let neighbors = |atoms: &[Object]| {
+            let mut pairs = Vec::new();
+            for i in 0..atoms.len() {
+                for j in (i + 1)..atoms.len() {
+                    let offset = atoms[j].transform.position - atoms[i].transform.position;
+                    if offset.magnitude() < neighbor_distance {
+                        pairs.push((i, j));
+                    }
+                }
+            }
+            return pairs;
+        };
+        let initial_neighbors = neighbors(&atoms);
+
+        let mut lennard_jones = LennardJones::from_units(&ARGON_LIKE, 2.5, 0.3);
+        let total_energy = |lennard_jones: &mut LennardJones, atoms: &[Object]| {
+            let kinetic: f32 = atoms
+                .iter()
+                .map(|p| 0.5 * p.mass * p.velocity.dot(p.velocity))
+                .sum();
+            return kinetic + lennard_jones.potential_energy(atoms);
+        };
+        let initial_energy = total_energy(&mut lennard_jones, &atoms);
+        let mut worst_drift: f32 = 0.0;
+        let dt = ARGON_LIKE.time(0.005);
+        for step in 0..4000 {
+            IntegratorType::VelocityVerlet.integrator().integrate(
+                &mut atoms,
+                dt,
+                &mut |objects: &[Object]| {
+                    let forces = generated_forces(&mut lennard_jones, objects);
+                    return forces.iter().map(|f| f * (1.0 / ARGON_LIKE.mass)).collect();
+                },
+            );
+            if step % 100 == 0 {
+                let energy = total_energy(&mut lennard_jones, &atoms);
+                worst_drift = worst_drift.max(((energy - initial_energy) / initial_energy).abs());
+            }
+        }
+        assert!(atoms.iter().all(|p| p.transform.position.z == 0.0));
+
+        let final_neighbors = neighbors(&atoms);
+        let kept = initial_neighbors
+            .iter()
+            .filter(|pair| final_neighbors.contains(pair))
+            .count();
+        let kept_fraction = kept as f32 / initial_neighbors.len() as f32;
+        return (
+            kept_fraction,
+            worst_drift,
+            lennard_jones.neighbor_list.rebuilds(),
         );
     }
 
     #[test]
-    fn test_bounce_given_invalid_particle_position() {
-        // If given a particle position that falls outside of the arena, then the function should return
-        // an error, to let the caller handle it
-        let mut sphere_1 = return_centered_sphere();
-        sphere_1.transform.position.x = SCREEN_WIDTH + 1.0;
-        sphere_1.transform.position.y = SCREEN_HEIGHT + 1.0;
-        sphere_1.velocity.y = -5.0;
-        let result = particle_sim::calculate_bounce(&sphere_1, 1.0, 0.99);
-        // TODO: change this to instead be a check for OutOfBoundsError
-        assert!(result.is_err());
+    fn test_lennard_jones_crystal_melts_when_heated() {
+        // Well below the 2D triple point at a reduced temperature of about 0.4, the atoms only rattle
+        // about their lattice sites, whereas well above it, the crystal melts and they wander away
+        // from their neighbors
+        let (cold_kept, cold_drift, cold_rebuilds) = run_lennard_jones_crystal(0.05);
+        let (hot_kept, hot_drift, hot_rebuilds) = run_lennard_jones_crystal(1.5);
+        assert!(
+            cold_kept > 0.99,
+            "cold crystal kept {} of its neighbors",
+            cold_kept
+        );
+        assert!(
+            hot_kept < 0.5,
+            "hot crystal kept {} of its neighbors",
+            hot_kept
+        );
+
+        // Velocity Verlet conserves the energy, and the neighbor list is only rebuilt occasionally,
+        // more often when the atoms move faster
+        assert!(cold_drift < 0.01, "cold energy drift {}", cold_drift);
+        assert!(hot_drift < 0.01, "hot energy drift {}", hot_drift);
+        assert!(cold_rebuilds < hot_rebuilds);
+        assert!(hot_rebuilds < 4000 / 4, "{} rebuilds", hot_rebuilds);
     }
 
     #[test]
-    fn test_bounce_no_infinite_bouncing() {
-        // Check that bounces eventually stop
-        let mut sphere_1 = return_centered_sphere();
-        sphere_1.velocity.y = -1.0;
-        let result = particle_sim::calculate_bounce(&sphere_1, 0.99, 200.0).unwrap();
-        assert_eq!(result.velocity.y.abs().floor(), 0.0);
+    fn test_rng_is_seeded_and_normal() {
+        let mut a = Rng::new(42);
+        let mut b = Rng::new(42);
+        let mut c = Rng::new(43);
+        let from_a: Vec<u64> = (0..10).map(|_| a.next_u64()).collect();
+        let from_b: Vec<u64> = (0..10).map(|_| b.next_u64()).collect();
+        let from_c: Vec<u64> = (0..10).map(|_| c.next_u64()).collect();
+        assert_eq!(from_a, from_b);
+        assert_ne!(from_a, from_c);
 
-        // The bounced object should remain within bounds, on its bouncing axis
-        assert!(result.position.y >= 0.0);
-        assert!(result.position.y <= SCREEN_HEIGHT);
+        let count = 100000;
+        let mut rng = Rng::new(7);
+        let uniform: Vec<f32> = (0..count).map(|_| rng.uniform()).collect();
+        assert!(uniform.iter().all(|u| (0.0..1.0).contains(u)));
+        assert!((uniform.iter().sum::<f32>() / count as f32 - 0.5).abs() < 0.01);
+
+        let normal: Vec<f32> = (0..count).map(|_| rng.normal()).collect();
+        let mean = normal.iter().sum::<f32>() / count as f32;
+        let variance = normal.iter().map(|x| (x - mean) * (x - mean)).sum::<f32>() / count as f32;
+        assert!(mean.abs() < 0.02, "mean {}", mean);
+        assert!((variance - 1.0).abs() < 0.02, "variance {}", variance);
+    }
+
+    /// Returns `count` free objects of the given mass with random velocities in X and Y, and an
+    /// immovable wall, which thermostats should leave alone
+    fn return_thermal_gas(count: usize, mass: f32) -> Vec<Object> {
+        let mut state = 99;
+        let mut objects: Vec<Object> = (0..count)
+            .map(|_| {
+                let mut p = return_moving_sphere(0.0, 0.0, 1.0, mass);
+                p.velocity = xyz(
+                    10.0 * next_pseudo_random(&mut state),
+                    10.0 * next_pseudo_random(&mut state),
+                    0.0,
+                );
+                return p;
+            })
+            .collect();
+        let mut wall = return_moving_sphere(0.0, 3.0, 1.0, f32::INFINITY);
+        wall.velocity.y = 4.0;
+        objects.push(wall);
+        return objects;
     }
 
     #[test]
-    fn test_bounce_terminates_with_object_remaining_in_bounds() {
-        // TODO: rename function
-        // Check that a bounced object remains above the arena floor
-        let mut sphere_1 = return_centered_sphere();
-        sphere_1.velocity.y = -1.0;
-        let result = particle_sim::calculate_bounce(&sphere_1, 0.01, 200.0).unwrap();
-        assert_eq!(result.velocity.y.abs().floor(), 0.0);
-        assert!(result.position.y >= 0.0);
+    fn test_kinetic_temperature() {
+        // Each direction holds half of the temperature: T = sum(m v^2) / (dimensions * count)
+        let mut objects = vec![
+            return_moving_sphere(0.0, 3.0, 1.0, 2.0),
+            return_moving_sphere(0.0, 0.0, 1.0, 1.0),
+        ];
+        objects[1].velocity = xyz(0.0, 4.0, 5.0);
+        assert!((kinetic_temperature(&objects, 2) - (18.0 + 16.0) / 4.0).abs() < 0.0001);
+        assert!((kinetic_temperature(&objects, 3) - (18.0 + 41.0) / 6.0).abs() < 0.0001);
+
+        // Immovable objects don't count, since they have no kinetic energy to share
+        let mut wall = return_moving_sphere(0.0, 100.0, 1.0, f32::INFINITY);
+        wall.velocity.y = 100.0;
+        objects.push(wall.clone());
+        assert!((kinetic_temperature(&objects, 2) - 8.5).abs() < 0.0001);
+        assert_eq!(kinetic_temperature(&[wall], 2), 0.0);
+        assert_eq!(kinetic_temperature(&[], 2), 0.0);
     }
 
     #[test]
-    fn test_bounce_at_arena_edge() {
-        // Check that bouncing at the very edge of the arena does not bounce it out of bounds
-        let mut sphere_1 = return_centered_sphere();
+    fn test_velocity_rescaling_reaches_target_at_once() {
+        let mut objects = return_thermal_gas(100, 2.0);
+        let mut thermostat = VelocityRescaling {
+            target_temperature: 5.0,
+            dimensions: 2,
+        };
+        thermostat.apply(&mut objects, 0.01, &mut Rng::new(0));
+        assert!((kinetic_temperature(&objects, 2) - 5.0).abs() < 0.001);
+        assert_eq!(objects[100].velocity, xyz(3.0, 4.0, 0.0));
 
-        // We want the particle ever so slightly in bounds
+        // With no motion, there's nothing to rescale
+        let mut still = vec![return_moving_sphere(0.0, 0.0, 1.0, 1.0)];
+        thermostat.apply(&mut still, 0.01, &mut Rng::new(0));
+        assert_eq!(still[0].velocity, XYZ::default());
+    }
 
-        // ColliderType is an enum, and Rust enums don't allow direct field access like structs do
-        // so we can't do sphere_1.collider.radius = 1.0;
-        if let ColliderType::SPHERE { ref mut radius, .. } = sphere_1.collider {
-            *radius = 1.0;
+    #[test]
+    fn test_berendsen_relaxes_exponentially() {
+        let mut objects = return_thermal_gas(100, 2.0);
+        let initial = kinetic_temperature(&objects, 2);
+        let target = 3.0 * initial;
+        let (dt, time_constant) = (0.01, 0.5);
+        let mut thermostat = Berendsen {
+            target_temperature: target,
+            time_constant,
+            dimensions: 2,
+        };
+        // Each tick closes dt / tau of the gap to the target, so after time t, the gap has shrunk to
+        // roughly exp(-t / tau) of what it was
+        thermostat.apply(&mut objects, dt, &mut Rng::new(0));
+        let expected = initial + (dt / time_constant) * (target - initial);
+        assert!((kinetic_temperature(&objects, 2) - expected).abs() < 0.001 * expected);
+        for _i in 1..50 {
+            thermostat.apply(&mut objects, dt, &mut Rng::new(0));
         }
-        println!("{}", sphere_1.collider);
-        sphere_1.transform.position.x = 1.0001;
-        sphere_1.transform.position.y = 1.0001;
-        sphere_1.transform.position.z = 1.0001;
+        let gap = target - kinetic_temperature(&objects, 2);
+        let expected_gap = (target - initial) * (1.0f32 - dt / time_constant).powi(50);
+        assert!((gap - expected_gap).abs() < 0.001 * target, "gap {}", gap);
+        assert!((expected_gap / (target - initial) - (-1.0f32).exp()).abs() < 0.01);
+        assert_eq!(objects[100].velocity, xyz(3.0, 4.0, 0.0));
+    }
 
-        // We want a negligible velocity, which is significant enough to affect results (in
-        // this case greater than the distance to the arena edge), but tiny enough that a lazy
-        // programmer may be tempted not to calculate it
-        sphere_1.velocity.y = -0.0005;
+    #[test]
+    fn test_langevin_equilibrates_to_target() {
+        let target = 8.0;
+        let dt = 0.01;
+        let mut objects = return_thermal_gas(500, 2.0);
+        let mut thermostat = Langevin::new(target, 5.0, 2);
+        let mut rng = Rng::new(1234);
+        // Starting from rest, the kicks heat the objects up to the target, around which the temperature
+        // then fluctuates
+        for p in objects.iter_mut().take(500) {
+            p.velocity = XYZ::default();
+        }
+        for _i in 0..500 {
+            thermostat.apply(&mut objects, dt, &mut rng);
+        }
+        let mut mean_temperature = 0.0;
+        let samples = 1000;
+        for _i in 0..samples {
+            thermostat.apply(&mut objects, dt, &mut rng);
+            mean_temperature += kinetic_temperature(&objects, 2) / samples as f32;
+        }
+        assert!(
+            (mean_temperature - target).abs() < 0.03 * target,
+            "mean temperature {}",
+            mean_temperature
+        );
+        // Objects aren't kicked out of the plane, and the wall isn't kicked at all
+        assert!(objects.iter().all(|p| p.velocity.z == 0.0));
+        assert_eq!(objects[500].velocity, xyz(3.0, 4.0, 0.0));
 
-        // TODO: re-enable this, once we have new collision logic implemented. At the moment,
-        // our employed collision logic is using a hard-coded particle radius value
-        // Check that the resulting bounce does not move the particle out of bounds
-        // let result = particle_sim::calculate_bounce(&sphere_1, 0.9, 1.0).unwrap();
-        // println!("Resulting Y position {}", result.position.y);
-        // assert!(result.position.y >= PARTICLE_RADIUS_PX);
+        // The same seed gives the same kicks
+        let run = |seed: u64| {
+            let mut objects = return_thermal_gas(10, 1.0);
+            let mut thermostat = Langevin::new(target, 5.0, 2);
+            let mut rng = Rng::new(seed);
+            for _i in 0..10 {
+                thermostat.apply(&mut objects, dt, &mut rng);
+            }
+            return objects.iter().map(|p| p.velocity).collect::<Vec<XYZ>>();
+        };
+        assert_eq!(run(1), run(1));
+        assert_ne!(run(1), run(2));
+    }
 
-        // Check the same for the X axis and in the opposite direction
-        let initial_position_x = PARTICLE_RADIUS_PX + 0.0001;
-        let mut sphere_2 = return_centered_sphere();
-        sphere_2.transform.position.x = initial_position_x;
-        sphere_2.velocity.x = -0.0005;
-        if let ColliderType::SPHERE { ref mut radius, .. } = sphere_1.collider {
-            *radius = 1.0;
+    #[test]
+    fn test_world_applies_thermostat_after_integration() {
+        let mut world = World::new(SimConfig::default()).unwrap();
+        for idx in 0..10 {
+            let mut p = return_centered_sphere();
+            p.transform.position.x += 30.0 * idx as f32 - 150.0;
+            p.velocity = xyz(idx as f32, 1.0, 0.0);
+            world.add_object(p).unwrap();
         }
-        let result = particle_sim::calculate_bounce(&sphere_2, 0.9, 1.0).unwrap();
-        println!("Resulting X position {}", result.position.x);
-        assert!(result.position.x >= PARTICLE_RADIUS_PX);
+        world.set_thermostat(Box::new(VelocityRescaling {
+            target_temperature: 50.0,
+            dimensions: 2,
+        }));
+        // Gravity speeds the objects up during integration, but the thermostat runs afterwards
+        world.step(0.01);
+        assert!((kinetic_temperature(&world.objects, 2) - 50.0).abs() < 0.01);
     }
 
     #[test]
-    fn test_bounce_extreme_velocity_particle_remains_within_bounds() {
-        // TODO: add test to check the particle's final velocity after bouncing
-        // Check that high velocities update a particle's position and also don't push it out of bounds
-        let mut sphere_1 = return_centered_sphere();
-        sphere_1.velocity.x = 5000.0;
-        sphere_1.velocity.y = -5001.0;
+    fn test_world_langevin_thermostat_uses_config_seed() {
+        let run = |seed: u64| {
+            let config = SimConfig {
+                seed,
+                ..SimConfig::default()
+            };
+            let mut world = World::new(config).unwrap();
+            world.force_generators.clear();
+            world.add_object(return_centered_sphere()).unwrap();
+            world.set_thermostat(Box::new(Langevin::new(5.0, 5.0, 2)));
+            for _i in 0..10 {
+                world.step(0.01);
+            }
+            return world.objects[0].velocity;
+        };
+        assert_eq!(run(1), run(1));
+        assert_ne!(run(1), run(2));
+    }
 
-        let result = particle_sim::calculate_bounce(&sphere_1, 0.20, 1.0).unwrap();
-        assert!(result.position.x >= 0.0);
-        assert!(result.position.x <= SCREEN_WIDTH + 0.1);
-        assert!(result.position.y >= 0.0);
-        assert!(result.position.y <= SCREEN_HEIGHT + 0.1);
+    #[test]
+    fn test_brownian_kicks_are_seeded_and_fixed_within_a_tick() {
+        let (temperature, damping, dt) = (4.0, 2.0, 0.01);
+        let mut objects = return_thermal_gas(2000, 1.0);
+        for p in objects.iter_mut().take(2000) {
+            p.velocity = XYZ::default();
+        }
+        let mut brownian = BrownianForce::new(temperature, damping, 2);
+        let mut rng = Rng::new(5);
+        brownian.start_tick(&objects, dt, &mut rng);
 
-        assert!(result.velocity.y.abs() <= 5001.0);
-        assert!(result.velocity.x.abs() <= 5000.0);
+        // However many times the integrator asks, the kicks stay the same until the next tick
+        let kicks = generated_forces(&mut brownian, &objects);
+        assert_eq!(kicks, generated_forces(&mut brownian, &objects));
+        assert!(kicks.iter().all(|kick| kick.z == 0.0));
+        // The wall is immovable, so it's neither kicked nor dragged
+        assert_eq!(kicks[2000], XYZ::default());
+
+        // Each direction's kick has a variance of 2 gamma kT / dt
+        let variance = kicks[..2000].iter().map(|k| k.dot(*k)).sum::<f32>() / (2.0 * 2000.0);
+        let expected = 2.0 * damping * temperature / dt;
+        assert!(
+            (variance - expected).abs() < 0.05 * expected,
+            "variance {}",
+            variance
+        );
+
+        // The next tick draws new kicks, and the same seed draws the same ones
+        brownian.start_tick(&objects, dt, &mut rng);
+        assert_ne!(kicks, generated_forces(&mut brownian, &objects));
+        brownian.start_tick(&objects, dt, &mut Rng::new(5));
+        assert_eq!(kicks, generated_forces(&mut brownian, &objects));
+
+        // Moving objects are also dragged against their velocity
+        objects[0].velocity = xyz(3.0, 0.0, 0.0);
+        let dragged = generated_forces(&mut brownian, &objects);
+        assert_xyz_close(dragged[0], kicks[0] - xyz(damping * 3.0, 0.0, 0.0));
     }
 
-    /*
-    Integration tests below
-    */
+    #[test]
+    fn test_mean_squared_displacement_analysis() {
+        // Move one object so that its squared displacement grows as 4 D t + 1, i.e. diffusion in 2D
+        // with an offset, as after an initial ballistic phase
+        let diffusion = 1.5;
+        let mut objects = vec![return_atom(XYZ::default())];
+        let mut wall = return_atom(XYZ::default());
+        wall.mass = f32::INFINITY;
+        objects.push(wall);
+        let mut msd = MeanSquaredDisplacement::new(&objects, 2);
+        assert_eq!(msd.diffusion_coefficient(), None);
+        for t in [1.0, 2.0, 4.0, 8.0] {
+            objects[0].transform.position = xyz(0.0, (4.0 * diffusion * t + 1.0f32).sqrt(), 0.0);
+            // The wall doesn't count, even if it's moved
+            objects[1].transform.position.x += 100.0;
+            let recorded = msd.record(t, &objects);
+            assert!((recorded - (4.0 * diffusion * t + 1.0)).abs() < 0.001);
+        }
+        assert_eq!(msd.samples().len(), 4);
+        assert!((msd.diffusion_coefficient().unwrap() - diffusion).abs() < 0.001);
+        assert_eq!(einstein_diffusion_coefficient(6.0, 4.0), 1.5);
+    }
 
     #[test]
-    fn test_simulation_tick_basic() {
-        // Test that several simulation ticks do not move a particle out of bounds
-        let ticks = 10;
-        let seconds_elapsed = 1.0;
+    fn test_brownian_diffusion_matches_einstein_relation() {
+        // Free objects kicked by a solvent diffuse with D = kT / gamma, however heavy they are, once
+        // they've forgotten their initial velocities after a few m / gamma
+        let (temperature, damping, mass, dt) = (10.0, 5.0, 1.0, 0.01);
+        let mut objects: Vec<Object> = (0..1000)
+            .map(|_| return_moving_sphere(0.0, 0.0, 1.0, mass))
+            .collect();
+        let mut brownian = BrownianForce::new(temperature, damping, 2);
+        let mut rng = Rng::new(2024);
+        let mut tick = |objects: &mut Vec<Object>, brownian: &mut BrownianForce| {
+            brownian.start_tick(objects, dt, &mut rng);
+            IntegratorType::SemiImplicitEuler.integrator().integrate(
+                objects,
+                dt,
+                &mut |objects: &[Object]| {
+                    let forces = generated_forces(brownian, objects);
+                    return forces.iter().map(|f| f * (1.0 / mass)).collect();
+                },
+            );
+        };
+        // Skip the ballistic phase, where objects still move in straight lines
+        for _i in 0..100 {
+            tick(&mut objects, &mut brownian);
+        }
+        let mut msd = MeanSquaredDisplacement::new(&objects, 2);
+        for step in 1..=2000 {
+            tick(&mut objects, &mut brownian);
+            if step % 50 == 0 {
+                msd.record(step as f32 * dt, &objects);
+            }
+        }
+        let expected = einstein_diffusion_coefficient(temperature, damping);
+        let measured = msd.diffusion_coefficient().unwrap();
+        assert!(
+            (measured - expected).abs() < 0.1 * expected,
+            "expected D = {}, got {}",
+            expected,
+            measured
+        );
+        // The objects also settle at the solvent's temperature
+        let temperature_error = (kinetic_temperature(&objects, 2) - temperature).abs();
+        assert!(temperature_error < 0.1 * temperature);
+        assert!(objects.iter().all(|p| p.transform.position.z == 0.0));
+    }
 
-        let mut sphere_1 = return_centered_sphere();
-        sphere_1.velocity.y = 50.0;
+    #[test]
+    fn test_world_brownian_runs_are_reproducible() {
+        let run = |seed: u64| {
+            let config = SimConfig {
+                seed,
+                ..SimConfig::default()
+            };
+            let mut world = World::new(config).unwrap();
+            world.force_generators.clear();
+            world.add_force_generator(Box::new(BrownianForce::new(10.0, 5.0, 2)));
+            for idx in 0..5 {
+                let mut p = return_centered_sphere();
+                p.transform.position.x += 30.0 * idx as f32;
+                world.add_object(p).unwrap();
+            }
+            for _i in 0..20 {
+                world.step(0.01);
+            }
+            return world
+                .objects
+                .iter()
+                .map(|p| p.velocity)
+                .collect::<Vec<XYZ>>();
+        };
+        let first = run(1);
+        assert!(first.iter().all(|v| *v != XYZ::default()));
+        assert_eq!(first, run(1));
+        assert_ne!(first, run(2));
+    }
 
-        let mut sphere_2 = return_centered_sphere();
-        sphere_2.transform.position.x = 0.25 * SCREEN_WIDTH;
-        sphere_2.transform.position.y = 0.25 * SCREEN_HEIGHT;
-        sphere_2.velocity.y = -50.0;
+    #[test]
+    fn test_closure_force_generator() {
+        let config = SimConfig::default();
+        let mut world = World::new(config.clone()).unwrap();
+        world.force_generators.clear();
+        // e.g. a wind that only blows on objects left of the middle of the screen
+        let middle = 0.5 * config.screen_width;
+        world.add_force_generator(Box::new(move |objects: &[Object], forces: &mut [XYZ]| {
+            for (p, force) in objects.iter().zip(forces.iter_mut()) {
+                if p.transform.position.x < middle {
+                    force.x += 10.0;
+                }
+            }
+        }));
+        let mut left = return_centered_sphere();
+        left.transform.position.x -= 100.0;
+        let mut right = return_centered_sphere();
+        right.transform.position.x += 100.0;
+        world.add_object(left).unwrap();
+        world.add_object(right).unwrap();
+        world.step(0.1);
+        assert!((world.objects[0].velocity.x - 1.0).abs() < 0.0001);
+        assert_eq!(world.objects[1].velocity.x, 0.0);
+        // Like the caller's force, generated forces are cleared after each step
+        assert_eq!(world.objects[0].force, XYZ::default());
+    }
 
-        let mut particles: Vec<Object> = vec![sphere_1, sphere_2];
+    #[test]
+    fn test_world_step_matches_fresh_worlds() {
+        // A world should be a thin owner of the simulation state, so stepping it repeatedly should
+        // give the same results as stepping the same objects in a new world each time
+        let config = SimConfig::default();
+        let mut world = World::new(config.clone()).unwrap();
+        world.add_object(return_centered_sphere()).unwrap();
+        let mut particles: Vec<Object> = vec![return_centered_sphere()];
 
-        // Check that the resulting bounce does not move the particle out of bounds
-        for _i in 0..ticks {
-            particle_sim::simulation_tick(&mut particles, seconds_elapsed);
+        for _i in 0..10 {
+            world.step(0.1);
+            step_objects(&mut particles, &config, 0.1);
         }
-        let result_1 = particles.get(0).unwrap();
-        let result_2 = particles.get(0).unwrap();
 
-        // Check we haven't errored or returned nan
-        assert!(!result_1.transform.position.x.is_nan());
-        assert!(!result_1.velocity.x.is_nan());
-        assert!(!result_2.transform.position.x.is_nan());
-        assert!(!result_2.velocity.x.is_nan());
+        assert!((world.time() - 1.0).abs() < 0.000001);
+        assert_eq!(world.objects[0].transform, particles[0].transform);
+        assert_eq!(world.objects[0].velocity, particles[0].velocity);
+    }
 
-        assert!(!result_1.transform.position.y.is_nan());
-        assert!(!result_1.velocity.y.is_nan());
-        assert!(!result_2.transform.position.y.is_nan());
-        assert!(!result_2.velocity.y.is_nan());
+    #[test]
+    fn test_world_step_rests_objects_on_floor_by_collider() {
+        // Objects rest on the floor where their colliders touch it, whatever the config's particle
+        // radius, including colliders offset from the object's position
+        let config = SimConfig::default();
+        for (radius, offset) in [(50.0, 0.0), (3.0, 0.0), (20.0, 5.0)] {
+            let mut world = World::new(config.clone()).unwrap();
+            let mut sphere = return_centered_sphere();
+            sphere.collider = ColliderType::SPHERE {
+                center: xyz(0.0, offset, 0.0),
+                radius,
+            };
+            // Dead enough that its bounces soon die out, rather than hopping on for a long time
+            sphere.material.restitution = 0.0;
+            world.add_object(sphere).unwrap();
+            for _i in 0..600 {
+                world.step(1.0 / 60.0);
+            }
+            let resting = &world.objects[0];
+            let expected = config.screen_height - offset - radius;
+            assert!(
+                (resting.transform.position.y - expected).abs() < 0.0001,
+                "radius {}: rests at {}",
+                radius,
+                resting.transform.position.y
+            );
+            assert_eq!(resting.velocity.y, 0.0);
+        }
+    }
 
-        // Check that we haven't gone off-screen
-        assert!(result_1.transform.position.x >= 0.0);
-        assert!(result_1.transform.position.x <= SCREEN_WIDTH + 0.1);
-        assert!(result_2.transform.position.x >= 0.0);
-        assert!(result_2.transform.position.x <= SCREEN_WIDTH + 0.1);
+    #[test]
+    fn test_world_records_arena_resets() {
+        let config = SimConfig::default();
+        let mut world = World::new(config.clone()).unwrap();
+        world.add_object(return_centered_sphere()).unwrap();
+        world.step(0.1);
+        assert_eq!(world.arena_resets(), 0);
 
-        assert!(result_1.transform.position.y >= 0.0);
-        assert!(result_1.transform.position.y <= SCREEN_HEIGHT + 0.1);
-        assert!(result_2.transform.position.y >= 0.0);
-        assert!(result_2.transform.position.y <= SCREEN_HEIGHT + 0.1);
+        // An object too big for the arena can't bounce within it, so it's reset to the center
+        let mut giant = return_centered_sphere();
+        giant.collider = ColliderType::SPHERE {
+            center: xyz(0.0, 0.0, 0.0),
+            radius: config.screen_width,
+        };
+        world.objects.push(giant);
+        world.step(0.1);
+        world.step(0.1);
+        assert_eq!(world.arena_resets(), 2);
+        assert_eq!(world.objects[1].velocity, XYZ::default());
+    }
+
+    #[test]
+    fn test_world_step_collides_particles() {
+        // Two particles moving towards each other should bounce off each other, not pass through.
+        // They're slow enough not to pass through each other within a single tick.
+        let config = SimConfig {
+            gravity_ms: 0.0,
+            ..SimConfig::default()
+        };
+        let radius = config.particle_radius_px;
+        let mut sphere_1 = return_moving_sphere(0.5 * config.screen_width - 15.0, 2.0, radius, 1.0);
+        let mut sphere_2 =
+            return_moving_sphere(0.5 * config.screen_width + 15.0, -2.0, radius, 1.0);
+        sphere_1.transform.position.y = 0.5 * config.screen_height;
+        sphere_2.transform.position.y = 0.5 * config.screen_height;
+        let mut particles = vec![sphere_1, sphere_2];
+
+        for _i in 0..10 {
+            step_objects(&mut particles, &config, 0.1);
+            assert!(particles[0].transform.position.x < particles[1].transform.position.x);
+        }
+        assert!(particles[0].velocity.x < 0.0);
+        assert!(particles[1].velocity.x > 0.0);
     }
 
     #[test]
-    fn test_simulation_tick_is_deterministic() {
+    fn test_world_step_is_deterministic() {
         // Check that running our simulation twice with the same parameters gives the same results each time
+        let config = SimConfig::default();
         let ticks = 10;
         let seconds_elapsed = 1.0;
 
         let mut particles_1: Vec<Object> = vec![return_centered_sphere()];
         let mut particles_2: Vec<Object> = vec![return_centered_sphere()];
         for _i in 0..ticks {
-            particle_sim::simulation_tick(&mut particles_1, seconds_elapsed);
-            particle_sim::simulation_tick(&mut particles_2, seconds_elapsed);
+            step_objects(&mut particles_1, &config, seconds_elapsed);
+            step_objects(&mut particles_2, &config, seconds_elapsed);
         }
-        let result_1 = particles_1.get(0).unwrap();
-        let result_2 = particles_2.get(0).unwrap();
+        let result_1 = particles_1.first().unwrap();
+        let result_2 = particles_2.first().unwrap();
 
         assert_eq!(result_1.velocity.x, result_2.velocity.x);
         assert_eq!(result_1.velocity.y, result_2.velocity.y);
@@ -786,42 +4872,114 @@ mod tests {
     }
 
     #[test]
-    fn test_simulation_tick_frequency_does_not_affect_results() {
-        // Check that simulation produces the same results regardless of tick frequency over an identical timespan
+    fn test_world_step_frequency_does_not_affect_results() {
+        // Check that simulation produces the same results regardless of frame rate over an identical timespan.
+        // Note that stepping a world directly with different time steps still gives different results,
+        // because e.g. bounce travel distance isn't linear in the time step. A FixedTimestep avoids that by
+        // always running the same steps for the same total time.
+        let config = SimConfig {
+            fixed_timestep_hz: 10.0,
+            max_substeps_per_frame: 20,
+            ..SimConfig::default()
+        };
         let seconds_elapsed = 1.0;
 
-        let mut particles_1: Vec<Object> = vec![return_centered_sphere()];
-        let mut particles_2: Vec<Object> = vec![return_centered_sphere()];
+        let mut world_1 = World::new(config.clone()).unwrap();
+        let mut world_2 = World::new(config.clone()).unwrap();
+        world_1.add_object(return_centered_sphere()).unwrap();
+        world_2.add_object(return_centered_sphere()).unwrap();
+        let mut timestep_1 = FixedTimestep::new(&config);
+        let mut timestep_2 = FixedTimestep::new(&config);
+
+        timestep_1.advance(&mut world_1, seconds_elapsed);
+        timestep_2.advance(&mut world_2, seconds_elapsed / 2.0);
+        timestep_2.advance(&mut world_2, seconds_elapsed / 2.0);
+
+        let result_1 = &world_1.objects[0];
+        let result_2 = &world_2.objects[0];
+        assert_eq!(result_1.transform.position, result_2.transform.position);
+        assert_eq!(result_1.velocity, result_2.velocity);
+
+        // Repeat the test, but with extra frames, to test for potentially compounding divergence
+        let extra_frames = 9;
+        for _i in 0..extra_frames {
+            timestep_1.advance(&mut world_1, seconds_elapsed);
+
+            // Uneven frame times, which don't line up with the step size
+            timestep_2.advance(&mut world_2, seconds_elapsed * 0.33);
+            timestep_2.advance(&mut world_2, seconds_elapsed * 0.67);
+        }
+        let result_1 = &world_1.objects[0];
+        let result_2 = &world_2.objects[0];
+        assert_eq!(result_1.transform.position, result_2.transform.position);
+        assert_eq!(result_1.velocity, result_2.velocity);
+    }
+
+    #[test]
+    fn test_fixed_timestep_carries_over_partial_steps() {
+        let config = SimConfig {
+            fixed_timestep_hz: 10.0,
+            ..SimConfig::default()
+        };
+        let mut world = World::new(config.clone()).unwrap();
+        world.add_object(return_centered_sphere()).unwrap();
+        let mut timestep = FixedTimestep::new(&config);
+
+        // Less than one step shouldn't run the simulation, only report how far into the step we are
+        let alpha = timestep.advance(&mut world, 0.025);
+        assert_eq!(world.time(), 0.0);
+        assert!((alpha - 0.25).abs() < 0.0001);
+
+        // The leftover time should count towards the next step
+        let alpha = timestep.advance(&mut world, 0.1);
+        assert!((world.time() - 0.1).abs() < 0.000001);
+        assert!((alpha - 0.25).abs() < 0.0001);
+    }
+
+    #[test]
+    fn test_fixed_timestep_limits_substeps() {
+        // A very slow frame shouldn't run an unbounded number of steps
+        let config = SimConfig {
+            fixed_timestep_hz: 10.0,
+            max_substeps_per_frame: 3,
+            ..SimConfig::default()
+        };
+        let mut world = World::new(config.clone()).unwrap();
+        world.add_object(return_centered_sphere()).unwrap();
+        let mut timestep = FixedTimestep::new(&config);
+
+        let alpha = timestep.advance(&mut world, 10.05);
+        assert!((world.time() - 0.3).abs() < 0.000001);
+        // The dropped time shouldn't be simulated on later frames either
+        assert!((0.0..1.0).contains(&alpha));
+        timestep.advance(&mut world, 0.0);
+        assert!((world.time() - 0.3).abs() < 0.000001);
+    }
+
+    #[test]
+    fn test_interpolated_transform_blends_previous_and_current_step() {
+        let config = SimConfig::default();
+        let mut world = World::new(config).unwrap();
+        world.add_object(return_centered_sphere()).unwrap();
+
+        // Before any step, there's nothing to interpolate from
+        let initial_position = world.objects[0].transform.position;
+        let result = world.interpolated_transform(0, 0.5).unwrap();
+        assert_eq!(result.position, initial_position);
+
+        world.step(0.1);
+        let current_position = world.objects[0].transform.position;
+        assert_ne!(initial_position, current_position);
+
+        let result = world.interpolated_transform(0, 0.0).unwrap();
+        assert_eq!(result.position, initial_position);
+        let result = world.interpolated_transform(0, 1.0).unwrap();
+        assert_eq!(result.position, current_position);
+        let result = world.interpolated_transform(0, 0.5).unwrap();
+        let expected_y = 0.5 * (initial_position.y + current_position.y);
+        assert!((result.position.y - expected_y).abs() < 0.0001);
 
-        particle_sim::simulation_tick(&mut particles_1, seconds_elapsed);
-        let result_1 = particles_1.get(0).unwrap();
-
-        particle_sim::simulation_tick(&mut particles_2, seconds_elapsed / 2.0);
-        particle_sim::simulation_tick(&mut particles_2, seconds_elapsed / 2.0);
-        let result_2 = particles_2.get(0).unwrap();
-
-        // TODO: determine why the X pos and velocities agree with one another, but the Y velocity only doesn't?
-        // TODO: re-enable
-        // It's because I've got my units mixed up. For the bounce travel distance, I use the particle's current velocity,
-        // then multiply it by the time elapsed (roughly). That means that if I halve the time examined, I incorrectly
-        // quarter the total travel distance
-        // assert_eq!(result_1.transform.position.x, result_2.transform.position.x);
-        // assert_eq!(result_1.transform.position.y, result_2.transform.position.y);
-        // assert_eq!(result_1.velocity.y, result_2.velocity.y);
-        // assert_eq!(result_1.velocity.x, result_2.velocity.x);
-
-        // Repeat the test, but with extra ticks, to test for potentially compounding divergence
-        // let extra_ticks = 9;
-        // for _i in 0..extra_ticks {
-        //     particle_sim::simulation_tick(&mut particles_1, seconds_elapsed);
-        //
-        //     particle_sim::simulation_tick(&mut particles_2, seconds_elapsed / 2.0);
-        //     particle_sim::simulation_tick(&mut particles_2, seconds_elapsed / 2.0);
-        // }
-        // assert_eq!(result_1.position.x, result_2.position.x);
-        // assert_eq!(result_1.position.y, result_2.position.y);
-        // assert_eq!(result_1.velocity.x, result_2.velocity.x);
-        // assert_eq!(result_1.velocity.y, result_2.velocity.y);
+        assert!(world.interpolated_transform(1, 0.5).is_none());
     }
 }
 